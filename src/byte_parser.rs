@@ -0,0 +1,442 @@
+use std::collections::HashSet;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Read};
+use std::mem;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+
+use memchr::memchr;
+
+use crate::errors::RfastaError;
+use crate::io::{insert_unique_header, FastaRecord};
+
+/// A borrowed view of a FASTA record, valid until the parser reads the next one.
+///
+/// The header is the text after `>` with surrounding whitespace removed. The sequence is every
+/// sequence line of the record joined, trimmed, and ASCII-uppercased, exactly as in
+/// [`FastaRecord::sequence`], but left as bytes: it is not checked for UTF-8 until
+/// [`RecordRef::to_record`] makes an owned copy.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct RecordRef<'a> {
+    /// FASTA header text without the leading `>`.
+    pub header: &'a str,
+    /// Uppercased sequence bytes associated with the header.
+    pub sequence: &'a [u8],
+}
+
+impl RecordRef<'_> {
+    /// Copies the view into an owned [`FastaRecord`]. Fails when the sequence is not UTF-8.
+    pub fn to_record(&self) -> Result<FastaRecord, RfastaError> {
+        let sequence = std::str::from_utf8(self.sequence).map_err(|_| {
+            RfastaError::invalid_record(
+                format!("sequence for `{}` is not valid UTF-8", self.header),
+                "FASTA sequences should contain only ASCII residue letters.",
+            )
+        })?;
+        Ok(FastaRecord::new(self.header, sequence))
+    }
+}
+
+/// How [`RecordParser::read_line`] classified a line. Sequence lines are already appended to the
+/// record and header text is already in `pending_header`.
+enum Line {
+    Blank,
+    Header,
+    InvalidHeader,
+    Sequence,
+}
+
+/// Pull parser over a byte stream that reuses its line, header, and sequence buffers.
+///
+/// Lines are split with `memchr` directly in the reader's buffer and only copied when they cross a
+/// buffer boundary, so each residue is copied once, into the sequence buffer. Records are those
+/// whose header lines start within `range`; a range starting past zero must begin at a line start,
+/// and lines before its first header belong to the previous range and are skipped.
+pub(crate) struct RecordParser<R> {
+    reader: R,
+    source_name: Option<PathBuf>,
+    range: Range<u64>,
+    offset: u64,
+    /// Lines read so far, counted from the start of the range.
+    line_number: usize,
+    /// Where `line_number` counts from, so errors can name lines of the whole file.
+    line_origin: LineOrigin,
+    partial_line: Vec<u8>,
+    header: String,
+    header_line: usize,
+    sequence: Vec<u8>,
+    /// Header already read for the next record, with its line number.
+    pending_header: String,
+    pending_line: Option<usize>,
+    seen_headers: Option<HashSet<String>>,
+    records: usize,
+    finished: bool,
+}
+
+impl<R: BufRead> RecordParser<R> {
+    pub(crate) fn new(
+        reader: R,
+        expect_unique_header: bool,
+        source_name: Option<&Path>,
+        range: Range<u64>,
+    ) -> Self {
+        Self {
+            reader,
+            source_name: source_name.map(Path::to_path_buf),
+            offset: range.start,
+            line_origin: LineOrigin {
+                file_offset: range.start,
+                lines_before: 0,
+            },
+            range,
+            line_number: 0,
+            partial_line: Vec::new(),
+            header: String::new(),
+            header_line: 0,
+            sequence: Vec::new(),
+            pending_header: String::new(),
+            pending_line: None,
+            seen_headers: expect_unique_header.then(HashSet::new),
+            records: 0,
+            finished: false,
+        }
+    }
+
+    /// Places the input within a larger file: it starts `lines_before` lines after the line
+    /// starting at byte `file_offset` of the source file.
+    pub(crate) fn with_line_origin(mut self, file_offset: u64, lines_before: usize) -> Self {
+        self.line_origin = LineOrigin {
+            file_offset,
+            lines_before,
+        };
+        self
+    }
+
+    /// Converts a line number counted from the start of the input to one counted from the start
+    /// of the source file.
+    ///
+    /// Lines before `file_offset` are only counted here, when an error needs them, so reading a
+    /// part never reads the file before it. If the file cannot be reread, the number stays
+    /// relative to the part.
+    fn file_line(&self, line_number: usize) -> usize {
+        let LineOrigin {
+            file_offset,
+            lines_before,
+        } = self.line_origin;
+        let skipped = match self.source_name.as_deref() {
+            Some(path) if file_offset > 0 => count_lines_before(path, file_offset).unwrap_or(0),
+            _ => 0,
+        };
+        skipped + lines_before + line_number
+    }
+
+    /// Number of records returned so far.
+    pub(crate) fn records(&self) -> usize {
+        self.records
+    }
+
+    /// Returns a view of the next record, reusing the parser's buffers.
+    pub(crate) fn next_ref(&mut self) -> Result<Option<RecordRef<'_>>, RfastaError> {
+        if !self.advance()? {
+            return Ok(None);
+        }
+        Ok(Some(RecordRef {
+            header: &self.header,
+            sequence: &self.sequence,
+        }))
+    }
+
+    /// Returns the next record as an owned [`FastaRecord`], moving the buffers out instead of
+    /// copying them.
+    pub(crate) fn next_record(&mut self) -> Result<Option<FastaRecord>, RfastaError> {
+        if !self.advance()? {
+            return Ok(None);
+        }
+        let header = mem::take(&mut self.header);
+        let capacity = self.sequence.len();
+        let sequence = mem::replace(&mut self.sequence, Vec::with_capacity(capacity));
+        match String::from_utf8(sequence) {
+            Ok(sequence) => Ok(Some(FastaRecord { header, sequence })),
+            Err(_) => Err(RfastaError::malformed_fasta(
+                self.source_name.as_deref(),
+                self.file_line(self.header_line),
+                format!("sequence for `{header}` is not valid UTF-8"),
+                "FASTA sequences should contain only ASCII residue letters.",
+            )),
+        }
+    }
+
+    /// Reads the next record into `header` and `sequence`. Returns `false` at the end of input
+    /// or of the range.
+    fn advance(&mut self) -> Result<bool, RfastaError> {
+        if self.finished {
+            return Ok(false);
+        }
+        self.sequence.clear();
+        if self.pending_line.is_none() {
+            loop {
+                match self.read_line(false)? {
+                    None => {
+                        self.finished = true;
+                        return Ok(false);
+                    }
+                    Some((Line::Header, line_start)) => {
+                        if line_start >= self.range.end {
+                            self.finished = true;
+                            return Ok(false);
+                        }
+                        self.pending_line = Some(self.line_number);
+                        break;
+                    }
+                    Some((Line::Sequence, _)) if self.range.start == 0 => {
+                        return Err(RfastaError::malformed_fasta(
+                            self.source_name.as_deref(),
+                            self.file_line(self.line_number),
+                            "found sequence data before the first FASTA header",
+                            "Make sure the file starts with a header line beginning with `>`.",
+                        ));
+                    }
+                    Some(_) => {}
+                }
+            }
+        }
+        mem::swap(&mut self.header, &mut self.pending_header);
+        self.header_line = self
+            .pending_line
+            .take()
+            .expect("a header is pending at this point");
+
+        loop {
+            self.read_buffered_sequence_lines()?;
+            match self.read_line(true)? {
+                None => {
+                    self.finished = true;
+                    break;
+                }
+                Some((Line::Header, line_start)) => {
+                    if line_start >= self.range.end {
+                        self.finished = true;
+                    } else {
+                        self.pending_line = Some(self.line_number);
+                    }
+                    break;
+                }
+                Some(_) => {}
+            }
+        }
+
+        if self.sequence.is_empty() {
+            return Err(RfastaError::empty_sequence(
+                self.source_name.as_deref(),
+                self.header.clone(),
+                "Ensure each FASTA header is followed by at least one sequence line.",
+            ));
+        }
+        if let Some(seen_headers) = self.seen_headers.as_mut() {
+            insert_unique_header(seen_headers, &self.header, self.source_name.as_deref())?;
+        }
+        self.sequence.make_ascii_uppercase();
+        self.records += 1;
+        Ok(true)
+    }
+
+    /// Appends the complete sequence lines at the front of the reader's buffer, stopping at a
+    /// header or at a line the buffer does not hold in full, which [`Self::read_line`] handles.
+    ///
+    /// This is the hot loop for most inputs, so it scans the buffer in place rather than going
+    /// through [`next_line`] once per line.
+    fn read_buffered_sequence_lines(&mut self) -> Result<(), RfastaError> {
+        let buffer = self.reader.fill_buf().map_err(|source| {
+            RfastaError::io(
+                "parse",
+                self.source_name.as_deref(),
+                source,
+                "Check that the input file exists and is readable.",
+            )
+        })?;
+        let mut consumed = 0;
+        while let Some(end) = memchr(b'\n', &buffer[consumed..]) {
+            let line = &buffer[consumed..=consumed + end];
+            let text = trim_line(line);
+            if text.first() == Some(&b'>') {
+                break;
+            }
+            self.sequence.extend_from_slice(text);
+            self.line_number += 1;
+            self.offset += line.len() as u64;
+            consumed += line.len();
+        }
+        self.reader.consume(consumed);
+        Ok(())
+    }
+
+    /// Reads and classifies one line, returning it with its starting offset. Sequence lines are
+    /// appended to the record only when `in_record` is set.
+    fn read_line(&mut self, in_record: bool) -> Result<Option<(Line, u64)>, RfastaError> {
+        let sequence = &mut self.sequence;
+        let pending_header = &mut self.pending_header;
+        let line = next_line(&mut self.reader, &mut self.partial_line, |line| {
+            let text = trim_line(line);
+            let kind = match text.split_first() {
+                None => Line::Blank,
+                Some((b'>', header)) => match std::str::from_utf8(header) {
+                    Ok(header) => {
+                        pending_header.clear();
+                        pending_header.push_str(header);
+                        Line::Header
+                    }
+                    Err(_) => Line::InvalidHeader,
+                },
+                Some(_) => {
+                    if in_record {
+                        sequence.extend_from_slice(text);
+                    }
+                    Line::Sequence
+                }
+            };
+            (kind, line.len())
+        })
+        .map_err(|source| {
+            RfastaError::io(
+                "parse",
+                self.source_name.as_deref(),
+                source,
+                "Check that the input file exists and is readable.",
+            )
+        })?;
+
+        let Some((kind, len)) = line else {
+            return Ok(None);
+        };
+        self.line_number += 1;
+        let line_start = self.offset;
+        self.offset += len as u64;
+        if let Line::InvalidHeader = kind {
+            return Err(RfastaError::malformed_fasta(
+                self.source_name.as_deref(),
+                self.file_line(self.line_number),
+                "header is not valid UTF-8",
+                "Re-encode the file as UTF-8, or fix the header line.",
+            ));
+        }
+        Ok(Some((kind, line_start)))
+    }
+}
+
+/// Position of a parser's input within its source file; see [`RecordParser::with_line_origin`].
+#[derive(Clone, Copy)]
+struct LineOrigin {
+    file_offset: u64,
+    lines_before: usize,
+}
+
+/// Counts the line breaks in the first `offset` bytes of `path`.
+fn count_lines_before(path: &Path, offset: u64) -> io::Result<usize> {
+    let mut reader = BufReader::new(File::open(path)?.take(offset));
+    let mut lines = 0;
+    loop {
+        let buffer = reader.fill_buf()?;
+        if buffer.is_empty() {
+            return Ok(lines);
+        }
+        lines += memchr::memchr_iter(b'\n', buffer).count();
+        let consumed = buffer.len();
+        reader.consume(consumed);
+    }
+}
+
+/// Calls `visit` with the next line, including its `\n`. The line is borrowed from the reader's
+/// buffer unless it crosses a buffer boundary, in which case it is gathered in `partial`.
+fn next_line<R: BufRead, T>(
+    reader: &mut R,
+    partial: &mut Vec<u8>,
+    visit: impl FnOnce(&[u8]) -> T,
+) -> io::Result<Option<T>> {
+    partial.clear();
+    loop {
+        let buffer = match reader.fill_buf() {
+            Ok(buffer) => buffer,
+            Err(error) if error.kind() == io::ErrorKind::Interrupted => continue,
+            Err(error) => return Err(error),
+        };
+        if buffer.is_empty() {
+            return Ok((!partial.is_empty()).then(|| visit(partial)));
+        }
+        if let Some(end) = memchr(b'\n', buffer) {
+            let result = if partial.is_empty() {
+                visit(&buffer[..=end])
+            } else {
+                partial.extend_from_slice(&buffer[..=end]);
+                visit(partial)
+            };
+            reader.consume(end + 1);
+            return Ok(Some(result));
+        }
+        partial.extend_from_slice(buffer);
+        let consumed = buffer.len();
+        reader.consume(consumed);
+    }
+}
+
+/// Trims whitespace the way `str::trim` does, without decoding lines that start and end in ASCII.
+pub(crate) fn trim_line(line: &[u8]) -> &[u8] {
+    // `str::trim` also removes the vertical tab, which `u8::is_ascii_whitespace` keeps.
+    let is_space = |byte: &u8| matches!(byte, b' ' | b'\t' | b'\n' | b'\r' | 0x0b | 0x0c);
+    let start = line
+        .iter()
+        .position(|byte| !is_space(byte))
+        .unwrap_or(line.len());
+    let end = line
+        .iter()
+        .rposition(|byte| !is_space(byte))
+        .map_or(start, |last| last + 1);
+    let trimmed = &line[start..end];
+    // Unicode whitespace such as U+00A0 can only hide in non-ASCII bytes at either end.
+    let non_ascii_end = |byte: Option<&u8>| byte.is_some_and(|byte| !byte.is_ascii());
+    if non_ascii_end(trimmed.first()) || non_ascii_end(trimmed.last()) {
+        if let Ok(text) = std::str::from_utf8(trimmed) {
+            return text.trim().as_bytes();
+        }
+    }
+    trimmed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lines_are_trimmed_like_str_trim() {
+        for line in [
+            "  ACGT \r\n",
+            "\x0bACGT\x0c",
+            "\u{a0}ACGT\u{2003}\n",
+            "A\u{a0}C",
+            " \t\r\n",
+            "",
+            ">seq 1\u{3000}",
+        ] {
+            assert_eq!(
+                trim_line(line.as_bytes()),
+                line.trim().as_bytes(),
+                "{line:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn lines_crossing_buffer_boundaries_are_gathered() {
+        let input = b">first record\nacgt\nAC\n\n>second\r\nMK\nLV";
+        let reader = io::BufReader::with_capacity(3, &input[..]);
+        let mut parser = RecordParser::new(reader, true, None, 0..u64::MAX);
+        let first = parser.next_ref().unwrap().unwrap();
+        assert_eq!(
+            (first.header, first.sequence),
+            ("first record", &b"ACGTAC"[..])
+        );
+        let second = parser.next_record().unwrap().unwrap();
+        assert_eq!(second, FastaRecord::new("second", "MKLV"));
+        assert!(parser.next_ref().unwrap().is_none());
+        assert_eq!(parser.records(), 2);
+    }
+}
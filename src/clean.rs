@@ -2,8 +2,20 @@
 //!
 //! This module contains the user-facing cleaning API: duplicate handling, invalid-sequence
 //! behavior, and record filtering. It intentionally exposes the policy types and the top-level
-//! cleaning entry point, while lower-level residue conversion helpers remain internal.
+//! cleaning entry points, while lower-level residue conversion helpers remain internal.
+//!
+//! [`clean_sequences`] works on records already in memory. The streaming entry points
+//! ([`visit_cleaned_fasta_reader`], [`visit_cleaned_fasta_file`], and [`clean_fasta_file`]) apply
+//! the same policies one record at a time for inputs too large to hold in memory. Every entry point
+//! except [`clean_sequences`] returns a [`CleanReport`] with per-stage counts.
+//!
+//! Residue conversion uses the standard map unless a correction dictionary or ordered
+//! [`CorrectionRules`] are supplied through [`CleanOptions`].
 
+pub use crate::corrections::{CorrectionRule, CorrectionRules, PatternKind};
+pub use crate::rejection::RejectedOutput;
 pub use crate::sequence_processing::{
-    clean_sequences, CleanOptions, DuplicateAction, InvalidSequenceAction,
+    clean_fasta_file, clean_sequences, clean_sequences_with_report, visit_cleaned_fasta_file,
+    visit_cleaned_fasta_reader, CleanOptions, CleanReport, DuplicateAction, InvalidSequenceAction,
 };
+pub use crate::utilities::read_correction_file;
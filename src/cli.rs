@@ -1,15 +1,38 @@
-use std::path::PathBuf;
+use std::fmt;
+use std::io::Write;
+use std::path::{Path, PathBuf};
 
-use clap::{Parser, Subcommand};
+use clap::{Parser, Subcommand, ValueEnum};
 
-use crate::configs::DEFAULT_LINE_LENGTH;
+use crate::compression::{decompressed_reader, Compression};
+use crate::configs::{
+    DEFAULT_LINE_LENGTH, DEFAULT_MAX_KMER_FREQUENCY, DEFAULT_MAX_OPEN_FILES,
+    DEFAULT_SIMILARITY_KMER_SIZE,
+};
 use crate::errors::RfastaError;
+use crate::explode::{
+    explode_fasta_file, explode_fasta_reader, gather_fasta_files, list_fasta_files, ExplodeOptions,
+    RecordName,
+};
+use crate::fai::{index_fasta_file, FastaIndex, IndexedFasta};
+use crate::grouping::GroupKey;
 use crate::io::{
-    parse_fasta_file, split_fasta_file_round_robin, write_fasta_file, ParseOptions, WriteOptions,
+    parse_fasta_file, parse_fasta_reader, write_fasta_file, write_fasta_writer, FastaRecord,
+    FastaWriter, FilePart, ParseOptions, WriteOptions,
 };
+use crate::manifest::verify_shards;
+use crate::partition::{Partition, PartitionAssignment, SimilarityGrouping};
+use crate::rejection::RejectedOutput;
 use crate::sequence_processing::{
-    clean_sequences, CleanOptions, DuplicateAction, InvalidSequenceAction,
+    clean_sequences_with_report, visit_cleaned_fasta_file, visit_cleaned_fasta_reader,
+    CleanOptions, CleanReport, DuplicateAction, InvalidSequenceAction,
 };
+use crate::sharding::{
+    split_fasta_file, split_fasta_reader, ShardBalance, ShardKey, ShardLimits, ShardStrategy,
+    SplitOptions,
+};
+use crate::unsplit::{unsplit_fasta_files, unsplit_shard_dir};
+use crate::utilities::{overlay_standard_conversions, read_correction_file};
 
 #[derive(Parser)]
 #[command(
@@ -17,7 +40,7 @@ use crate::sequence_processing::{
     version,
     about = "rfasta parses, cleans, writes, and shards protein FASTA files.",
     long_about = "rfasta is a production-ready FASTA toolkit for protein datasets. Use `clean` to standardize and validate records, and `split` to create shard files for parallel downstream processing.",
-    after_help = "Examples:\n  rfasta clean proteins.fasta -o cleaned.fasta --duplicate-record remove --invalid-sequence convert-remove\n  rfasta split proteins.fasta --output-dir shards --chunks 8"
+    after_help = "Examples:\n  rfasta clean proteins.fasta -o cleaned.fasta --duplicate-record remove --invalid-sequence convert-remove\n  zcat proteins.fasta.gz | rfasta clean - -o - --silent | gzip > cleaned.fasta.gz\n  rfasta split proteins.fasta --output-dir shards --chunks 8"
 )]
 struct Args {
     #[command(subcommand)]
@@ -28,10 +51,10 @@ struct Args {
 enum Commands {
     /// Parse and clean a FASTA file.
     Clean {
-        /// Input FASTA file.
+        /// Input FASTA file, or `-` for standard input.
         filename: PathBuf,
 
-        /// Output FASTA file.
+        /// Output FASTA file, or `-` for standard output.
         #[arg(short = 'o')]
         output: Option<PathBuf>,
 
@@ -39,6 +62,12 @@ enum Commands {
         #[arg(long)]
         non_unique_header: bool,
 
+        /// Read only part I of N equal byte ranges of the input, numbered from 0, e.g. `3/16`.
+        /// Every record falls in exactly one part, so job-array worker I can clean its share of
+        /// an uncompressed file without splitting it first.
+        #[arg(long, value_name = "I/N", value_parser = parse_file_part)]
+        part: Option<FilePart>,
+
         /// How to deal with exact duplicate FASTA records.
         #[arg(long, value_enum, default_value_t = DuplicateAction::Fail)]
         duplicate_record: DuplicateAction,
@@ -51,6 +80,18 @@ enum Commands {
         #[arg(long, value_enum, default_value_t = InvalidSequenceAction::Fail)]
         invalid_sequence: InvalidSequenceAction,
 
+        /// Treat `-` as a valid residue for aligned sequences.
+        #[arg(long)]
+        alignment: bool,
+
+        /// Residue corrections used by the convert modes: a JSON object or TSV of `from<TAB>to` pairs.
+        #[arg(long)]
+        correction_file: Option<PathBuf>,
+
+        /// Apply --correction-file on top of the standard conversions instead of replacing them.
+        #[arg(long, requires = "correction_file")]
+        overlay_standard: bool,
+
         /// Number of residues per line in the output FASTA.
         #[arg(long)]
         number_lines: Option<usize>,
@@ -67,6 +108,10 @@ enum Commands {
         #[arg(long)]
         random_subsample: Option<usize>,
 
+        /// Seed for --random-subsample; the same seed and input give the same subsample.
+        #[arg(long)]
+        seed: Option<u64>,
+
         /// Print summary statistics for the cleaned output.
         #[arg(long)]
         print_statistics: bool,
@@ -82,10 +127,38 @@ enum Commands {
         /// Replace commas in FASTA headers with semicolons.
         #[arg(long)]
         remove_comma_from_header: bool,
+
+        /// Output compression. Inferred from the `-o` extension (.gz, .bgz, .zst) when omitted.
+        #[arg(long, value_enum)]
+        compress: Option<Compression>,
+
+        /// Compression level for gzip/bgzf (0-9) or zstd (1-22).
+        #[arg(long)]
+        compression_level: Option<i32>,
+
+        /// Write a JSON report of per-stage cleaning counts to this file, or `-` for standard output.
+        #[arg(long)]
+        report: Option<PathBuf>,
+
+        /// Write every dropped record to this FASTA file, with the reason appended to its header.
+        /// Must be a file path; `-` is not accepted. With duplicate-sequence removal, the header of
+        /// every kept sequence is held in memory to name the kept duplicate.
+        #[arg(long)]
+        rejected: Option<PathBuf>,
+
+        /// Write one TSV row per dropped record with its stage, offending residue, and kept duplicate.
+        /// Must be a file path; `-` is not accepted. With duplicate-sequence removal, the header of
+        /// every kept sequence is held in memory to name the kept duplicate.
+        #[arg(long)]
+        rejected_tsv: Option<PathBuf>,
+
+        /// Threads for parsing and cleaning; 0 uses every core. Output is identical for any count.
+        #[arg(long, default_value_t = 1)]
+        threads: usize,
     },
     /// Split a FASTA file into shard files.
     Split {
-        /// Input FASTA file.
+        /// Input FASTA file, or `-` for standard input.
         filename: PathBuf,
 
         /// Output directory. Files are created on demand.
@@ -93,13 +166,66 @@ enum Commands {
         output_dir: PathBuf,
 
         /// Number of requested shard buckets.
-        #[arg(short, long)]
-        chunks: usize,
+        #[arg(
+            short,
+            long,
+            required_unless_present_any = ["max_records", "max_bytes", "by_key"],
+            conflicts_with_all = ["max_records", "max_bytes", "by_key"]
+        )]
+        chunks: Option<usize>,
+
+        /// Start a new shard after this many records instead of using a fixed shard count.
+        #[arg(long)]
+        max_records: Option<usize>,
+
+        /// Start a new shard before it would exceed this size (uncompressed). Accepts a plain byte
+        /// count or a K, M, or G suffix (powers of 1024), e.g. `512M`.
+        #[arg(long, value_parser = parse_byte_size)]
+        max_bytes: Option<u64>,
+
+        /// What to even out across shards when placing each record: record count (round-robin),
+        /// residue count, or output bytes before compression.
+        #[arg(
+            long,
+            value_enum,
+            default_value_t = ShardBalance::Records,
+            conflicts_with_all = ["max_records", "max_bytes"]
+        )]
+        balance: ShardBalance,
+
+        /// Place each record by a stable hash of its header, accession, or sequence instead, so
+        /// the same record lands in the same shard across runs and releases.
+        #[arg(long, value_enum, conflicts_with = "balance", requires = "chunks")]
+        by: Option<SplitBy>,
+
+        /// Write one file per group instead of a fixed number of shards. The group is a UniProt
+        /// header field (`field:OX`), the first capture of a header regex (`regex:GN=(\S+)`), or a
+        /// sequence length bucket (`length:100`).
+        #[arg(
+            long,
+            value_parser = parse_group_key,
+            conflicts_with_all = ["max_records", "max_bytes", "balance"]
+        )]
+        by_key: Option<GroupKey>,
 
         /// Line length to use in shard output.
         #[arg(long, default_value_t = DEFAULT_LINE_LENGTH)]
         line_length: usize,
 
+        /// Compression applied to each shard file.
+        #[arg(long, value_enum, default_value_t = Compression::None)]
+        compress: Compression,
+
+        /// Compression level for gzip/bgzf (0-9) or zstd (1-22).
+        #[arg(long)]
+        compression_level: Option<i32>,
+
+        /// Maximum shard files held open at once. Shards beyond the limit are closed and reopened
+        /// in append mode as needed; compressed shards then hold several members that decode to
+        /// the same records.
+        #[arg(long, default_value_t = DEFAULT_MAX_OPEN_FILES)]
+        max_open_files: usize,
+
         /// Skip writing output files.
         #[arg(long)]
         no_outputfiles: bool,
@@ -108,6 +234,467 @@ enum Commands {
         #[arg(long)]
         silent: bool,
     },
+    /// Split a FASTA file into named partitions such as train, validation, and test sets.
+    Partition {
+        /// Input FASTA file, or `-` for standard input.
+        filename: PathBuf,
+
+        /// Output directory for `{stem}_{name}.fasta` files and manifest.json.
+        #[arg(short = 'o', long)]
+        output_dir: PathBuf,
+
+        /// Comma-separated share of records per partition, e.g. `0.8,0.1,0.1`. Ratios are
+        /// normalized by their sum.
+        #[arg(long, value_delimiter = ',', required = true)]
+        ratios: Vec<f64>,
+
+        /// Comma-separated partition names, one per ratio. Defaults to `train,test` for two ratios
+        /// and `train,val,test` for three.
+        #[arg(long, value_delimiter = ',')]
+        names: Option<Vec<String>>,
+
+        /// Assign records with a seeded random draw, reproducible for the same input order.
+        #[arg(long, required_unless_present = "by", conflicts_with = "by")]
+        seed: Option<u64>,
+
+        /// Assign records by a stable hash of their header, accession, or sequence instead, so
+        /// assignments do not depend on input order.
+        #[arg(long, value_enum)]
+        by: Option<SplitBy>,
+
+        /// Keep sequences in one partition when their k-mer similarity reaches this value (0-1),
+        /// in addition to identical sequences. Loads the whole input into memory, and takes time
+        /// proportional to records × k-mers per record × --max-kmer-frequency.
+        #[arg(long, conflicts_with = "by")]
+        min_similarity: Option<f64>,
+
+        /// K-mer length used by --min-similarity.
+        #[arg(long, default_value_t = DEFAULT_SIMILARITY_KMER_SIZE, requires = "min_similarity")]
+        kmer_size: usize,
+
+        /// Ignore a k-mer once this many sequences contain it. Lower values are faster on large
+        /// inputs but undercount similarity through very common k-mers.
+        #[arg(long, default_value_t = DEFAULT_MAX_KMER_FREQUENCY, requires = "min_similarity")]
+        max_kmer_frequency: usize,
+
+        /// Line length to use in partition output.
+        #[arg(long, default_value_t = DEFAULT_LINE_LENGTH)]
+        line_length: usize,
+
+        /// Compression applied to each partition file.
+        #[arg(long, value_enum, default_value_t = Compression::None)]
+        compress: Compression,
+
+        /// Compression level for gzip/bgzf (0-9) or zstd (1-22).
+        #[arg(long)]
+        compression_level: Option<i32>,
+
+        /// Suppress informational output.
+        #[arg(long)]
+        silent: bool,
+    },
+    /// Write every record to its own single-sequence FASTA file.
+    Explode {
+        /// Input FASTA file, or `-` for standard input.
+        filename: PathBuf,
+
+        /// Output directory for the per-record files.
+        #[arg(short = 'o', long)]
+        output_dir: PathBuf,
+
+        /// Name each file from the record's accession or full header.
+        #[arg(long, value_enum, default_value_t = RecordName::Accession)]
+        name_by: RecordName,
+
+        /// Spread files over nested numbered subdirectories so no directory holds more than this
+        /// many entries. Requires a file input, which is read twice.
+        #[arg(long)]
+        max_per_dir: Option<usize>,
+
+        /// Line length to use in the output files.
+        #[arg(long, default_value_t = DEFAULT_LINE_LENGTH)]
+        line_length: usize,
+
+        /// Compression applied to each output file.
+        #[arg(long, value_enum, default_value_t = Compression::None)]
+        compress: Compression,
+
+        /// Compression level for gzip/bgzf (0-9) or zstd (1-22).
+        #[arg(long)]
+        compression_level: Option<i32>,
+
+        /// Suppress informational output.
+        #[arg(long)]
+        silent: bool,
+    },
+    /// Combine every FASTA file under a directory into one file, in path order.
+    Gather {
+        /// Directory searched recursively for .fasta, .fa, .faa, .fas, and .fna files, optionally
+        /// compressed.
+        dir: PathBuf,
+
+        /// Output FASTA file, or `-` for standard output.
+        #[arg(short = 'o')]
+        output: PathBuf,
+
+        /// Line length to use in the output.
+        #[arg(long, default_value_t = DEFAULT_LINE_LENGTH)]
+        line_length: usize,
+
+        /// Output compression. Inferred from the output extension when omitted.
+        #[arg(long, value_enum)]
+        compress: Option<Compression>,
+
+        /// Compression level for gzip/bgzf (0-9) or zstd (1-22).
+        #[arg(long)]
+        compression_level: Option<i32>,
+
+        /// Suppress informational output.
+        #[arg(long)]
+        silent: bool,
+    },
+    /// Interleave round-robin shards back into the original record order.
+    Unsplit {
+        /// A shard directory containing manifest.json, or the shard files in shard-number order.
+        #[arg(required = true)]
+        shards: Vec<PathBuf>,
+
+        /// Output FASTA file, or `-` for standard output.
+        #[arg(short = 'o')]
+        output: PathBuf,
+
+        /// Line length to use in the output.
+        #[arg(long, default_value_t = DEFAULT_LINE_LENGTH)]
+        line_length: usize,
+
+        /// Output compression. Inferred from the output extension when omitted.
+        #[arg(long, value_enum)]
+        compress: Option<Compression>,
+
+        /// Compression level for gzip/bgzf (0-9) or zstd (1-22).
+        #[arg(long)]
+        compression_level: Option<i32>,
+
+        /// Suppress informational output.
+        #[arg(long)]
+        silent: bool,
+    },
+    /// Check a shard directory against the manifest.json written by `split`.
+    VerifyShards {
+        /// Directory containing manifest.json and the shard files.
+        dir: PathBuf,
+
+        /// Suppress informational output.
+        #[arg(long)]
+        silent: bool,
+    },
+    /// Build a samtools-compatible .fai index for a FASTA file.
+    Faidx {
+        /// Input FASTA file. Must be uncompressed with uniform line widths per record.
+        filename: PathBuf,
+
+        /// Index output path, or `-` for standard output. Defaults to the input path with `.fai`
+        /// appended.
+        #[arg(short = 'o', long)]
+        output: Option<PathBuf>,
+
+        /// Suppress informational output.
+        #[arg(long)]
+        silent: bool,
+    },
+    /// Fetch records or residue ranges from an indexed FASTA file.
+    Fetch {
+        /// Input FASTA file. Uses `<filename>.fai` when present, otherwise indexes in memory.
+        filename: PathBuf,
+
+        /// Regions to fetch: `name` or `name:start-end` (1-based, inclusive). An end past the
+        /// sequence is clamped to its length.
+        #[arg(required = true)]
+        regions: Vec<String>,
+
+        /// Output FASTA file, or `-` for standard output. Defaults to standard output.
+        #[arg(short = 'o')]
+        output: Option<PathBuf>,
+
+        /// Line length to use in the fetched output.
+        #[arg(long, default_value_t = DEFAULT_LINE_LENGTH)]
+        line_length: usize,
+    },
+}
+
+/// Hash partitioning keys accepted by `rfasta split --by`.
+#[derive(Clone, Copy, Debug, ValueEnum)]
+enum SplitBy {
+    /// Hash the full header.
+    #[value(name = "hash-header")]
+    Header,
+    /// Hash the accession (the UniProt accession, or the first header word).
+    #[value(name = "hash-accession")]
+    Accession,
+    /// Hash the sequence, keeping identical sequences in one shard.
+    #[value(name = "hash-sequence")]
+    Sequence,
+}
+
+impl SplitBy {
+    fn key(self) -> ShardKey {
+        match self {
+            Self::Header => ShardKey::Header,
+            Self::Accession => ShardKey::Accession,
+            Self::Sequence => ShardKey::Sequence,
+        }
+    }
+}
+
+/// Parses a byte count with an optional binary `K`, `M`, or `G` suffix.
+fn parse_byte_size(value: &str) -> Result<u64, String> {
+    let trimmed = value.trim();
+    let (digits, multiplier) = match trimmed.char_indices().last() {
+        Some((index, suffix)) if suffix.is_ascii_alphabetic() => {
+            let multiplier = match suffix.to_ascii_uppercase() {
+                'K' => 1 << 10,
+                'M' => 1 << 20,
+                'G' => 1 << 30,
+                _ => return Err(format!("unknown size suffix `{suffix}`; use K, M, or G")),
+            };
+            (&trimmed[..index], multiplier)
+        }
+        _ => (trimmed, 1),
+    };
+    digits
+        .parse::<u64>()
+        .ok()
+        .and_then(|count| count.checked_mul(multiplier))
+        .ok_or_else(|| format!("`{value}` is not a byte size such as 1048576 or 512M"))
+}
+
+/// Gathers `files` into `writer` and completes its output.
+fn gather_into<W: Write>(
+    files: &[PathBuf],
+    mut writer: FastaWriter<W>,
+    verbose: bool,
+) -> Result<usize, RfastaError> {
+    let records = gather_fasta_files(files, &mut writer, verbose)?;
+    writer.finish()?;
+    Ok(records)
+}
+
+/// Unsplits `shards` into `writer` and completes its output.
+///
+/// A single directory argument is read through its manifest; anything else is a list of shard
+/// files in shard-number order.
+fn unsplit_into<W: Write>(
+    shards: &[PathBuf],
+    mut writer: FastaWriter<W>,
+    verbose: bool,
+) -> Result<usize, RfastaError> {
+    let records = match shards {
+        [dir] if dir.is_dir() => unsplit_shard_dir(dir, &mut writer, verbose)?,
+        _ => unsplit_fasta_files(shards, &mut writer, verbose)?,
+    };
+    writer.finish()?;
+    Ok(records)
+}
+
+/// Parses an `I/N` file part.
+fn parse_file_part(value: &str) -> Result<FilePart, String> {
+    let (index, count) = value
+        .split_once('/')
+        .and_then(|(index, count)| Some((index.parse().ok()?, count.parse().ok()?)))
+        .ok_or_else(|| format!("`{value}` is not a part such as 0/8"))?;
+    FilePart::new(index, count).map_err(|_| format!("part {value} needs 0 <= I < N"))
+}
+
+/// Parses a `field:NAME`, `regex:PATTERN`, or `length:WIDTH` group key.
+fn parse_group_key(value: &str) -> Result<GroupKey, String> {
+    match value.split_once(':') {
+        Some(("field", field)) => Ok(GroupKey::UniprotField(field.to_string())),
+        Some(("regex", pattern)) => Ok(GroupKey::HeaderRegex(pattern.to_string())),
+        Some(("length", width)) => width
+            .parse()
+            .map(GroupKey::LengthBucket)
+            .map_err(|_| format!("`{width}` is not a bucket width such as 100")),
+        _ => Err(format!(
+            "`{value}` is not a group key; use field:OX, regex:PATTERN, or length:100"
+        )),
+    }
+}
+
+/// Path argument selecting standard input or standard output.
+const STDIO_PATH: &str = "-";
+
+/// Shard file stem used when splitting standard input.
+const STDIN_SHARD_STEM: &str = "stdin";
+
+fn is_stdio(path: &Path) -> bool {
+    path.as_os_str() == STDIO_PATH
+}
+
+/// Rejects `-` for subcommands that need a seekable input file.
+fn require_file_input(filename: &Path, operation: &'static str) -> Result<(), RfastaError> {
+    if is_stdio(filename) {
+        return Err(RfastaError::invalid_input(
+            operation,
+            "standard input cannot be used because random access requires a seekable file",
+            "Write the FASTA to a file first, then pass its path.",
+        ));
+    }
+    Ok(())
+}
+
+fn read_records(
+    filename: &Path,
+    options: ParseOptions,
+    verbose: bool,
+) -> Result<Vec<FastaRecord>, RfastaError> {
+    if !is_stdio(filename) {
+        return parse_fasta_file(filename, options, verbose);
+    }
+    let reader = decompressed_reader(std::io::stdin().lock(), None)?;
+    let records = parse_fasta_reader(reader, options)?;
+    if verbose {
+        eprintln!(
+            "[INFO]: Parsed standard input to recover {} sequences",
+            records.len()
+        );
+    }
+    Ok(records)
+}
+
+fn write_records(
+    records: &[FastaRecord],
+    output: &Path,
+    options: WriteOptions,
+    verbose: bool,
+) -> Result<(), RfastaError> {
+    if is_stdio(output) {
+        write_fasta_writer(&mut std::io::stdout().lock(), records, &options)
+    } else {
+        write_fasta_file(records, output, options, verbose)
+    }
+}
+
+/// Summary printed by `rfasta clean --print-statistics`.
+#[derive(Default)]
+struct SequenceStatistics {
+    total: usize,
+    shortest: Option<usize>,
+    longest: Option<usize>,
+}
+
+impl SequenceStatistics {
+    fn observe(&mut self, record: &FastaRecord) {
+        let length = record.sequence.len();
+        self.total += 1;
+        self.shortest = Some(
+            self.shortest
+                .map_or(length, |shortest| shortest.min(length)),
+        );
+        self.longest = Some(self.longest.map_or(length, |longest| longest.max(length)));
+    }
+}
+
+impl fmt::Display for SequenceStatistics {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Total sequences: {}", self.total)?;
+        if let Some(shortest) = self.shortest {
+            writeln!(f, "Shortest sequence: {shortest}")?;
+        }
+        if let Some(longest) = self.longest {
+            writeln!(f, "Longest sequence: {longest}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Runs the streaming cleaner over a file or standard input, writing kept records to `writer`.
+fn stream_clean<W: Write>(
+    filename: &Path,
+    parse_options: ParseOptions,
+    options: &CleanOptions,
+    mut writer: Option<FastaWriter<W>>,
+) -> Result<(SequenceStatistics, CleanReport), RfastaError> {
+    let mut statistics = SequenceStatistics::default();
+    let mut visit = |record: FastaRecord| {
+        statistics.observe(&record);
+        match writer.as_mut() {
+            Some(writer) => writer.write_record(&record),
+            None => Ok(()),
+        }
+    };
+    let report = if is_stdio(filename) {
+        let reader = decompressed_reader(std::io::stdin().lock(), None)?;
+        visit_cleaned_fasta_reader(reader, parse_options, options, &mut visit)?
+    } else {
+        visit_cleaned_fasta_file(filename, parse_options, options, &mut visit)?
+    };
+    if let Some(writer) = writer {
+        writer.finish()?;
+    }
+    Ok((statistics, report))
+}
+
+fn write_clean_report(report: &CleanReport, path: &Path) -> Result<(), RfastaError> {
+    let json = format!("{}\n", report.to_json());
+    let result = if is_stdio(path) {
+        std::io::stdout().lock().write_all(json.as_bytes())
+    } else {
+        std::fs::write(path, json)
+    };
+    result.map_err(|source| {
+        RfastaError::io(
+            "clean",
+            Some(path),
+            source,
+            "Check that the report path is writable.",
+        )
+    })
+}
+
+/// Resolves a samtools-style region into a sequence name and 0-based, end-exclusive range.
+///
+/// As in samtools, an end past the sequence is clamped to its length; only a start past the end
+/// is an error.
+fn parse_region(
+    index: &FastaIndex,
+    region: &str,
+) -> Result<(String, Option<(u64, u64)>), RfastaError> {
+    if index.get(region).is_some() {
+        return Ok((region.to_string(), None));
+    }
+
+    let invalid = || {
+        RfastaError::invalid_input(
+            "fetch",
+            format!("region `{region}` does not match an indexed sequence"),
+            "Use `name` or `name:start-end` with 1-based inclusive coordinates.",
+        )
+    };
+    let (name, range) = region.rsplit_once(':').ok_or_else(invalid)?;
+    let entry = index.get(name).ok_or_else(invalid)?;
+    let (start, end) = match range.split_once('-') {
+        Some((start, end)) => (start, Some(end)),
+        None => (range, None),
+    };
+    let start: u64 = start.replace(',', "").parse().map_err(|_| invalid())?;
+    let end: u64 = match end {
+        Some(end) => end.replace(',', "").parse().map_err(|_| invalid())?,
+        None => entry.length,
+    };
+    if start == 0 || end < start {
+        return Err(invalid());
+    }
+    if start > entry.length {
+        return Err(RfastaError::invalid_input(
+            "fetch",
+            format!(
+                "region `{region}` starts at {start}, past the end of `{name}` ({} residues)",
+                entry.length
+            ),
+            "Use a start position between 1 and the sequence length reported by the .fai index.",
+        ));
+    }
+    Ok((name.to_string(), Some((start - 1, end.min(entry.length)))))
 }
 
 /// Runs the rfasta command-line interface.
@@ -119,17 +706,28 @@ pub fn main(args: &[String]) -> Result<(), RfastaError> {
             filename,
             output,
             non_unique_header,
+            part,
             duplicate_record,
             duplicate_sequence,
             invalid_sequence,
+            alignment,
+            correction_file,
+            overlay_standard,
             number_lines,
             shortest_seq,
             longest_seq,
             random_subsample,
+            seed,
             print_statistics,
             no_outputfile,
             silent,
             remove_comma_from_header,
+            compress,
+            compression_level,
+            report,
+            rejected,
+            rejected_tsv,
+            threads,
         } => {
             if !non_unique_header && matches!(duplicate_record, DuplicateAction::Ignore) {
                 return Err(RfastaError::invalid_input(
@@ -139,72 +737,409 @@ pub fn main(args: &[String]) -> Result<(), RfastaError> {
                 ));
             }
 
-            let records = parse_fasta_file(
-                filename,
-                ParseOptions {
-                    expect_unique_header: !non_unique_header,
-                },
-                !silent,
-            )?;
-
-            let cleaned = clean_sequences(
-                records,
-                &CleanOptions {
-                    invalid_sequence_action: invalid_sequence,
-                    duplicate_record_action: duplicate_record,
-                    duplicate_sequence_action: duplicate_sequence,
-                    shortest_seq,
-                    longest_seq,
-                    random_subsample,
-                    remove_comma_from_header,
-                    alignment: false,
-                    verbose: !silent,
-                    correction_dictionary: None,
-                },
-            )?;
+            let parse_options = ParseOptions {
+                expect_unique_header: !non_unique_header,
+                part,
+            };
+            let correction_dictionary = correction_file
+                .map(|path| read_correction_file(&path))
+                .transpose()?
+                .map(|dictionary| {
+                    if overlay_standard {
+                        overlay_standard_conversions(dictionary, alignment)
+                    } else {
+                        dictionary
+                    }
+                });
+            for (flag, path) in [("--rejected", &rejected), ("--rejected-tsv", &rejected_tsv)] {
+                if path.as_deref().is_some_and(is_stdio) {
+                    return Err(RfastaError::invalid_input(
+                        "clean",
+                        format!("{flag} cannot write to standard output"),
+                        "Pass a file path for rejected records; standard output is reserved for -o - and --report -.",
+                    ));
+                }
+            }
+            let clean_options = CleanOptions {
+                invalid_sequence_action: invalid_sequence,
+                duplicate_record_action: duplicate_record,
+                duplicate_sequence_action: duplicate_sequence,
+                shortest_seq,
+                longest_seq,
+                random_subsample,
+                seed,
+                remove_comma_from_header,
+                alignment,
+                verbose: !silent,
+                correction_dictionary,
+                correction_rules: None,
+                rejected: (rejected.is_some() || rejected_tsv.is_some()).then_some(
+                    RejectedOutput {
+                        fasta: rejected,
+                        tsv: rejected_tsv,
+                    },
+                ),
+                threads,
+            };
+            let output = output.filter(|_| !no_outputfile);
+            if report.as_deref().is_some_and(is_stdio) && output.as_deref().is_some_and(is_stdio) {
+                return Err(RfastaError::invalid_input(
+                    "clean",
+                    "cannot write both the cleaned FASTA and the JSON report to standard output",
+                    "Pass a file path to --report when using -o -.",
+                ));
+            }
+            let write_options = WriteOptions {
+                line_length: number_lines.or(Some(DEFAULT_LINE_LENGTH)),
+                append: false,
+                compression: compress.unwrap_or_else(|| {
+                    output
+                        .as_deref()
+                        .map(Compression::from_path)
+                        .unwrap_or_default()
+                }),
+                compression_level,
+            };
 
-            if print_statistics && !silent {
-                println!("Total sequences: {}", cleaned.len());
-                if let Some(shortest) = cleaned.iter().map(|record| record.sequence.len()).min() {
-                    println!("Shortest sequence: {shortest}");
+            // Stream whenever the policy allows it so memory stays bounded for large inputs.
+            let (statistics, clean_report) = if clean_options.supports_streaming() {
+                match output.as_deref() {
+                    Some(path) if is_stdio(path) => {
+                        let writer = FastaWriter::new(std::io::stdout().lock(), &write_options)?;
+                        stream_clean(&filename, parse_options, &clean_options, Some(writer))?
+                    }
+                    Some(path) => {
+                        let writer = FastaWriter::create(path, &write_options)?;
+                        let cleaned =
+                            stream_clean(&filename, parse_options, &clean_options, Some(writer))?;
+                        if !silent {
+                            eprintln!(
+                                "[INFO]: Wrote {} sequences to {}",
+                                cleaned.1.records_out,
+                                path.display()
+                            );
+                        }
+                        cleaned
+                    }
+                    None => stream_clean::<std::io::Sink>(
+                        &filename,
+                        parse_options,
+                        &clean_options,
+                        None,
+                    )?,
                 }
-                if let Some(longest) = cleaned.iter().map(|record| record.sequence.len()).max() {
-                    println!("Longest sequence: {longest}");
+            } else {
+                let records = read_records(&filename, parse_options, !silent)?;
+                let (cleaned, clean_report) = clean_sequences_with_report(records, &clean_options)?;
+                if let Some(output) = &output {
+                    write_records(&cleaned, output, write_options, !silent)?;
                 }
-            }
+                let mut statistics = SequenceStatistics::default();
+                cleaned.iter().for_each(|record| statistics.observe(record));
+                (statistics, clean_report)
+            };
 
-            if !no_outputfile {
-                if let Some(output) = output {
-                    write_fasta_file(
-                        &cleaned,
-                        output,
-                        WriteOptions {
-                            line_length: number_lines.or(Some(DEFAULT_LINE_LENGTH)),
-                            append: false,
-                        },
-                        !silent,
-                    )?;
+            if print_statistics && !silent {
+                // Keep statistics off stdout when it carries the FASTA output.
+                let summary = statistics.to_string();
+                if output.as_deref().is_some_and(is_stdio) {
+                    eprint!("{summary}");
+                } else {
+                    print!("{summary}");
                 }
             }
+            if let Some(report) = report {
+                write_clean_report(&clean_report, &report)?;
+            }
         }
         Commands::Split {
             filename,
             output_dir,
             chunks,
+            max_records,
+            max_bytes,
+            balance,
+            by,
+            by_key,
             line_length,
+            compress,
+            compression_level,
+            max_open_files,
             no_outputfiles,
             silent,
         } => {
             if !no_outputfiles {
-                split_fasta_file_round_robin(
-                    filename,
+                let options = SplitOptions {
+                    write: WriteOptions {
+                        line_length: Some(line_length),
+                        compression: compress,
+                        compression_level,
+                        ..WriteOptions::default()
+                    },
+                    max_open_files,
+                };
+                let strategy = match (chunks, by, by_key) {
+                    (_, _, Some(key)) => ShardStrategy::Keyed { key },
+                    (Some(chunks), Some(by), None) => ShardStrategy::Hashed {
+                        chunks,
+                        key: by.key(),
+                    },
+                    (Some(chunks), None, None) => ShardStrategy::Balanced { chunks, balance },
+                    (None, _, None) => ShardStrategy::Rolling(ShardLimits {
+                        max_records,
+                        max_bytes,
+                    }),
+                };
+                if is_stdio(&filename) {
+                    let reader = decompressed_reader(std::io::stdin().lock(), None)?;
+                    split_fasta_reader(
+                        reader,
+                        output_dir,
+                        STDIN_SHARD_STEM,
+                        &strategy,
+                        &options,
+                        !silent,
+                    )?;
+                } else {
+                    split_fasta_file(filename, output_dir, &strategy, &options, !silent)?;
+                }
+            }
+        }
+        Commands::Partition {
+            filename,
+            output_dir,
+            ratios,
+            names,
+            seed,
+            by,
+            min_similarity,
+            kmer_size,
+            max_kmer_frequency,
+            line_length,
+            compress,
+            compression_level,
+            silent,
+        } => {
+            let names = match names {
+                Some(names) => names,
+                None if ratios.len() == 2 => vec!["train".to_string(), "test".to_string()],
+                None if ratios.len() == 3 => {
+                    vec!["train".to_string(), "val".to_string(), "test".to_string()]
+                }
+                None => {
+                    return Err(RfastaError::invalid_input(
+                        "partition",
+                        format!("{} ratios were given without --names", ratios.len()),
+                        "Pass --names with one name per ratio.",
+                    ))
+                }
+            };
+            if names.len() != ratios.len() {
+                return Err(RfastaError::invalid_input(
+                    "partition",
+                    format!(
+                        "{} names were given for {} ratios",
+                        names.len(),
+                        ratios.len()
+                    ),
+                    "Pass exactly one name per ratio.",
+                ));
+            }
+            let partitions: Vec<Partition> = names
+                .into_iter()
+                .zip(ratios)
+                .map(|(name, ratio)| Partition::new(name, ratio))
+                .collect();
+            let strategy = match (seed, by, min_similarity) {
+                (_, Some(by), _) => ShardStrategy::Partitioned {
+                    partitions,
+                    assignment: PartitionAssignment::Hashed { key: by.key() },
+                },
+                (Some(seed), None, Some(min_similarity)) => ShardStrategy::Grouped {
+                    partitions,
+                    grouping: SimilarityGrouping {
+                        kmer_size,
+                        min_similarity,
+                        max_kmer_frequency,
+                    },
+                    seed,
+                },
+                (Some(seed), None, None) => ShardStrategy::Partitioned {
+                    partitions,
+                    assignment: PartitionAssignment::Random { seed },
+                },
+                (None, None, _) => {
+                    return Err(RfastaError::invalid_input(
+                        "partition",
+                        "--seed is required unless --by is given",
+                        "Pass --seed N for random partitions, or --by KEY for hashed ones.",
+                    ))
+                }
+            };
+            let options = SplitOptions::from(WriteOptions {
+                line_length: Some(line_length),
+                compression: compress,
+                compression_level,
+                ..WriteOptions::default()
+            });
+            if is_stdio(&filename) {
+                let reader = decompressed_reader(std::io::stdin().lock(), None)?;
+                split_fasta_reader(
+                    reader,
                     output_dir,
-                    chunks,
-                    Some(line_length),
+                    STDIN_SHARD_STEM,
+                    &strategy,
+                    &options,
                     !silent,
                 )?;
+            } else {
+                split_fasta_file(filename, output_dir, &strategy, &options, !silent)?;
             }
         }
+        Commands::Explode {
+            filename,
+            output_dir,
+            name_by,
+            max_per_dir,
+            line_length,
+            compress,
+            compression_level,
+            silent,
+        } => {
+            let explode = ExplodeOptions {
+                name_by,
+                max_per_dir,
+            };
+            let options = WriteOptions {
+                line_length: Some(line_length),
+                compression: compress,
+                compression_level,
+                ..WriteOptions::default()
+            };
+            if is_stdio(&filename) {
+                let reader = decompressed_reader(std::io::stdin().lock(), None)?;
+                explode_fasta_reader(reader, output_dir, &explode, &options, !silent)?;
+            } else {
+                explode_fasta_file(filename, output_dir, &explode, &options, !silent)?;
+            }
+        }
+        Commands::Gather {
+            dir,
+            output,
+            line_length,
+            compress,
+            compression_level,
+            silent,
+        } => {
+            let mut files = list_fasta_files(&dir)?;
+            // A rerun must not read the previous output back in.
+            if let Ok(output) = output.canonicalize() {
+                files.retain(|file| file.canonicalize().map_or(true, |file| file != output));
+            }
+            let options = WriteOptions {
+                line_length: Some(line_length),
+                compression: compress.unwrap_or_else(|| Compression::from_path(&output)),
+                compression_level,
+                ..WriteOptions::default()
+            };
+            if is_stdio(&output) {
+                let writer = FastaWriter::new(std::io::stdout().lock(), &options)?;
+                gather_into(&files, writer, !silent)?;
+            } else {
+                gather_into(&files, FastaWriter::create(&output, &options)?, !silent)?;
+                if !silent {
+                    eprintln!("[INFO]: Wrote gathered sequences to {}", output.display());
+                }
+            }
+        }
+        Commands::Unsplit {
+            shards,
+            output,
+            line_length,
+            compress,
+            compression_level,
+            silent,
+        } => {
+            let options = WriteOptions {
+                line_length: Some(line_length),
+                compression: compress.unwrap_or_else(|| Compression::from_path(&output)),
+                compression_level,
+                ..WriteOptions::default()
+            };
+            if is_stdio(&output) {
+                let writer = FastaWriter::new(std::io::stdout().lock(), &options)?;
+                unsplit_into(&shards, writer, !silent)?;
+            } else {
+                unsplit_into(&shards, FastaWriter::create(&output, &options)?, !silent)?;
+                if !silent {
+                    eprintln!("[INFO]: Wrote unsplit sequences to {}", output.display());
+                }
+            }
+        }
+        Commands::VerifyShards { dir, silent } => {
+            let manifest = verify_shards(&dir)?;
+            if !silent {
+                let records: usize = manifest.shards.iter().map(|shard| shard.records).sum();
+                eprintln!(
+                    "[INFO]: Verified {} shards ({records} sequences) in {}",
+                    manifest.shards.len(),
+                    dir.display()
+                );
+            }
+        }
+        Commands::Faidx {
+            filename,
+            output,
+            silent,
+        } => match output {
+            Some(output) if is_stdio(&output) => {
+                require_file_input(&filename, "index")?;
+                FastaIndex::build(&filename)?.write_to(&mut std::io::stdout().lock())?;
+            }
+            Some(output) => {
+                require_file_input(&filename, "index")?;
+                let index = FastaIndex::build(&filename)?;
+                index.write(&output)?;
+                if !silent {
+                    eprintln!(
+                        "[INFO]: Indexed {} sequences into {}",
+                        index.len(),
+                        output.display()
+                    );
+                }
+            }
+            None => {
+                require_file_input(&filename, "index")?;
+                index_fasta_file(&filename, !silent)?;
+            }
+        },
+        Commands::Fetch {
+            filename,
+            regions,
+            output,
+            line_length,
+        } => {
+            require_file_input(&filename, "fetch")?;
+            let mut fasta = IndexedFasta::open(&filename)?;
+
+            let mut records = Vec::with_capacity(regions.len());
+            for region in &regions {
+                let record = match parse_region(fasta.index(), region)? {
+                    (name, None) => fasta.fetch(&name)?,
+                    (name, Some((start, end))) => {
+                        FastaRecord::new(region.as_str(), fasta.fetch_range(&name, start, end)?)
+                    }
+                };
+                records.push(record);
+            }
+
+            let options = WriteOptions {
+                line_length: Some(line_length),
+                ..WriteOptions::default()
+            };
+            let output = output.unwrap_or_else(|| PathBuf::from(STDIO_PATH));
+            write_records(&records, &output, options, false)?;
+        }
     }
 
     Ok(())
@@ -0,0 +1,402 @@
+use std::io::{self, BufRead, BufReader, Cursor, Read, Write};
+use std::path::Path;
+
+use clap::ValueEnum;
+use flate2::read::MultiGzDecoder;
+use flate2::write::{DeflateEncoder, GzEncoder};
+use flate2::Crc;
+use serde::{Deserialize, Serialize};
+
+use crate::errors::RfastaError;
+
+/// Compression applied to FASTA output.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq, Serialize, ValueEnum)]
+#[serde(rename_all = "snake_case")]
+pub enum Compression {
+    /// Plain-text FASTA.
+    #[default]
+    None,
+    /// Single-member gzip stream.
+    Gzip,
+    /// Blocked gzip as written by `bgzip`, readable by any gzip decoder.
+    Bgzf,
+    /// Zstandard stream.
+    Zstd,
+}
+
+impl Compression {
+    /// Infers the compression format from an output path extension.
+    ///
+    /// `.gz` maps to [`Compression::Gzip`], `.bgz`/`.bgzf` to [`Compression::Bgzf`], and `.zst` to
+    /// [`Compression::Zstd`]. Any other extension maps to [`Compression::None`].
+    ///
+    /// # Example
+    /// ```
+    /// use rfasta::write::Compression;
+    ///
+    /// assert_eq!(Compression::from_path("out.fasta.gz"), Compression::Gzip);
+    /// assert_eq!(Compression::from_path("out.fasta"), Compression::None);
+    /// ```
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Self {
+        let extension = path
+            .as_ref()
+            .extension()
+            .and_then(|value| value.to_str())
+            .unwrap_or_default()
+            .to_ascii_lowercase();
+        match extension.as_str() {
+            "gz" => Self::Gzip,
+            "bgz" | "bgzf" => Self::Bgzf,
+            "zst" => Self::Zstd,
+            _ => Self::None,
+        }
+    }
+
+    /// Returns the bytes that end a stream after its last member: the BGZF end-of-file block, and
+    /// nothing for the other formats.
+    pub(crate) fn stream_trailer(self) -> &'static [u8] {
+        match self {
+            Self::Bgzf => &BGZF_EOF,
+            Self::None | Self::Gzip | Self::Zstd => &[],
+        }
+    }
+
+    /// Returns the file extension appended to generated output names, without the leading dot.
+    pub fn extension(self) -> Option<&'static str> {
+        match self {
+            Self::None => None,
+            Self::Gzip | Self::Bgzf => Some("gz"),
+            Self::Zstd => Some("zst"),
+        }
+    }
+}
+
+/// Compression formats recognized on FASTA inputs.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(crate) enum InputCompression {
+    None,
+    Gzip,
+    Zstd,
+    Xz,
+}
+
+const GZIP_MAGIC: &[u8] = &[0x1f, 0x8b];
+const ZSTD_MAGIC: &[u8] = &[0x28, 0xb5, 0x2f, 0xfd];
+const XZ_MAGIC: &[u8] = &[0xfd, b'7', b'z', b'X', b'Z', 0x00];
+
+/// Length of the longest magic number, which [`decompressed_reader`] buffers before detecting.
+const MAGIC_LEN: usize = XZ_MAGIC.len();
+
+/// File extensions stripped from input names before deriving output stems.
+pub(crate) const COMPRESSED_EXTENSIONS: [&str; 5] = ["gz", "bgz", "bgzf", "zst", "xz"];
+
+impl InputCompression {
+    pub(crate) fn detect(prefix: &[u8]) -> Self {
+        if prefix.starts_with(GZIP_MAGIC) {
+            Self::Gzip
+        } else if prefix.starts_with(ZSTD_MAGIC) {
+            Self::Zstd
+        } else if prefix.starts_with(XZ_MAGIC) {
+            Self::Xz
+        } else {
+            Self::None
+        }
+    }
+}
+
+/// Wraps a buffered reader in the decoder matching its leading magic bytes.
+///
+/// Gzip input is decoded as a multi-member stream so bgzip files are read in full. Inputs that do
+/// not start with a known magic number are passed through unchanged. Pipes may return fewer bytes
+/// than a magic number on the first read, so reading continues until the longest magic number is
+/// buffered or the input ends.
+pub(crate) fn decompressed_reader<R>(
+    mut reader: R,
+    path: Option<&Path>,
+) -> Result<Box<dyn BufRead>, RfastaError>
+where
+    R: BufRead + 'static,
+{
+    let read_error = |source| {
+        RfastaError::io(
+            "parse",
+            path,
+            source,
+            "Check that the input file exists and is readable.",
+        )
+    };
+    let buffered = reader.fill_buf().map_err(read_error)?;
+    if buffered.len() >= MAGIC_LEN || buffered.is_empty() {
+        let compression = InputCompression::detect(buffered);
+        return wrap_decoder(reader, compression, path);
+    }
+
+    let mut prefix = Vec::with_capacity(MAGIC_LEN);
+    (&mut reader)
+        .take(MAGIC_LEN as u64)
+        .read_to_end(&mut prefix)
+        .map_err(read_error)?;
+    let compression = InputCompression::detect(&prefix);
+    wrap_decoder(Cursor::new(prefix).chain(reader), compression, path)
+}
+
+fn wrap_decoder<R>(
+    reader: R,
+    compression: InputCompression,
+    path: Option<&Path>,
+) -> Result<Box<dyn BufRead>, RfastaError>
+where
+    R: BufRead + 'static,
+{
+    let reader: Box<dyn BufRead> = match compression {
+        InputCompression::None => Box::new(reader),
+        InputCompression::Gzip => Box::new(BufReader::new(MultiGzDecoder::new(reader))),
+        InputCompression::Zstd => {
+            let decoder = zstd::Decoder::with_buffer(reader).map_err(|source| {
+                RfastaError::io(
+                    "parse",
+                    path,
+                    source,
+                    "Check that the zstd-compressed input is complete and not corrupted.",
+                )
+            })?;
+            Box::new(BufReader::new(decoder))
+        }
+        InputCompression::Xz => Box::new(BufReader::new(xz2::read::XzDecoder::new_multi_decoder(
+            reader,
+        ))),
+    };
+    Ok(reader)
+}
+
+/// Uncompressed bytes per BGZF block, matching `bgzip`.
+const BGZF_BLOCK_SIZE: usize = 0xff00;
+
+/// Empty BGZF block that marks the end of a BGZF file.
+const BGZF_EOF: [u8; 28] = [
+    0x1f, 0x8b, 0x08, 0x04, 0x00, 0x00, 0x00, 0x00, 0x00, 0xff, 0x06, 0x00, 0x42, 0x43, 0x02, 0x00,
+    0x1b, 0x00, 0x03, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+];
+
+/// Writer emitting BGZF blocks: gzip members carrying their compressed size in a `BC` extra field.
+pub(crate) struct BgzfWriter<W: Write> {
+    inner: W,
+    buffer: Vec<u8>,
+    level: flate2::Compression,
+}
+
+impl<W: Write> BgzfWriter<W> {
+    fn new(inner: W, level: flate2::Compression) -> Self {
+        Self {
+            inner,
+            buffer: Vec::with_capacity(BGZF_BLOCK_SIZE),
+            level,
+        }
+    }
+
+    fn write_block(&mut self) -> io::Result<()> {
+        if self.buffer.is_empty() {
+            return Ok(());
+        }
+
+        let mut encoder = DeflateEncoder::new(Vec::with_capacity(self.buffer.len()), self.level);
+        encoder.write_all(&self.buffer)?;
+        let data = encoder.finish()?;
+        let mut crc = Crc::new();
+        crc.update(&self.buffer);
+
+        // 18-byte header + deflate data + CRC32 + ISIZE, minus one as required by the spec.
+        let block_size = u16::try_from(data.len() + 25)
+            .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "BGZF block exceeds 64 KiB"))?;
+        let mut header = [
+            0x1f, 0x8b, 0x08, 0x04, 0x00, 0x00, 0x00, 0x00, 0x00, 0xff, 0x06, 0x00, 0x42, 0x43,
+            0x02, 0x00, 0x00, 0x00,
+        ];
+        header[16..18].copy_from_slice(&block_size.to_le_bytes());
+
+        self.inner.write_all(&header)?;
+        self.inner.write_all(&data)?;
+        self.inner.write_all(&crc.sum().to_le_bytes())?;
+        self.inner
+            .write_all(&(self.buffer.len() as u32).to_le_bytes())?;
+        self.buffer.clear();
+        Ok(())
+    }
+
+    fn finish(self) -> io::Result<W> {
+        let mut inner = self.finish_member()?;
+        inner.write_all(&BGZF_EOF)?;
+        Ok(inner)
+    }
+
+    /// Writes the buffered block without the end-of-file marker.
+    fn finish_member(mut self) -> io::Result<W> {
+        self.write_block()?;
+        Ok(self.inner)
+    }
+}
+
+impl<W: Write> Write for BgzfWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let available = BGZF_BLOCK_SIZE - self.buffer.len();
+        let written = buf.len().min(available);
+        self.buffer.extend_from_slice(&buf[..written]);
+        if self.buffer.len() == BGZF_BLOCK_SIZE {
+            self.write_block()?;
+        }
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.write_block()?;
+        self.inner.flush()
+    }
+}
+
+/// Output writer applying the configured [`Compression`].
+///
+/// Compressed streams must be completed with [`CompressedWriter::finish`] so trailers are written.
+pub(crate) enum CompressedWriter<W: Write> {
+    Plain(W),
+    Gzip(GzEncoder<W>),
+    Bgzf(BgzfWriter<W>),
+    Zstd(zstd::Encoder<'static, W>),
+}
+
+impl<W: Write> CompressedWriter<W> {
+    /// Wraps `inner` in an encoder for `compression`.
+    ///
+    /// Levels are clamped to each format's supported range; `None` selects the format default.
+    pub(crate) fn new(inner: W, compression: Compression, level: Option<i32>) -> io::Result<Self> {
+        let deflate_level = || flate2::Compression::new(level.unwrap_or(6).clamp(0, 9) as u32);
+        Ok(match compression {
+            Compression::None => Self::Plain(inner),
+            Compression::Gzip => Self::Gzip(GzEncoder::new(inner, deflate_level())),
+            Compression::Bgzf => Self::Bgzf(BgzfWriter::new(inner, deflate_level())),
+            Compression::Zstd => {
+                let range = zstd::compression_level_range();
+                let level = level
+                    .unwrap_or(zstd::DEFAULT_COMPRESSION_LEVEL)
+                    .clamp(*range.start(), *range.end());
+                Self::Zstd(zstd::Encoder::new(inner, level)?)
+            }
+        })
+    }
+
+    /// Writes any compression trailer and returns the underlying writer.
+    pub(crate) fn finish(self) -> io::Result<W> {
+        match self {
+            Self::Plain(inner) => Ok(inner),
+            Self::Gzip(encoder) => encoder.finish(),
+            Self::Bgzf(encoder) => encoder.finish(),
+            Self::Zstd(encoder) => encoder.finish(),
+        }
+    }
+
+    /// Completes the current gzip member, BGZF block, or zstd frame and returns the underlying
+    /// writer, leaving the stream open for further members.
+    ///
+    /// Writing another member and then [`Compression::stream_trailer`] gives a multi-member stream
+    /// that decodes to the same bytes as a single one.
+    pub(crate) fn finish_member(self) -> io::Result<W> {
+        match self {
+            Self::Bgzf(encoder) => encoder.finish_member(),
+            other => other.finish(),
+        }
+    }
+}
+
+impl<W: Write> Write for CompressedWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            Self::Plain(inner) => inner.write(buf),
+            Self::Gzip(encoder) => encoder.write(buf),
+            Self::Bgzf(encoder) => encoder.write(buf),
+            Self::Zstd(encoder) => encoder.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            Self::Plain(inner) => inner.flush(),
+            Self::Gzip(encoder) => encoder.flush(),
+            Self::Bgzf(encoder) => encoder.flush(),
+            Self::Zstd(encoder) => encoder.flush(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn compression_is_detected_from_magic_bytes() {
+        assert_eq!(
+            InputCompression::detect(&[0x1f, 0x8b, 0x08]),
+            InputCompression::Gzip
+        );
+        assert_eq!(
+            InputCompression::detect(&[0x28, 0xb5, 0x2f, 0xfd, 0x00]),
+            InputCompression::Zstd
+        );
+        assert_eq!(InputCompression::detect(XZ_MAGIC), InputCompression::Xz);
+        assert_eq!(InputCompression::detect(b">seq1\n"), InputCompression::None);
+        assert_eq!(InputCompression::detect(b""), InputCompression::None);
+    }
+
+    /// Reader returning one byte per call, like a slow pipe.
+    struct OneByteReader(Vec<u8>, usize);
+
+    impl Read for OneByteReader {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            match self.0.get(self.1) {
+                Some(&byte) if !buf.is_empty() => {
+                    buf[0] = byte;
+                    self.1 += 1;
+                    Ok(1)
+                }
+                _ => Ok(0),
+            }
+        }
+    }
+
+    #[test]
+    fn compression_is_detected_across_short_reads() {
+        let payload = b">seq1\nACDEFGHIKLMNPQRSTVWY\n".to_vec();
+        let mut writer = CompressedWriter::new(Vec::new(), Compression::Zstd, None).unwrap();
+        writer.write_all(&payload).unwrap();
+        let encoded = writer.finish().unwrap();
+
+        for (input, expected) in [
+            (encoded, &payload),
+            (b">s\nA\n".to_vec(), &b">s\nA\n".to_vec()),
+        ] {
+            let reader = BufReader::new(OneByteReader(input, 0));
+            let mut decoded = Vec::new();
+            decompressed_reader(reader, None)
+                .unwrap()
+                .read_to_end(&mut decoded)
+                .unwrap();
+            assert_eq!(&decoded, expected);
+        }
+    }
+
+    #[test]
+    fn bgzf_blocks_round_trip_and_end_with_eof_marker() {
+        let payload = b">seq1\nACDEFGHIKLMNPQRSTVWY\n\n".repeat(5_000);
+        let mut writer = CompressedWriter::new(Vec::new(), Compression::Bgzf, None).unwrap();
+        writer.write_all(&payload).unwrap();
+        let encoded = writer.finish().unwrap();
+
+        assert!(encoded.ends_with(&BGZF_EOF));
+        let first_block = u16::from_le_bytes([encoded[16], encoded[17]]) as usize + 1;
+        assert_eq!(&encoded[first_block..first_block + 2], GZIP_MAGIC);
+
+        let mut decoded = Vec::new();
+        MultiGzDecoder::new(encoded.as_slice())
+            .read_to_end(&mut decoded)
+            .unwrap();
+        assert_eq!(decoded, payload);
+    }
+}
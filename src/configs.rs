@@ -3,6 +3,18 @@ use std::collections::HashMap;
 /// Default FASTA line length used by the CLI and examples.
 pub const DEFAULT_LINE_LENGTH: usize = 60;
 
+/// Default limit on shard files held open at once while splitting.
+pub const DEFAULT_MAX_OPEN_FILES: usize = 512;
+
+/// Default k-mer length for similarity-aware partitioning.
+pub const DEFAULT_SIMILARITY_KMER_SIZE: usize = 5;
+
+/// Default k-mer similarity at which sequences are kept in the same partition.
+pub const DEFAULT_MIN_SIMILARITY: f64 = 0.5;
+
+/// Default number of sequences after which a k-mer is ignored in similarity-aware partitioning.
+pub const DEFAULT_MAX_KMER_FREQUENCY: usize = 1000;
+
 /// Minimum line length accepted for wrapped FASTA output.
 pub const MIN_LINE_LENGTH: usize = 5;
 
@@ -0,0 +1,248 @@
+use std::collections::HashMap;
+
+use regex::Regex;
+
+use crate::errors::RfastaError;
+
+/// How a [`CorrectionRule`] pattern is matched.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum PatternKind {
+    /// Match the pattern text exactly, ignoring ASCII case.
+    Literal,
+    /// Match a regular expression against the uppercased sequence.
+    Regex,
+}
+
+/// One residue correction: replace `pattern` with `replacement`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct CorrectionRule {
+    /// Text or regular expression to replace.
+    pub pattern: String,
+    /// Replacement text. An empty replacement deletes the match.
+    pub replacement: String,
+    /// Rules with a higher priority win when several match at the same position.
+    pub priority: i32,
+    /// Whether `pattern` is literal text or a regular expression.
+    pub kind: PatternKind,
+}
+
+impl CorrectionRule {
+    /// Creates a literal rule with priority 0.
+    pub fn literal(pattern: impl Into<String>, replacement: impl Into<String>) -> Self {
+        Self {
+            pattern: pattern.into(),
+            replacement: replacement.into(),
+            priority: 0,
+            kind: PatternKind::Literal,
+        }
+    }
+
+    /// Creates a regular-expression rule with priority 0.
+    pub fn regex(pattern: impl Into<String>, replacement: impl Into<String>) -> Self {
+        Self {
+            kind: PatternKind::Regex,
+            ..Self::literal(pattern, replacement)
+        }
+    }
+
+    /// Returns the rule with `priority` set.
+    pub fn with_priority(mut self, priority: i32) -> Self {
+        self.priority = priority;
+        self
+    }
+}
+
+#[derive(Clone, Debug)]
+enum Matcher {
+    Literal(String),
+    Regex(Regex),
+}
+
+impl Matcher {
+    /// Returns the length in bytes of a non-empty match at the start of `text`.
+    fn match_len(&self, text: &str) -> Option<usize> {
+        let len = match self {
+            Self::Literal(pattern) => text.starts_with(pattern.as_str()).then_some(pattern.len()),
+            Self::Regex(regex) => regex.find(text).map(|found| found.end()),
+        }?;
+        (len > 0).then_some(len)
+    }
+}
+
+/// Ordered list of residue correction rules with deterministic output.
+///
+/// The sequence is uppercased and scanned once from left to right. At each position, the rule
+/// with the highest priority that matches wins; ties go to the longest match, then to the rule
+/// listed first. The winning match is replaced and scanning resumes after it, so replacements
+/// never cascade into each other. Positions no rule matches are copied unchanged.
+///
+/// # Example
+/// ```
+/// use rfasta::clean::{CorrectionRule, CorrectionRules};
+///
+/// let rules = CorrectionRules::new(vec![
+///     CorrectionRule::literal("X", "G"),
+///     CorrectionRule::literal("XX", "A"),
+///     CorrectionRule::regex("\\*+$", ""),
+/// ])?;
+/// assert_eq!(rules.apply("mxxx**"), "MAG");
+/// # Ok::<(), rfasta::RfastaError>(())
+/// ```
+#[derive(Clone, Debug)]
+pub struct CorrectionRules {
+    rules: Vec<CorrectionRule>,
+    matchers: Vec<Matcher>,
+}
+
+impl CorrectionRules {
+    /// Validates and compiles `rules`.
+    ///
+    /// Literal patterns and all replacements are uppercased. Empty patterns and invalid regular
+    /// expressions are rejected.
+    pub fn new(rules: Vec<CorrectionRule>) -> Result<Self, RfastaError> {
+        let mut rules = rules;
+        // A stable sort keeps declaration order as the final tie-breaker.
+        rules.sort_by_key(|rule| std::cmp::Reverse(rule.priority));
+        let mut matchers = Vec::with_capacity(rules.len());
+        for rule in &mut rules {
+            if rule.pattern.is_empty() {
+                return Err(RfastaError::invalid_input(
+                    "clean",
+                    "correction rules cannot have an empty pattern",
+                    "Remove the empty rule or give it a residue or pattern to match.",
+                ));
+            }
+            rule.replacement = rule.replacement.to_ascii_uppercase();
+            matchers.push(match rule.kind {
+                PatternKind::Literal => {
+                    rule.pattern = rule.pattern.to_ascii_uppercase();
+                    Matcher::Literal(rule.pattern.clone())
+                }
+                PatternKind::Regex => {
+                    let regex = Regex::new(&format!("^(?:{})", rule.pattern)).map_err(|error| {
+                        RfastaError::invalid_input(
+                            "clean",
+                            format!("invalid correction pattern `{}`: {error}", rule.pattern),
+                            "Correction regexes use Rust `regex` syntax and are matched against uppercased sequences.",
+                        )
+                    })?;
+                    Matcher::Regex(regex)
+                }
+            });
+        }
+        Ok(Self { rules, matchers })
+    }
+
+    /// Builds literal rules from a correction dictionary.
+    ///
+    /// All rules share priority 0, so overlapping keys resolve longest-match-first regardless of
+    /// the dictionary's iteration order. Empty keys are ignored.
+    pub fn from_dictionary(dictionary: &HashMap<String, String>) -> Self {
+        let mut rules: Vec<CorrectionRule> = dictionary
+            .iter()
+            .filter(|(from, _)| !from.is_empty())
+            .map(|(from, to)| CorrectionRule::literal(from.as_str(), to.as_str()))
+            .collect();
+        // Sorting removes the hash order; distinct keys never tie at the same position.
+        rules.sort_by(|left, right| left.pattern.cmp(&right.pattern));
+        Self::new(rules).expect("non-empty literal rules always compile")
+    }
+
+    /// Returns the rules in evaluation order.
+    pub fn rules(&self) -> &[CorrectionRule] {
+        &self.rules
+    }
+
+    /// Applies the rules to `seq`.
+    pub fn apply(&self, seq: &str) -> String {
+        self.apply_with(seq, |_| {})
+    }
+
+    /// Applies the rules, calling `on_replace` with the pattern of every replacement made.
+    pub(crate) fn apply_with(&self, seq: &str, mut on_replace: impl FnMut(&str)) -> String {
+        let upper = seq.to_ascii_uppercase();
+        let mut converted = String::with_capacity(upper.len());
+        let mut position = 0;
+        while let Some(rest) = upper.get(position..).filter(|rest| !rest.is_empty()) {
+            let mut best: Option<(usize, usize)> = None;
+            for (index, matcher) in self.matchers.iter().enumerate() {
+                if let Some((best_index, _)) = best {
+                    if self.rules[index].priority < self.rules[best_index].priority {
+                        break;
+                    }
+                }
+                if let Some(len) = matcher.match_len(rest) {
+                    if best.is_none_or(|(_, best_len)| len > best_len) {
+                        best = Some((index, len));
+                    }
+                }
+            }
+            match best {
+                Some((index, len)) => {
+                    let rule = &self.rules[index];
+                    on_replace(&rule.pattern);
+                    converted.push_str(&rule.replacement);
+                    position += len;
+                }
+                None => {
+                    let residue = rest.chars().next().expect("rest is non-empty");
+                    converted.push(residue);
+                    position += residue.len_utf8();
+                }
+            }
+        }
+        converted
+    }
+}
+
+impl PartialEq for CorrectionRules {
+    fn eq(&self, other: &Self) -> bool {
+        self.rules == other.rules
+    }
+}
+
+impl Eq for CorrectionRules {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn overlapping_dictionary_keys_resolve_longest_first() {
+        let dictionary: HashMap<String, String> = [("X", "G"), ("XX", "A"), ("XXX", "W")]
+            .into_iter()
+            .map(|(from, to)| (from.to_string(), to.to_string()))
+            .collect();
+        let rules = CorrectionRules::from_dictionary(&dictionary);
+        assert_eq!(rules.apply("xxxxxMxx"), "WAMA");
+        assert_eq!(rules.apply("MXK"), "MGK");
+    }
+
+    #[test]
+    fn priority_beats_length_and_order_breaks_ties() {
+        let rules = CorrectionRules::new(vec![
+            CorrectionRule::literal("XX", "A"),
+            CorrectionRule::literal("X", "G").with_priority(1),
+            CorrectionRule::regex("B|Z", "N"),
+            CorrectionRule::literal("B", "D"),
+        ])
+        .unwrap();
+        assert_eq!(rules.apply("XXBZ"), "GGNN");
+    }
+
+    #[test]
+    fn replacements_do_not_cascade() {
+        let rules = CorrectionRules::new(vec![
+            CorrectionRule::literal("X", "Z"),
+            CorrectionRule::literal("Z", "Q"),
+        ])
+        .unwrap();
+        assert_eq!(rules.apply("XZ"), "ZQ");
+    }
+
+    #[test]
+    fn invalid_rules_are_rejected() {
+        assert!(CorrectionRules::new(vec![CorrectionRule::literal("", "A")]).is_err());
+        assert!(CorrectionRules::new(vec![CorrectionRule::regex("(", "A")]).is_err());
+    }
+}
@@ -87,6 +87,19 @@ pub enum RfastaError {
         /// User-facing recovery hint.
         hint: &'static str,
     },
+    /// FASTA record that cannot be described by a `.fai` index entry.
+    UnindexableRecord {
+        /// Path being indexed, when known.
+        path: Option<PathBuf>,
+        /// Sequence name of the offending record.
+        name: String,
+        /// 1-based line number where indexing failed.
+        line_number: usize,
+        /// Specific indexing failure message.
+        message: String,
+        /// User-facing recovery hint.
+        hint: &'static str,
+    },
     /// General invalid input error for a high-level operation.
     InvalidInput {
         /// High-level operation being attempted.
@@ -170,6 +183,22 @@ impl RfastaError {
         }
     }
 
+    pub fn unindexable_record(
+        path: Option<&Path>,
+        name: impl Into<String>,
+        line_number: usize,
+        message: impl Into<String>,
+        hint: &'static str,
+    ) -> Self {
+        Self::UnindexableRecord {
+            path: path.map(Path::to_path_buf),
+            name: name.into(),
+            line_number,
+            message: message.into(),
+            hint,
+        }
+    }
+
     pub fn invalid_chunk_count(chunks: usize, hint: &'static str) -> Self {
         Self::InvalidChunkCount { chunks, hint }
     }
@@ -246,6 +275,22 @@ impl fmt::Display for RfastaError {
             Self::InvalidRecord { message, hint } => {
                 write!(f, "rfasta record error: {message}\nhelp: {hint}")
             }
+            Self::UnindexableRecord {
+                path,
+                name,
+                line_number,
+                message,
+                hint,
+            } => {
+                write!(f, "rfasta index error")?;
+                if let Some(path) = path {
+                    write!(f, " for {}", path.display())?;
+                }
+                write!(
+                    f,
+                    " at line {line_number}: record `{name}` cannot be indexed: {message}\nhelp: {hint}"
+                )
+            }
             Self::InvalidInput {
                 operation,
                 message,
@@ -0,0 +1,386 @@
+use std::collections::HashSet;
+use std::fs;
+use std::io::{BufRead, Write};
+use std::path::{Path, PathBuf};
+
+use clap::ValueEnum;
+
+use crate::compression::COMPRESSED_EXTENSIONS;
+use crate::errors::RfastaError;
+use crate::grouping::sanitize_file_stem;
+use crate::io::{
+    open_fasta_input, visit_fasta_file, visit_fasta_reader_with_source, FastaRecord, FastaWriter,
+    ParseOptions, WriteOptions,
+};
+use crate::sharding::{fasta_extension, header_accession};
+
+/// File extensions, after any compression suffix, that [`list_fasta_files`] treats as FASTA.
+const FASTA_EXTENSIONS: [&str; 5] = ["fasta", "fa", "faa", "fas", "fna"];
+
+/// File stem used for records whose name is empty after sanitizing.
+const UNNAMED_RECORD_STEM: &str = "record";
+
+/// Part of a record used to name its file in [`explode_fasta_file`].
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, ValueEnum)]
+pub enum RecordName {
+    /// The UniProt accession in `db|ACCESSION|NAME` headers, otherwise the first header word.
+    #[default]
+    Accession,
+    /// The full header.
+    Header,
+}
+
+/// Options for [`explode_fasta_file`].
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct ExplodeOptions {
+    /// Part of each record used for its file name.
+    pub name_by: RecordName,
+    /// Largest number of entries allowed in one directory. When set, files are spread over nested
+    /// numbered subdirectories, `000/001/P69905.fasta`, deep enough to respect the limit. `None`
+    /// writes every file directly into the output directory.
+    pub max_per_dir: Option<usize>,
+}
+
+/// Writes every record of a FASTA file to its own single-record file in `output_dir`.
+///
+/// Files are named `{name}.fasta` from each record's accession or header, with characters other
+/// than ASCII letters, digits, `.`, `_`, and `-` replaced by `_`. When two records map to the same
+/// name, ignoring case so that case-insensitive filesystems are safe, later records get `_2`,
+/// `_3`, and so on. With [`ExplodeOptions::max_per_dir`], the input is read once to count records
+/// before files are written. Returns the number of files written.
+///
+/// # Example
+/// ```no_run
+/// use rfasta::shard::{explode_fasta_file, ExplodeOptions};
+/// use rfasta::write::WriteOptions;
+///
+/// let options = ExplodeOptions {
+///     max_per_dir: Some(1000),
+///     ..ExplodeOptions::default()
+/// };
+/// explode_fasta_file("targets.fasta", "jobs", &options, &WriteOptions::default(), true)?;
+/// # Ok::<(), rfasta::RfastaError>(())
+/// ```
+pub fn explode_fasta_file<P: AsRef<Path>, Q: AsRef<Path>>(
+    input_path: P,
+    output_dir: Q,
+    explode: &ExplodeOptions,
+    options: &WriteOptions,
+    verbose: bool,
+) -> Result<usize, RfastaError> {
+    let input_path = input_path.as_ref();
+    let parse_options = ParseOptions {
+        expect_unique_header: false,
+        part: None,
+    };
+    let layout = match explode.max_per_dir {
+        Some(max_per_dir) => {
+            let records = visit_fasta_file(input_path, parse_options, false, |_| Ok(()))?;
+            Some(FanOut::new(max_per_dir, records)?)
+        }
+        None => None,
+    };
+    let mut reader = open_fasta_input(input_path)?;
+    explode_reader(
+        &mut reader,
+        Some(input_path),
+        output_dir.as_ref(),
+        explode.name_by,
+        layout,
+        options,
+        verbose,
+    )
+}
+
+/// Writes every record from any buffered reader to its own file in `output_dir`.
+///
+/// This is the reader-based counterpart to [`explode_fasta_file`]. A reader cannot be counted in
+/// advance, so [`ExplodeOptions::max_per_dir`] is rejected here.
+pub fn explode_fasta_reader<R: BufRead, Q: AsRef<Path>>(
+    mut reader: R,
+    output_dir: Q,
+    explode: &ExplodeOptions,
+    options: &WriteOptions,
+    verbose: bool,
+) -> Result<usize, RfastaError> {
+    if explode.max_per_dir.is_some() {
+        return Err(RfastaError::invalid_input(
+            "explode",
+            "a per-directory limit needs the record count before writing",
+            "Explode a file path instead of a stream, or leave max_per_dir unset.",
+        ));
+    }
+    explode_reader(
+        &mut reader,
+        None,
+        output_dir.as_ref(),
+        explode.name_by,
+        None,
+        options,
+        verbose,
+    )
+}
+
+/// Nested numbered directories holding at most `max_per_dir` entries each.
+#[derive(Clone, Copy)]
+struct FanOut {
+    max_per_dir: usize,
+    /// Number of directory levels between the output directory and the files.
+    depth: u32,
+    /// Zero-padded width of each directory name.
+    width: usize,
+}
+
+impl FanOut {
+    fn new(max_per_dir: usize, records: usize) -> Result<Self, RfastaError> {
+        if max_per_dir < 2 {
+            return Err(RfastaError::invalid_input(
+                "explode",
+                format!("cannot limit directories to {max_per_dir} entries"),
+                "Pass a per-directory limit of at least 2.",
+            ));
+        }
+        let mut depth = 0;
+        let mut capacity = max_per_dir;
+        while capacity < records {
+            capacity = capacity.saturating_mul(max_per_dir);
+            depth += 1;
+        }
+        Ok(Self {
+            max_per_dir,
+            depth,
+            width: (max_per_dir - 1).to_string().len(),
+        })
+    }
+
+    /// Returns the directory, relative to the output directory, of the `index`-th record.
+    fn directory(&self, index: usize) -> PathBuf {
+        let leaf = index / self.max_per_dir;
+        (0..self.depth)
+            .rev()
+            .map(|level| {
+                let digit = leaf / self.max_per_dir.pow(level) % self.max_per_dir;
+                format!("{digit:0width$}", width = self.width)
+            })
+            .collect()
+    }
+}
+
+fn explode_reader<R: BufRead>(
+    reader: &mut R,
+    source_name: Option<&Path>,
+    output_dir: &Path,
+    name_by: RecordName,
+    layout: Option<FanOut>,
+    options: &WriteOptions,
+    verbose: bool,
+) -> Result<usize, RfastaError> {
+    let extension = fasta_extension(options);
+    let mut used_names = HashSet::new();
+    let mut created_dirs = HashSet::new();
+    let mut files_written = 0;
+
+    visit_fasta_reader_with_source(
+        reader,
+        ParseOptions {
+            expect_unique_header: false,
+            part: None,
+        },
+        source_name,
+        &mut |record| {
+            let dir = match layout {
+                Some(layout) => output_dir.join(layout.directory(files_written)),
+                None => output_dir.to_path_buf(),
+            };
+            if created_dirs.insert(dir.clone()) {
+                fs::create_dir_all(&dir).map_err(|source| {
+                    RfastaError::io(
+                        "explode",
+                        Some(&dir),
+                        source,
+                        "Check that the output directory is writable.",
+                    )
+                })?;
+            }
+            let name = unique_record_name(&record, name_by, &mut used_names);
+            let mut writer = FastaWriter::create(dir.join(format!("{name}.{extension}")), options)?;
+            writer.write_record(&record)?;
+            writer.finish()?;
+            files_written += 1;
+            Ok(())
+        },
+    )?;
+
+    if verbose {
+        eprintln!(
+            "[INFO]: Wrote {files_written} single-sequence files to {}",
+            output_dir.display()
+        );
+    }
+    Ok(files_written)
+}
+
+/// Returns a sanitized file stem for `record` that no earlier record has used, ignoring case.
+fn unique_record_name(
+    record: &FastaRecord,
+    name_by: RecordName,
+    used_names: &mut HashSet<String>,
+) -> String {
+    let source = match name_by {
+        RecordName::Accession => header_accession(&record.header),
+        RecordName::Header => record.header.as_str(),
+    };
+    let mut base = sanitize_file_stem(source);
+    if base.is_empty() {
+        base = UNNAMED_RECORD_STEM.to_string();
+    }
+    let mut name = base.clone();
+    let mut suffix = 1;
+    while !used_names.insert(name.to_ascii_lowercase()) {
+        suffix += 1;
+        name = format!("{base}_{suffix}");
+    }
+    name
+}
+
+/// Returns every FASTA file under `dir`, recursively, in path order.
+///
+/// Files count as FASTA when their extension, after an optional `.gz`, `.bgz`, `.zst`, or `.xz`
+/// suffix, is `fasta`, `fa`, `faa`, `fas`, or `fna`. Other files, such as manifests, are skipped.
+pub fn list_fasta_files<P: AsRef<Path>>(dir: P) -> Result<Vec<PathBuf>, RfastaError> {
+    let mut files = Vec::new();
+    let mut pending = vec![dir.as_ref().to_path_buf()];
+    while let Some(dir) = pending.pop() {
+        let entries = fs::read_dir(&dir).map_err(|source| {
+            RfastaError::io(
+                "gather",
+                Some(&dir),
+                source,
+                "Check that the directory exists and is readable.",
+            )
+        })?;
+        for entry in entries {
+            let path = entry
+                .and_then(|entry| entry.file_type().map(|kind| (entry.path(), kind)))
+                .map_err(|source| {
+                    RfastaError::io(
+                        "gather",
+                        Some(&dir),
+                        source,
+                        "Check that the directory is readable.",
+                    )
+                })?;
+            match path {
+                (path, kind) if kind.is_dir() => pending.push(path),
+                (path, _) if is_fasta_path(&path) => files.push(path),
+                _ => {}
+            }
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+fn is_fasta_path(path: &Path) -> bool {
+    let Some(mut name) = path.file_name().and_then(|name| name.to_str()) else {
+        return false;
+    };
+    if let Some((rest, extension)) = name.rsplit_once('.') {
+        if COMPRESSED_EXTENSIONS
+            .iter()
+            .any(|known| known.eq_ignore_ascii_case(extension))
+        {
+            name = rest;
+        }
+    }
+    name.rsplit_once('.').is_some_and(|(_, extension)| {
+        FASTA_EXTENSIONS
+            .iter()
+            .any(|known| known.eq_ignore_ascii_case(extension))
+    })
+}
+
+/// Concatenates the records of `paths`, in order, into `writer`.
+///
+/// This is the inverse of [`explode_fasta_file`] when combined with [`list_fasta_files`]. Records
+/// are re-wrapped to the writer's line length, compressed inputs are decompressed, and repeated
+/// headers across files are kept. Returns the number of records written.
+///
+/// # Example
+/// ```no_run
+/// use rfasta::shard::{gather_fasta_files, list_fasta_files};
+/// use rfasta::write::{FastaWriter, WriteOptions};
+///
+/// let files = list_fasta_files("jobs")?;
+/// let mut writer = FastaWriter::create("combined.fasta", &WriteOptions::default())?;
+/// gather_fasta_files(&files, &mut writer, true)?;
+/// writer.finish()?;
+/// # Ok::<(), rfasta::RfastaError>(())
+/// ```
+pub fn gather_fasta_files<P: AsRef<Path>, W: Write>(
+    paths: &[P],
+    writer: &mut FastaWriter<W>,
+    verbose: bool,
+) -> Result<usize, RfastaError> {
+    let mut records = 0;
+    for path in paths {
+        records += visit_fasta_file(
+            path,
+            ParseOptions {
+                expect_unique_header: false,
+                part: None,
+            },
+            false,
+            |record| writer.write_record(&record),
+        )?;
+    }
+    if verbose {
+        eprintln!(
+            "[INFO]: Gathered {records} sequences from {} files",
+            paths.len()
+        );
+    }
+    Ok(records)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fan_out_keeps_every_directory_within_the_limit() {
+        let layout = FanOut::new(10, 1000).unwrap();
+        assert_eq!(layout.depth, 2);
+        assert_eq!(layout.directory(0), PathBuf::from("0/0"));
+        assert_eq!(layout.directory(999), PathBuf::from("9/9"));
+        assert_eq!(layout.directory(123), PathBuf::from("1/2"));
+        assert_eq!(FanOut::new(1000, 1000).unwrap().depth, 0);
+        assert_eq!(
+            FanOut::new(100, 1001).unwrap().directory(1000),
+            PathBuf::from("10")
+        );
+        assert!(FanOut::new(1, 10).is_err());
+    }
+
+    #[test]
+    fn record_names_are_sanitized_and_unique_ignoring_case() {
+        let mut used = HashSet::new();
+        let names: Vec<String> = ["sp|P69905|HBA_HUMAN", "p69905 other", "a/b c", ""]
+            .into_iter()
+            .map(|header| {
+                unique_record_name(
+                    &FastaRecord::new(header, "M"),
+                    RecordName::Accession,
+                    &mut used,
+                )
+            })
+            .collect();
+        assert_eq!(names, vec!["P69905", "p69905_2", "a_b", "record"]);
+        let header = unique_record_name(
+            &FastaRecord::new("a/b c", "M"),
+            RecordName::Header,
+            &mut used,
+        );
+        assert_eq!(header, "a_b_c");
+    }
+}
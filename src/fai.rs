@@ -0,0 +1,565 @@
+use std::collections::HashMap;
+use std::ffi::OsString;
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+
+use crate::compression::InputCompression;
+use crate::errors::RfastaError;
+use crate::io::FastaRecord;
+
+/// One line of a samtools-compatible `.fai` index.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct FaiEntry {
+    /// Sequence name: the header text up to the first whitespace.
+    pub name: String,
+    /// Total number of residues in the sequence.
+    pub length: u64,
+    /// Byte offset of the first residue in the FASTA file.
+    pub offset: u64,
+    /// Residues per full sequence line.
+    pub line_bases: u64,
+    /// Bytes per full sequence line, including the line terminator.
+    pub line_width: u64,
+}
+
+impl FaiEntry {
+    /// Returns the file offset of the residue at 0-based `position`.
+    fn byte_offset(&self, position: u64) -> u64 {
+        if self.line_bases == 0 {
+            return self.offset;
+        }
+        self.offset + (position / self.line_bases) * self.line_width + position % self.line_bases
+    }
+}
+
+/// In-memory `.fai` index with name lookup.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct FastaIndex {
+    entries: Vec<FaiEntry>,
+    positions: HashMap<String, usize>,
+}
+
+impl FastaIndex {
+    /// Scans a plain-text FASTA file and builds its index.
+    ///
+    /// Every record must use a uniform line width: all sequence lines except the last must contain
+    /// the same number of residues, and the last line may not be longer. Records that break this
+    /// rule, duplicate names, and compressed inputs are rejected.
+    pub fn build<P: AsRef<Path>>(path: P) -> Result<Self, RfastaError> {
+        let path = path.as_ref();
+        let file = File::open(path).map_err(|source| {
+            RfastaError::io(
+                "index",
+                Some(path),
+                source,
+                "Check that the input file exists and is readable.",
+            )
+        })?;
+        let mut reader = BufReader::new(file);
+        let prefix = reader.fill_buf().map_err(|source| {
+            RfastaError::io(
+                "index",
+                Some(path),
+                source,
+                "Check that the input file exists and is readable.",
+            )
+        })?;
+        if InputCompression::detect(prefix) != InputCompression::None {
+            return Err(RfastaError::invalid_input(
+                "index",
+                format!("{} is compressed", path.display()),
+                "Decompress the FASTA before indexing; .fai offsets refer to uncompressed bytes.",
+            ));
+        }
+
+        let mut builder = IndexBuilder::new(path);
+        let mut line = Vec::new();
+        loop {
+            line.clear();
+            let bytes_read = reader.read_until(b'\n', &mut line).map_err(|source| {
+                RfastaError::io(
+                    "index",
+                    Some(path),
+                    source,
+                    "Check that the input file exists and is readable.",
+                )
+            })?;
+            if bytes_read == 0 {
+                break;
+            }
+            builder.push_line(&line)?;
+        }
+        Ok(builder.finish())
+    }
+
+    /// Reads an existing `.fai` file.
+    pub fn read<P: AsRef<Path>>(fai_path: P) -> Result<Self, RfastaError> {
+        let fai_path = fai_path.as_ref();
+        let file = File::open(fai_path).map_err(|source| {
+            RfastaError::io(
+                "index",
+                Some(fai_path),
+                source,
+                "Check that the index exists, or build it with `rfasta faidx`.",
+            )
+        })?;
+
+        let mut index = Self::default();
+        for (line_index, line) in BufReader::new(file).lines().enumerate() {
+            let line = line.map_err(|source| {
+                RfastaError::io(
+                    "index",
+                    Some(fai_path),
+                    source,
+                    "Check that the index file is readable.",
+                )
+            })?;
+            if line.is_empty() {
+                continue;
+            }
+            let entry = parse_fai_line(&line).ok_or_else(|| {
+                RfastaError::invalid_input(
+                    "index",
+                    format!(
+                        "line {} of {} is not a valid .fai entry",
+                        line_index + 1,
+                        fai_path.display()
+                    ),
+                    "Rebuild the index with `rfasta faidx`.",
+                )
+            })?;
+            index.push(entry);
+        }
+        Ok(index)
+    }
+
+    /// Writes the index in `.fai` format.
+    pub fn write<P: AsRef<Path>>(&self, fai_path: P) -> Result<(), RfastaError> {
+        let fai_path = fai_path.as_ref();
+        let file = File::create(fai_path).map_err(|source| {
+            RfastaError::io(
+                "index",
+                Some(fai_path),
+                source,
+                "Check that the index path is writable and that parent directories exist.",
+            )
+        })?;
+        self.write_entries(&mut BufWriter::new(file), Some(fai_path))
+    }
+
+    /// Writes the index in `.fai` format to any writer.
+    pub fn write_to<W: Write>(&self, writer: &mut W) -> Result<(), RfastaError> {
+        self.write_entries(writer, None)
+    }
+
+    fn write_entries<W: Write>(
+        &self,
+        writer: &mut W,
+        path: Option<&Path>,
+    ) -> Result<(), RfastaError> {
+        let write_error = |source| {
+            RfastaError::io(
+                "index",
+                path,
+                source,
+                "Check that the index output is writable and has enough free space.",
+            )
+        };
+        for entry in &self.entries {
+            writeln!(
+                writer,
+                "{}\t{}\t{}\t{}\t{}",
+                entry.name, entry.length, entry.offset, entry.line_bases, entry.line_width
+            )
+            .map_err(write_error)?;
+        }
+        writer.flush().map_err(write_error)
+    }
+
+    /// Returns all entries in file order.
+    pub fn entries(&self) -> &[FaiEntry] {
+        &self.entries
+    }
+
+    /// Looks up an entry by sequence name.
+    pub fn get(&self, name: &str) -> Option<&FaiEntry> {
+        self.positions.get(name).map(|&index| &self.entries[index])
+    }
+
+    /// Returns the number of indexed records.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns `true` when the index contains no records.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    fn push(&mut self, entry: FaiEntry) {
+        self.positions
+            .insert(entry.name.clone(), self.entries.len());
+        self.entries.push(entry);
+    }
+}
+
+fn parse_fai_line(line: &str) -> Option<FaiEntry> {
+    let mut fields = line.split('\t');
+    let entry = FaiEntry {
+        name: fields.next()?.to_string(),
+        length: fields.next()?.parse().ok()?,
+        offset: fields.next()?.parse().ok()?,
+        line_bases: fields.next()?.parse().ok()?,
+        line_width: fields.next()?.parse().ok()?,
+    };
+    (!entry.name.is_empty()).then_some(entry)
+}
+
+/// Record currently being scanned by [`IndexBuilder`].
+struct PendingEntry {
+    entry: FaiEntry,
+    /// Set once a short or blank line ends the fixed-width block of the record.
+    closed: bool,
+}
+
+struct IndexBuilder<'a> {
+    path: &'a Path,
+    index: FastaIndex,
+    pending: Option<PendingEntry>,
+    offset: u64,
+    line_number: usize,
+}
+
+impl<'a> IndexBuilder<'a> {
+    fn new(path: &'a Path) -> Self {
+        Self {
+            path,
+            index: FastaIndex::default(),
+            pending: None,
+            offset: 0,
+            line_number: 0,
+        }
+    }
+
+    fn error(&self, name: &str, message: impl Into<String>) -> RfastaError {
+        RfastaError::unindexable_record(
+            Some(self.path),
+            name,
+            self.line_number,
+            message,
+            "Rewrap the FASTA with a fixed line length, for example with `rfasta clean --number-lines 60`.",
+        )
+    }
+
+    fn push_line(&mut self, line: &[u8]) -> Result<(), RfastaError> {
+        self.line_number += 1;
+        let line_start = self.offset;
+        self.offset += line.len() as u64;
+
+        let has_newline = line.ends_with(b"\n");
+        let content = line.strip_suffix(b"\n").unwrap_or(line);
+        let content = content.strip_suffix(b"\r").unwrap_or(content);
+
+        if let Some(header) = content.strip_prefix(b">") {
+            self.finish_pending();
+            let name_end = header
+                .iter()
+                .position(u8::is_ascii_whitespace)
+                .unwrap_or(header.len());
+            let name = String::from_utf8_lossy(&header[..name_end]).into_owned();
+            if name.is_empty() {
+                return Err(self.error(&name, "header has no sequence name"));
+            }
+            if self.index.get(&name).is_some() {
+                return Err(self.error(&name, "duplicate sequence name"));
+            }
+            self.pending = Some(PendingEntry {
+                entry: FaiEntry {
+                    name,
+                    length: 0,
+                    offset: self.offset,
+                    line_bases: 0,
+                    line_width: 0,
+                },
+                closed: false,
+            });
+            return Ok(());
+        }
+
+        let Some(pending) = self.pending.as_mut() else {
+            if content.iter().all(u8::is_ascii_whitespace) {
+                return Ok(());
+            }
+            return Err(RfastaError::malformed_fasta(
+                Some(self.path),
+                self.line_number,
+                "found sequence data before the first FASTA header",
+                "Make sure the file starts with a header line beginning with `>`.",
+            ));
+        };
+
+        let bases = content.len() as u64;
+        let width = line.len() as u64;
+        if bases == 0 {
+            if pending.entry.line_bases == 0 {
+                // Blank lines between the header and the first residue shift the record start.
+                pending.entry.offset = self.offset;
+            } else {
+                pending.closed = true;
+            }
+            return Ok(());
+        }
+
+        if pending.closed {
+            let name = pending.entry.name.clone();
+            return Err(self.error(
+                &name,
+                "sequence continues after a shorter or blank line; every line except the last must have the same width",
+            ));
+        }
+
+        let entry = &mut pending.entry;
+        if entry.line_bases == 0 {
+            entry.offset = line_start;
+            entry.line_bases = bases;
+            entry.line_width = if has_newline { width } else { bases + 1 };
+        } else if bases > entry.line_bases {
+            let message = format!(
+                "line has {bases} residues but earlier lines have {}",
+                entry.line_bases
+            );
+            let name = entry.name.clone();
+            return Err(self.error(&name, message));
+        } else if has_newline && width - bases != entry.line_width - entry.line_bases {
+            let name = entry.name.clone();
+            return Err(self.error(&name, "record mixes line terminators"));
+        } else if bases < entry.line_bases {
+            pending.closed = true;
+        }
+        pending.entry.length += bases;
+        Ok(())
+    }
+
+    fn finish_pending(&mut self) {
+        if let Some(pending) = self.pending.take() {
+            self.index.push(pending.entry);
+        }
+    }
+
+    fn finish(mut self) -> FastaIndex {
+        self.finish_pending();
+        self.index
+    }
+}
+
+/// Returns the conventional index path for a FASTA file: the FASTA path with `.fai` appended.
+pub fn fai_path<P: AsRef<Path>>(fasta_path: P) -> PathBuf {
+    let mut path = OsString::from(fasta_path.as_ref());
+    path.push(".fai");
+    PathBuf::from(path)
+}
+
+/// Builds a `.fai` index for a FASTA file and writes it next to the input.
+///
+/// The index is written to [`fai_path`] of the input and is compatible with `samtools faidx` and
+/// `pyfaidx`.
+///
+/// # Example
+/// ```no_run
+/// use rfasta::index::index_fasta_file;
+///
+/// let index = index_fasta_file("proteins.fasta", true)?;
+/// println!("Indexed {} records", index.len());
+/// # Ok::<(), rfasta::RfastaError>(())
+/// ```
+pub fn index_fasta_file<P: AsRef<Path>>(path: P, verbose: bool) -> Result<FastaIndex, RfastaError> {
+    let path = path.as_ref();
+    let index = FastaIndex::build(path)?;
+    let output = fai_path(path);
+    index.write(&output)?;
+    if verbose {
+        eprintln!(
+            "[INFO]: Indexed {} sequences into {}",
+            index.len(),
+            output.display()
+        );
+    }
+    Ok(index)
+}
+
+/// Random-access reader over an indexed FASTA file.
+///
+/// Fetches seek directly to the requested residues, so lookups cost one seek and one read
+/// regardless of file size. Returned sequences are uppercased to match the parser.
+///
+/// # Example
+/// ```no_run
+/// use rfasta::index::IndexedFasta;
+///
+/// let mut fasta = IndexedFasta::open("proteins.fasta")?;
+/// let record = fasta.fetch("sp|P69905|HBA_HUMAN")?;
+/// let motif = fasta.fetch_range("sp|P69905|HBA_HUMAN", 0, 10)?;
+/// assert!(record.sequence.starts_with(&motif));
+/// # Ok::<(), rfasta::RfastaError>(())
+/// ```
+pub struct IndexedFasta {
+    path: PathBuf,
+    reader: BufReader<File>,
+    index: FastaIndex,
+}
+
+impl IndexedFasta {
+    /// Opens a FASTA file, loading its `.fai` index or building one in memory when none exists.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, RfastaError> {
+        let path = path.as_ref();
+        let index_path = fai_path(path);
+        let index = if index_path.exists() {
+            FastaIndex::read(index_path)?
+        } else {
+            FastaIndex::build(path)?
+        };
+        Self::with_index(path, index)
+    }
+
+    /// Opens a FASTA file using an already loaded index.
+    pub fn with_index<P: AsRef<Path>>(path: P, index: FastaIndex) -> Result<Self, RfastaError> {
+        let path = path.as_ref().to_path_buf();
+        let file = File::open(&path).map_err(|source| {
+            RfastaError::io(
+                "fetch",
+                Some(&path),
+                source,
+                "Check that the input file exists and is readable.",
+            )
+        })?;
+        Ok(Self {
+            path,
+            reader: BufReader::new(file),
+            index,
+        })
+    }
+
+    /// Returns the index backing this reader.
+    pub fn index(&self) -> &FastaIndex {
+        &self.index
+    }
+
+    /// Fetches a full record by name. The returned header is the indexed sequence name.
+    pub fn fetch(&mut self, name: &str) -> Result<FastaRecord, RfastaError> {
+        let length = self.entry(name)?.length;
+        let sequence = self.fetch_range(name, 0, length)?;
+        Ok(FastaRecord::new(name, sequence))
+    }
+
+    /// Fetches residues `start..end` (0-based, end-exclusive) of the named record.
+    pub fn fetch_range(&mut self, name: &str, start: u64, end: u64) -> Result<String, RfastaError> {
+        let entry = self.entry(name)?;
+        if start > end || end > entry.length {
+            return Err(RfastaError::invalid_input(
+                "fetch",
+                format!(
+                    "range {start}..{end} is outside sequence `{name}` of length {}",
+                    entry.length
+                ),
+                "Request a range within the sequence length reported by the .fai index.",
+            ));
+        }
+        if start == end {
+            return Ok(String::new());
+        }
+
+        let first = entry.byte_offset(start);
+        let last = entry.byte_offset(end - 1);
+        let mut raw = vec![0u8; (last - first + 1) as usize];
+        let read_error = |source| {
+            RfastaError::io(
+                "fetch",
+                Some(&self.path),
+                source,
+                "Check that the FASTA has not changed since it was indexed; rebuild the .fai if it has.",
+            )
+        };
+        self.reader
+            .seek(SeekFrom::Start(first))
+            .and_then(|_| self.reader.read_exact(&mut raw))
+            .map_err(read_error)?;
+
+        raw.retain(|byte| !matches!(byte, b'\n' | b'\r'));
+        raw.make_ascii_uppercase();
+        String::from_utf8(raw).map_err(|_| {
+            RfastaError::invalid_input(
+                "fetch",
+                format!("sequence `{name}` contains non-UTF-8 bytes"),
+                "Check that the FASTA has not changed since it was indexed; rebuild the .fai if it has.",
+            )
+        })
+    }
+
+    fn entry(&self, name: &str) -> Result<FaiEntry, RfastaError> {
+        self.index.get(name).cloned().ok_or_else(|| {
+            RfastaError::invalid_input(
+                "fetch",
+                format!("sequence `{name}` is not in the index"),
+                "Use the sequence name up to the first whitespace in the header, as listed in the .fai file.",
+            )
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn build_from_bytes(bytes: &[u8]) -> Result<FastaIndex, RfastaError> {
+        let mut builder = IndexBuilder::new(Path::new("test.fasta"));
+        for line in bytes.split_inclusive(|byte| *byte == b'\n') {
+            builder.push_line(line)?;
+        }
+        Ok(builder.finish())
+    }
+
+    #[test]
+    fn builder_records_offsets_and_line_geometry() {
+        let index = build_from_bytes(b">seq1 desc\nACGT\nAC\n\n>seq2\nTTTTT\r\nTT\r\n").unwrap();
+        assert_eq!(
+            index.entries(),
+            &[
+                FaiEntry {
+                    name: "seq1".to_string(),
+                    length: 6,
+                    offset: 11,
+                    line_bases: 4,
+                    line_width: 5,
+                },
+                FaiEntry {
+                    name: "seq2".to_string(),
+                    length: 7,
+                    offset: 26,
+                    line_bases: 5,
+                    line_width: 7,
+                },
+            ]
+        );
+        assert_eq!(index.get("seq2").unwrap().byte_offset(5), 33);
+    }
+
+    #[test]
+    fn builder_rejects_non_uniform_lines() {
+        let error = build_from_bytes(b">seq1\nACG\nACGT\n").unwrap_err();
+        assert!(matches!(
+            error,
+            RfastaError::UnindexableRecord { line_number: 3, .. }
+        ));
+
+        let error = build_from_bytes(b">seq1\nACGT\nAC\nACGT\n").unwrap_err();
+        assert!(error.to_string().contains("seq1"));
+
+        let error = build_from_bytes(b">seq1\nACGT\n>seq1\nACGT\n").unwrap_err();
+        assert!(matches!(
+            error,
+            RfastaError::UnindexableRecord { line_number: 3, .. }
+        ));
+        assert!(error.to_string().contains("duplicate"));
+    }
+}
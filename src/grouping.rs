@@ -0,0 +1,310 @@
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::io::BufRead;
+use std::path::Path;
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use crate::errors::RfastaError;
+use crate::io::{
+    input_stem, open_fasta_input, tsv_field, visit_fasta_reader_with_source, FastaRecord,
+    ParseOptions,
+};
+use crate::manifest::{ShardEntry, ShardManifest};
+use crate::shard_pool::HandlePool;
+use crate::sharding::{
+    prepare_output_dir, split_reader, ShardStrategy, ShardWriter, SplitOptions, DEFAULT_SHARD_STEM,
+};
+
+/// File name of the group-to-file table written by [`split_fasta_file_by_key`].
+pub const GROUP_TABLE_FILE_NAME: &str = "groups.tsv";
+
+/// File name suffix used for records whose key cannot be extracted.
+const UNMATCHED_GROUP_NAME: &str = "unmatched";
+
+/// Longest name, in bytes, used as a file stem before truncation.
+const MAX_FILE_STEM_LEN: usize = 100;
+
+/// How [`split_fasta_file_by_key`] derives a record's group.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GroupKey {
+    /// A regular expression matched against the header. The first capture group is the key, or
+    /// the whole match when the pattern has no groups.
+    HeaderRegex(String),
+    /// A UniProt `FIELD=value` header entry such as `OX` (taxonomy ID), `OS` (organism), or `GN`
+    /// (gene name). The value runs to the next ` XX=` entry, so multi-word organisms stay whole.
+    UniprotField(String),
+    /// Sequence length bucketed by this width, e.g. `100` gives `0-99`, `100-199`, and so on.
+    LengthBucket(usize),
+}
+
+impl GroupKey {
+    fn compile(&self) -> Result<KeyExtractor, RfastaError> {
+        let pattern = match self {
+            Self::HeaderRegex(pattern) => pattern.clone(),
+            Self::UniprotField(field) => {
+                if field.is_empty() || !field.chars().all(|c| c.is_ascii_alphanumeric()) {
+                    return Err(RfastaError::invalid_input(
+                        "split",
+                        format!("invalid UniProt header field `{field}`"),
+                        "Pass a field name such as OX, OS, or GN.",
+                    ));
+                }
+                format!(r"(?:^|\s){field}=(.*?)(?:\s[A-Z]{{2}}=|$)")
+            }
+            &Self::LengthBucket(0) => {
+                return Err(RfastaError::invalid_input(
+                    "split",
+                    "length buckets must be at least 1 residue wide",
+                    "Pass a bucket width greater than zero.",
+                ))
+            }
+            &Self::LengthBucket(width) => return Ok(KeyExtractor::Length(width)),
+        };
+        let regex = Regex::new(&pattern).map_err(|error| {
+            RfastaError::invalid_input(
+                "split",
+                format!("invalid group pattern `{pattern}`: {error}"),
+                "Group regexes use Rust `regex` syntax and are matched against the header.",
+            )
+        })?;
+        Ok(KeyExtractor::Header(regex))
+    }
+}
+
+enum KeyExtractor {
+    Header(Regex),
+    Length(usize),
+}
+
+impl KeyExtractor {
+    fn extract(&self, record: &FastaRecord) -> Option<String> {
+        match self {
+            Self::Header(regex) => {
+                let captures = regex.captures(&record.header)?;
+                let found = captures.get(1).or_else(|| captures.get(0))?;
+                Some(found.as_str().trim().to_string()).filter(|key| !key.is_empty())
+            }
+            Self::Length(width) => {
+                let low = record.sequence.len() / width * width;
+                Some(format!("{low}-{}", low + width - 1))
+            }
+        }
+    }
+}
+
+/// Streams a FASTA file into one file per group, `{stem}_{group}.fasta`.
+///
+/// Each record's group comes from `key`; records without one go to `{stem}_unmatched.fasta`.
+/// Group names are made safe for file names by replacing anything other than ASCII letters,
+/// digits, `.`, `_`, and `-` with `_` and truncating long names, and groups that still collide get
+/// a numeric suffix. [`GROUP_TABLE_FILE_NAME`] maps every group to its file with a record count,
+/// and the manifest entries carry the same `group`. Returns the manifest.
+///
+/// Files are opened as new groups appear, subject to [`SplitOptions::max_open_files`], so a
+/// single pass handles thousands of organisms.
+///
+/// # Example
+/// ```no_run
+/// use rfasta::shard::{split_fasta_file_by_key, GroupKey, SplitOptions};
+///
+/// let key = GroupKey::UniprotField("OX".to_string());
+/// let manifest = split_fasta_file_by_key("uniprot.fasta", "by_taxon", &key, &SplitOptions::default(), true)?;
+/// println!("{} organisms", manifest.shards.len());
+/// # Ok::<(), rfasta::RfastaError>(())
+/// ```
+pub fn split_fasta_file_by_key<P: AsRef<Path>, Q: AsRef<Path>>(
+    input_path: P,
+    output_dir: Q,
+    key: &GroupKey,
+    options: &SplitOptions,
+    verbose: bool,
+) -> Result<ShardManifest, RfastaError> {
+    let input_path = input_path.as_ref();
+    let stem = input_stem(input_path).unwrap_or_else(|| DEFAULT_SHARD_STEM.to_string());
+    let mut reader = open_fasta_input(input_path)?;
+    split_reader(
+        &mut reader,
+        Some(input_path),
+        output_dir.as_ref(),
+        &stem,
+        &ShardStrategy::Keyed { key: key.clone() },
+        options,
+        verbose,
+    )
+}
+
+/// Streams FASTA records from any buffered reader into one file per group.
+///
+/// This is the reader-based counterpart to [`split_fasta_file_by_key`].
+pub fn split_fasta_reader_by_key<R: BufRead, Q: AsRef<Path>>(
+    mut reader: R,
+    output_dir: Q,
+    stem: &str,
+    key: &GroupKey,
+    options: &SplitOptions,
+    verbose: bool,
+) -> Result<ShardManifest, RfastaError> {
+    split_reader(
+        &mut reader,
+        None,
+        output_dir.as_ref(),
+        stem,
+        &ShardStrategy::Keyed { key: key.clone() },
+        options,
+        verbose,
+    )
+}
+
+/// Replaces characters that are unsafe in file names and bounds the length.
+pub(crate) fn sanitize_file_stem(group: &str) -> String {
+    let mut name: String = group
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '-') {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    name.truncate(MAX_FILE_STEM_LEN);
+    // A leading dot would hide the file, and `.` or `..` would not name a file at all.
+    if name.starts_with('.') {
+        name.replace_range(..1, "_");
+    }
+    name
+}
+
+pub(crate) fn split_reader_keyed<R: BufRead>(
+    reader: &mut R,
+    source_name: Option<&Path>,
+    output_dir: &Path,
+    stem: &str,
+    key: &GroupKey,
+    options: &SplitOptions,
+    verbose: bool,
+) -> Result<Vec<ShardEntry>, RfastaError> {
+    let extractor = key.compile()?;
+    let extension = prepare_output_dir(output_dir, &options.write)?;
+    let pool = HandlePool::shared(options.max_open_files, &options.write);
+    let mut writer_of: HashMap<Option<String>, usize> = HashMap::new();
+    let mut groups: Vec<(Option<String>, ShardWriter)> = Vec::new();
+    let mut used_names = HashSet::new();
+
+    visit_fasta_reader_with_source(
+        reader,
+        ParseOptions {
+            expect_unique_header: false,
+            part: None,
+        },
+        source_name,
+        &mut |record| {
+            let group = extractor.extract(&record);
+            let index = match writer_of.get(&group) {
+                Some(&index) => index,
+                None => {
+                    let base = match &group {
+                        Some(group) => sanitize_file_stem(group),
+                        None => UNMATCHED_GROUP_NAME.to_string(),
+                    };
+                    let mut name = base.clone();
+                    let mut suffix = 1;
+                    // Compare case-insensitively so groups such as `HBA1` and `hba1` get
+                    // distinct files on case-insensitive filesystems too.
+                    while !used_names.insert(name.to_ascii_lowercase()) {
+                        suffix += 1;
+                        name = format!("{base}_{suffix}");
+                    }
+                    let index = groups.len();
+                    let writer = ShardWriter::create(
+                        &pool,
+                        index,
+                        output_dir.join(format!("{stem}_{name}.{extension}")),
+                    )?;
+                    groups.push((group.clone(), writer));
+                    writer_of.insert(group, index);
+                    index
+                }
+            };
+            groups[index].1.write(&record, options.write.line_length)
+        },
+    )?;
+
+    let shards = groups
+        .into_iter()
+        .map(|(group, writer)| {
+            writer.finish(false).map(|entry| ShardEntry {
+                group: group.or_else(|| Some(String::new())),
+                ..entry
+            })
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    write_group_table(output_dir, &shards)?;
+    if verbose {
+        let records: usize = shards.iter().map(|shard| shard.records).sum();
+        eprintln!(
+            "[INFO]: Wrote {records} sequences into {} groups; see {}",
+            shards.len(),
+            output_dir.join(GROUP_TABLE_FILE_NAME).display()
+        );
+    }
+    Ok(shards)
+}
+
+/// Writes the `group`, `file`, `records` table. Unmatched records have an empty group, and tabs or
+/// line breaks in group names become spaces.
+fn write_group_table(output_dir: &Path, shards: &[ShardEntry]) -> Result<(), RfastaError> {
+    let mut table = String::from("group\tfile\trecords\n");
+    for shard in shards {
+        table.push_str(&format!(
+            "{}\t{}\t{}\n",
+            tsv_field(shard.group.as_deref().unwrap_or("")),
+            shard.path,
+            shard.records
+        ));
+    }
+    let path = output_dir.join(GROUP_TABLE_FILE_NAME);
+    fs::write(&path, table).map_err(|source| {
+        RfastaError::io(
+            "split",
+            Some(&path),
+            source,
+            "Check that the output directory is writable and has enough free space.",
+        )
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn uniprot_fields_keep_multi_word_values() {
+        let record = FastaRecord::new(
+            "sp|P69905|HBA_HUMAN Hemoglobin subunit alpha OS=Homo sapiens OX=9606 GN=HBA1 PE=1 SV=2",
+            "MVLS",
+        );
+        let field = |name: &str| {
+            GroupKey::UniprotField(name.to_string())
+                .compile()
+                .unwrap()
+                .extract(&record)
+        };
+        assert_eq!(field("OS").as_deref(), Some("Homo sapiens"));
+        assert_eq!(field("OX").as_deref(), Some("9606"));
+        assert_eq!(field("SV").as_deref(), Some("2"));
+        assert_eq!(field("XX"), None);
+    }
+
+    #[test]
+    fn length_buckets_and_group_names() {
+        let extractor = GroupKey::LengthBucket(100).compile().unwrap();
+        let record = FastaRecord::new("seq", "A".repeat(250));
+        assert_eq!(extractor.extract(&record).as_deref(), Some("200-299"));
+        assert_eq!(sanitize_file_stem("Homo sapiens/x"), "Homo_sapiens_x");
+        assert_eq!(sanitize_file_stem(".."), "_.");
+    }
+}
@@ -0,0 +1,8 @@
+//! samtools-compatible `.fai` indexing and random-access record fetch.
+//!
+//! Build an index once with [`index_fasta_file`], then use [`IndexedFasta`] to pull whole records or
+//! residue ranges out of a multi-gigabyte FASTA with a single seek per request. [`MmapFasta`] needs
+//! no index file: it memory-maps the FASTA and hands out records by position without copying them.
+
+pub use crate::fai::{fai_path, index_fasta_file, FaiEntry, FastaIndex, IndexedFasta};
+pub use crate::mmap::{MmapFasta, MmapRecord};
@@ -1,8 +1,16 @@
+use std::borrow::Cow;
 use std::collections::HashSet;
-use std::fs::{self, File, OpenOptions};
-use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::fmt;
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, BufWriter, Seek, SeekFrom, Write};
+use std::iter::FusedIterator;
+use std::ops::Range;
 use std::path::{Path, PathBuf};
 
+use crate::byte_parser::{trim_line, RecordParser, RecordRef};
+use crate::compression::{
+    decompressed_reader, CompressedWriter, Compression, InputCompression, COMPRESSED_EXTENSIONS,
+};
 use crate::configs::{DEFAULT_LINE_LENGTH, MIN_LINE_LENGTH};
 use crate::errors::RfastaError;
 
@@ -25,21 +33,78 @@ impl FastaRecord {
     }
 }
 
+/// Approximate size of the record chunks handed to parsing threads.
+const PARSE_CHUNK_SIZE: usize = 256 * 1024;
+
 /// Options for FASTA parsing.
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub struct ParseOptions {
-    /// Whether duplicate headers should be rejected during parsing.
+    /// Whether duplicate headers should be rejected during parsing. With [`ParseOptions::part`],
+    /// headers are only compared within the part.
     pub expect_unique_header: bool,
+    /// Read only this byte-range part of the file instead of all of it. Parts need an uncompressed
+    /// file path, so reader-based parsing rejects them.
+    pub part: Option<FilePart>,
 }
 
 impl Default for ParseOptions {
     fn default() -> Self {
         Self {
             expect_unique_header: true,
+            part: None,
         }
     }
 }
 
+/// Part `index` of `count` equal byte ranges of a FASTA file, numbered from zero.
+///
+/// A part holds every record whose header line starts inside its byte range, so the parts of a
+/// file together hold each record exactly once. This lets worker `i` of `n` in a job array read
+/// its share of one FASTA without writing shards first. Parts split the file by bytes, not
+/// records, so their record counts vary with sequence lengths.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct FilePart {
+    index: usize,
+    count: usize,
+}
+
+impl FilePart {
+    /// Creates part `index` of `count`, failing unless `index < count`.
+    pub fn new(index: usize, count: usize) -> Result<Self, RfastaError> {
+        if index >= count {
+            return Err(RfastaError::invalid_input(
+                "parse",
+                format!("part {index}/{count} does not exist"),
+                "Parts are numbered from 0, so part i of n needs 0 <= i < n.",
+            ));
+        }
+        Ok(Self { index, count })
+    }
+
+    /// Zero-based part number.
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    /// Number of parts the file is divided into.
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    /// Returns the part's byte range in a file of `size` bytes.
+    fn byte_range(&self, size: u64) -> Range<u64> {
+        let boundary =
+            |index: usize| (u128::from(size) * index as u128 / self.count as u128) as u64;
+        boundary(self.index)..boundary(self.index + 1)
+    }
+}
+
+impl fmt::Display for FilePart {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}", self.index, self.count)
+    }
+}
+
 /// Options for FASTA writing.
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub struct WriteOptions {
@@ -47,6 +112,10 @@ pub struct WriteOptions {
     pub line_length: Option<usize>,
     /// Whether output should be appended instead of replacing the destination file.
     pub append: bool,
+    /// Compression applied to the output stream.
+    pub compression: Compression,
+    /// Compression level. `None` uses the format default; values are clamped to the valid range.
+    pub compression_level: Option<i32>,
 }
 
 impl Default for WriteOptions {
@@ -54,15 +123,38 @@ impl Default for WriteOptions {
         Self {
             line_length: Some(DEFAULT_LINE_LENGTH),
             append: false,
+            compression: Compression::None,
+            compression_level: None,
         }
     }
 }
 
-#[derive(Debug)]
-struct ShardWriter {
-    path: PathBuf,
-    writer: BufWriter<File>,
-    records_written: usize,
+/// Opens a FASTA file for reading, transparently decompressing gzip, bgzip, zstd, and xz input.
+pub(crate) fn open_fasta_input(path: &Path) -> Result<Box<dyn BufRead>, RfastaError> {
+    let file = File::open(path).map_err(|source| {
+        RfastaError::io(
+            "parse",
+            Some(path),
+            source,
+            "Check that the input file exists and is readable.",
+        )
+    })?;
+    decompressed_reader(BufReader::new(file), Some(path))
+}
+
+/// Returns the input file name without compression and FASTA extensions.
+pub(crate) fn input_stem(path: &Path) -> Option<String> {
+    let mut name = path.file_name()?.to_str()?;
+    if let Some((rest, extension)) = name.rsplit_once('.') {
+        if COMPRESSED_EXTENSIONS
+            .iter()
+            .any(|known| known.eq_ignore_ascii_case(extension))
+        {
+            name = rest;
+        }
+    }
+    let stem = Path::new(name).file_stem()?.to_str()?;
+    (!stem.is_empty()).then(|| stem.to_string())
 }
 
 fn normalize_line_length(line_length: Option<usize>) -> Option<usize> {
@@ -73,46 +165,43 @@ fn normalize_line_length(line_length: Option<usize>) -> Option<usize> {
     }
 }
 
-fn finish_record<F>(
-    header: &mut Option<String>,
-    sequence: &mut String,
-    seen_headers: &mut Option<HashSet<String>>,
+/// Adds `header` to `seen_headers`, failing when it is already there.
+pub(crate) fn insert_unique_header(
+    seen_headers: &mut HashSet<String>,
+    header: &str,
     source_name: Option<&Path>,
-    visit: &mut F,
-    records_seen: &mut usize,
-) -> Result<(), RfastaError>
-where
-    F: FnMut(FastaRecord) -> Result<(), RfastaError>,
-{
-    let header = header
-        .take()
-        .expect("finish_record is only called when a header is present");
-
-    if sequence.is_empty() {
-        return Err(RfastaError::empty_sequence(
-            source_name,
-            header,
-            "Ensure each FASTA header is followed by at least one sequence line.",
-        ));
+) -> Result<(), RfastaError> {
+    if seen_headers.insert(header.to_string()) {
+        return Ok(());
     }
+    Err(RfastaError::duplicate_header(
+        source_name,
+        header.to_string(),
+        "Pass `ParseOptions { expect_unique_header: false, ..ParseOptions::default() }` or use --non-unique-header if repeated headers are expected.",
+    ))
+}
 
-    if let Some(seen_headers) = seen_headers.as_mut() {
-        if !seen_headers.insert(header.clone()) {
-            return Err(RfastaError::duplicate_header(
-                source_name,
-                header,
-                "Pass ParseOptions { expect_unique_header: false } or use --non-unique-header if repeated headers are expected.",
-            ));
-        }
+/// Replaces tabs and line breaks in a TSV field with spaces, so headers cannot shift columns or
+/// split rows.
+pub(crate) fn tsv_field(text: &str) -> Cow<'_, str> {
+    if text.contains(['\t', '\r', '\n']) {
+        Cow::Owned(text.replace(['\t', '\r', '\n'], " "))
+    } else {
+        Cow::Borrowed(text)
     }
+}
 
-    sequence.make_ascii_uppercase();
-    let record = FastaRecord::new(header, std::mem::take(sequence));
-    *records_seen += 1;
-    visit(record)
+/// Returns the number of bytes [`write_record_to_writer`] produces for `record`, before compression.
+pub(crate) fn formatted_record_len(record: &FastaRecord, line_length: Option<usize>) -> usize {
+    let sequence_lines = match normalize_line_length(line_length) {
+        Some(line_length) => record.sequence.len().div_ceil(line_length),
+        None => 1,
+    };
+    // `>` + header + newline, the sequence with one newline per line, and the blank separator.
+    record.header.len() + 2 + record.sequence.len() + sequence_lines + 1
 }
 
-fn write_record_to_writer<W: Write>(
+pub(crate) fn write_record_to_writer<W: Write>(
     writer: &mut W,
     record: &FastaRecord,
     line_length: Option<usize>,
@@ -125,7 +214,17 @@ fn write_record_to_writer<W: Write>(
             "Remove empty records before writing, or make sure every header has a sequence.",
         ));
     }
+    write_record_allowing_empty(writer, record, line_length, path)
+}
 
+/// Writes `record` like [`write_record_to_writer`], but writes an empty sequence as a header with
+/// no sequence lines instead of failing.
+pub(crate) fn write_record_allowing_empty<W: Write>(
+    writer: &mut W,
+    record: &FastaRecord,
+    line_length: Option<usize>,
+    path: Option<&Path>,
+) -> Result<(), RfastaError> {
     writer
         .write_all(format!(">{}\n", record.header).as_bytes())
         .map_err(|source| {
@@ -158,6 +257,7 @@ fn write_record_to_writer<W: Write>(
                 })?;
             }
         }
+        None if record.sequence.is_empty() => {}
         None => {
             writer
                 .write_all(record.sequence.as_bytes())
@@ -205,7 +305,7 @@ fn write_record_to_writer<W: Write>(
 /// # Ok::<(), RfastaError>(())
 /// ```
 pub fn visit_fasta_reader<R, F>(
-    mut reader: R,
+    reader: R,
     options: ParseOptions,
     mut visit: F,
 ) -> Result<usize, RfastaError>
@@ -213,10 +313,14 @@ where
     R: BufRead,
     F: FnMut(FastaRecord) -> Result<(), RfastaError>,
 {
-    visit_fasta_reader_with_source(&mut reader, options, None, &mut visit)
+    let mut records = FastaReader::new(reader, options)?;
+    for record in &mut records {
+        visit(record?)?;
+    }
+    Ok(records.records_read())
 }
 
-fn visit_fasta_reader_with_source<R, F>(
+pub(crate) fn visit_fasta_reader_with_source<R, F>(
     reader: &mut R,
     options: ParseOptions,
     source_name: Option<&Path>,
@@ -226,74 +330,109 @@ where
     R: BufRead,
     F: FnMut(FastaRecord) -> Result<(), RfastaError>,
 {
-    let mut line = String::new();
-    let mut current_header = None;
-    let mut current_sequence = String::new();
-    let mut line_number = 0usize;
-    let mut records_seen = 0usize;
-    let mut seen_headers = options.expect_unique_header.then(HashSet::new);
-
-    loop {
-        line.clear();
-        let bytes_read = reader.read_line(&mut line).map_err(|source| {
-            RfastaError::io(
-                "parse",
-                source_name,
-                source,
-                "Check that the input file exists and is readable.",
-            )
-        })?;
+    let mut records = FastaReader::with_source(reader, &options, source_name)?;
+    for record in &mut records {
+        visit(record?)?;
+    }
+    Ok(records.records_read())
+}
 
-        if bytes_read == 0 {
-            break;
-        }
+/// Iterator over the FASTA records of a buffered reader.
+///
+/// This is the pull-based counterpart to [`visit_fasta_reader`], with the same [`ParseOptions`]
+/// checks and error messages, for callers that want iterator adapters such as `take`, `zip`, or
+/// an early `break`. Each item is a parsed record or the error that stopped parsing; the iterator
+/// ends after the first error.
+///
+/// # Example
+/// ```
+/// use std::io::Cursor;
+///
+/// use rfasta::parse::{FastaReader, ParseOptions};
+///
+/// let data = b">seq1\nacgt\n>seq2\nTTTT\n>seq3\nMK\n";
+/// let reader = FastaReader::new(Cursor::new(data), ParseOptions::default())?;
+/// let headers = reader
+///     .take(2)
+///     .map(|record| record.map(|record| record.header))
+///     .collect::<Result<Vec<_>, _>>()?;
+/// assert_eq!(headers, ["seq1", "seq2"]);
+/// # Ok::<(), rfasta::RfastaError>(())
+/// ```
+pub struct FastaReader<R> {
+    parser: RecordParser<R>,
+    failed: bool,
+}
 
-        line_number += 1;
-        let stripped = line.trim();
+impl<R: BufRead> FastaReader<R> {
+    /// Creates an iterator over the records of `reader`. Fails when [`ParseOptions::part`] is
+    /// set, since parts need a file; use [`FastaReader::open`] for those.
+    pub fn new(reader: R, options: ParseOptions) -> Result<Self, RfastaError> {
+        Self::with_source(reader, &options, None)
+    }
 
-        if stripped.is_empty() {
-            continue;
-        }
+    pub(crate) fn with_source(
+        reader: R,
+        options: &ParseOptions,
+        source_name: Option<&Path>,
+    ) -> Result<Self, RfastaError> {
+        reject_stream_part(options)?;
+        Ok(Self::from_parser(RecordParser::new(
+            reader,
+            options.expect_unique_header,
+            source_name,
+            0..u64::MAX,
+        )))
+    }
 
-        if let Some(header) = stripped.strip_prefix('>') {
-            if current_header.is_some() {
-                finish_record(
-                    &mut current_header,
-                    &mut current_sequence,
-                    &mut seen_headers,
-                    source_name,
-                    visit,
-                    &mut records_seen,
-                )?;
-            }
-            current_header = Some(header.to_string());
-            continue;
+    fn from_parser(parser: RecordParser<R>) -> Self {
+        Self {
+            parser,
+            failed: false,
         }
+    }
 
-        if current_header.is_none() {
-            return Err(RfastaError::malformed_fasta(
-                source_name,
-                line_number,
-                "found sequence data before the first FASTA header",
-                "Make sure the file starts with a header line beginning with `>`.",
-            ));
-        }
+    /// Returns the number of records yielded so far.
+    pub fn records_read(&self) -> usize {
+        self.parser.records()
+    }
+}
 
-        current_sequence.push_str(stripped);
+impl FastaReader<Box<dyn BufRead>> {
+    /// Opens a FASTA file for iteration, decompressing it like [`visit_fasta_file`] does and
+    /// honoring [`ParseOptions::part`]. Errors name the file.
+    pub fn open<P: AsRef<Path>>(path: P, options: ParseOptions) -> Result<Self, RfastaError> {
+        Ok(Self::from_parser(open_record_parser(
+            path.as_ref(),
+            &options,
+        )?))
     }
+}
 
-    if current_header.is_some() {
-        finish_record(
-            &mut current_header,
-            &mut current_sequence,
-            &mut seen_headers,
-            source_name,
-            visit,
-            &mut records_seen,
-        )?;
+impl<R: BufRead> Iterator for FastaReader<R> {
+    type Item = Result<FastaRecord, RfastaError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed {
+            return None;
+        }
+        let next = self.parser.next_record().transpose();
+        self.failed = matches!(next, Some(Err(_)));
+        next
     }
+}
 
-    Ok(records_seen)
+impl<R: BufRead> FusedIterator for FastaReader<R> {}
+
+fn reject_stream_part(options: &ParseOptions) -> Result<(), RfastaError> {
+    match options.part {
+        Some(part) => Err(RfastaError::invalid_input(
+            "parse",
+            format!("cannot read part {part} of a stream"),
+            "Byte-range parts need a seekable file; pass a file path instead of a reader or `-`.",
+        )),
+        None => Ok(()),
+    }
 }
 
 /// Parses all FASTA records from a buffered reader into memory.
@@ -328,6 +467,9 @@ pub fn parse_fasta_reader<R: BufRead>(
 ///
 /// This is the file-based counterpart to [`visit_fasta_reader`]. It is useful when you want
 /// bounded-memory processing over a large FASTA without manually opening a reader.
+///
+/// Gzip (including multi-member bgzip), zstd, and xz inputs are detected from their magic bytes
+/// and decompressed on the fly, regardless of the file extension.
 pub fn visit_fasta_file<P, F>(
     path: P,
     options: ParseOptions,
@@ -339,23 +481,289 @@ where
     F: FnMut(FastaRecord) -> Result<(), RfastaError>,
 {
     let path = path.as_ref();
-    let file = File::open(path).map_err(|source| {
+    let mut records = FastaReader::open(path, options.clone())?;
+    for record in &mut records {
+        visit(record?)?;
+    }
+    if verbose {
+        log_parsed_file(path, options.part, records.records_read());
+    }
+    Ok(records.records_read())
+}
+
+/// Streams borrowed [`RecordRef`] views from any buffered reader and invokes `visit` for each.
+///
+/// This is the allocation-light counterpart to [`visit_fasta_reader`]: the parser reuses one
+/// header buffer and one sequence buffer for every record and never checks the sequence for UTF-8,
+/// so counting, statistics, and filtering pay for neither a copy nor an allocation per record.
+/// Call [`RecordRef::to_record`] to keep a record past the callback.
+///
+/// # Example
+/// ```
+/// use std::io::Cursor;
+///
+/// use rfasta::parse::{visit_fasta_reader_refs, ParseOptions, RecordRef};
+/// use rfasta::RfastaError;
+///
+/// let data = b">seq1\nacgt\n>seq2\nTTTT\nTT\n";
+/// let mut residues = 0;
+/// visit_fasta_reader_refs(Cursor::new(data), ParseOptions::default(), |record: RecordRef<'_>| {
+///     residues += record.sequence.len();
+///     Ok::<(), RfastaError>(())
+/// })?;
+/// assert_eq!(residues, 10);
+/// # Ok::<(), RfastaError>(())
+/// ```
+pub fn visit_fasta_reader_refs<R, F>(
+    reader: R,
+    options: ParseOptions,
+    mut visit: F,
+) -> Result<usize, RfastaError>
+where
+    R: BufRead,
+    F: FnMut(RecordRef<'_>) -> Result<(), RfastaError>,
+{
+    reject_stream_part(&options)?;
+    let mut parser = RecordParser::new(reader, options.expect_unique_header, None, 0..u64::MAX);
+    while let Some(record) = parser.next_ref()? {
+        visit(record)?;
+    }
+    Ok(parser.records())
+}
+
+/// Streams borrowed [`RecordRef`] views from a file path, honoring [`ParseOptions::part`].
+///
+/// This is the file-based counterpart to [`visit_fasta_reader_refs`], and reads compressed input
+/// like [`visit_fasta_file`] does.
+pub fn visit_fasta_file_refs<P, F>(
+    path: P,
+    options: ParseOptions,
+    verbose: bool,
+    mut visit: F,
+) -> Result<usize, RfastaError>
+where
+    P: AsRef<Path>,
+    F: FnMut(RecordRef<'_>) -> Result<(), RfastaError>,
+{
+    let path = path.as_ref();
+    let mut parser = open_record_parser(path, &options)?;
+    while let Some(record) = parser.next_ref()? {
+        visit(record)?;
+    }
+    if verbose {
+        log_parsed_file(path, options.part, parser.records());
+    }
+    Ok(parser.records())
+}
+
+fn open_record_parser(
+    path: &Path,
+    options: &ParseOptions,
+) -> Result<RecordParser<Box<dyn BufRead>>, RfastaError> {
+    let (reader, range): (Box<dyn BufRead>, _) = match options.part {
+        Some(part) => {
+            let (reader, range) = open_fasta_part(path, part)?;
+            (Box::new(reader), range)
+        }
+        None => (open_fasta_input(path)?, 0..u64::MAX),
+    };
+    Ok(RecordParser::new(
+        reader,
+        options.expect_unique_header,
+        Some(path),
+        range,
+    ))
+}
+
+pub(crate) fn log_parsed_file(path: &Path, part: Option<FilePart>, records: usize) {
+    match part {
+        Some(part) => eprintln!(
+            "[INFO]: Parsed part {part} of {} to recover {records} sequences",
+            path.display()
+        ),
+        None => eprintln!(
+            "[INFO]: Parsed file to recover {records} sequences from {}",
+            path.display()
+        ),
+    }
+}
+
+/// A run of whole records cut from the input by [`visit_fasta_file_chunks`].
+pub(crate) struct FastaChunk {
+    bytes: Vec<u8>,
+    /// Byte offset in the file of the range the chunk was cut from.
+    range_start: u64,
+    /// Lines of the range before the chunk's first line.
+    lines_before: usize,
+}
+
+/// Streams a FASTA file as chunks of whole records, honoring [`ParseOptions::part`].
+///
+/// Parsing the chunks in order with [`visit_fasta_chunk`] yields the records [`visit_fasta_file`]
+/// would, so the chunks can be parsed on worker threads. Header uniqueness spans chunks and is
+/// left to the caller.
+pub(crate) fn visit_fasta_file_chunks(
+    path: &Path,
+    options: &ParseOptions,
+    visit: &mut dyn FnMut(FastaChunk) -> Result<(), RfastaError>,
+) -> Result<(), RfastaError> {
+    match options.part {
+        Some(part) => {
+            let (mut reader, range) = open_fasta_part(path, part)?;
+            chunk_fasta_range(&mut reader, Some(path), range, visit)
+        }
+        None => {
+            let mut reader = open_fasta_input(path)?;
+            chunk_fasta_range(&mut reader, Some(path), 0..u64::MAX, visit)
+        }
+    }
+}
+
+/// Reader-based counterpart to [`visit_fasta_file_chunks`].
+pub(crate) fn visit_fasta_reader_chunks<R: BufRead>(
+    reader: &mut R,
+    options: &ParseOptions,
+    visit: &mut dyn FnMut(FastaChunk) -> Result<(), RfastaError>,
+) -> Result<(), RfastaError> {
+    reject_stream_part(options)?;
+    chunk_fasta_range(reader, None, 0..u64::MAX, visit)
+}
+
+/// Parses one chunk from [`visit_fasta_file_chunks`] without checking header uniqueness. Errors
+/// name the same lines a serial read would.
+pub(crate) fn visit_fasta_chunk<F>(
+    chunk: &FastaChunk,
+    source_name: Option<&Path>,
+    visit: &mut F,
+) -> Result<usize, RfastaError>
+where
+    F: FnMut(FastaRecord) -> Result<(), RfastaError>,
+{
+    let parser = RecordParser::new(&chunk.bytes[..], false, source_name, 0..u64::MAX)
+        .with_line_origin(chunk.range_start, chunk.lines_before);
+    let mut records = FastaReader::from_parser(parser);
+    for record in &mut records {
+        visit(record?)?;
+    }
+    Ok(records.records_read())
+}
+
+/// Cuts the input into chunks of about [`PARSE_CHUNK_SIZE`] bytes at header lines.
+///
+/// Lines are kept or dropped by the same rules the parser applies to `range`.
+fn chunk_fasta_range<R: BufRead>(
+    reader: &mut R,
+    source_name: Option<&Path>,
+    range: Range<u64>,
+    visit: &mut dyn FnMut(FastaChunk) -> Result<(), RfastaError>,
+) -> Result<(), RfastaError> {
+    let mut chunk = Vec::new();
+    let mut offset = range.start;
+    let mut in_record = range.start == 0;
+    let mut record_start = 0;
+    let mut lines_read = 0;
+    let mut lines_before = 0;
+    let mut emit = |bytes, lines_before| {
+        visit(FastaChunk {
+            bytes,
+            range_start: range.start,
+            lines_before,
+        })
+    };
+    loop {
+        let line_start = chunk.len();
+        let bytes_read = match reader.read_until(b'\n', &mut chunk) {
+            Ok(bytes_read) => bytes_read,
+            Err(source) => {
+                // Records completed before the failure are still parsed, as in a serial read.
+                chunk.truncate(record_start);
+                if !chunk.is_empty() {
+                    emit(chunk, lines_before)?;
+                }
+                return Err(RfastaError::io(
+                    "parse",
+                    source_name,
+                    source,
+                    "Check that the input file exists and is readable.",
+                ));
+            }
+        };
+        if bytes_read == 0 {
+            break;
+        }
+        let line_offset = offset;
+        offset += bytes_read as u64;
+        lines_read += 1;
+
+        if is_header_line(&chunk[line_start..]) {
+            if line_offset >= range.end {
+                chunk.truncate(line_start);
+                break;
+            }
+            in_record = true;
+            record_start = line_start;
+            if line_start >= PARSE_CHUNK_SIZE {
+                let next = chunk.split_off(line_start);
+                emit(std::mem::replace(&mut chunk, next), lines_before)?;
+                record_start = 0;
+                lines_before = lines_read - 1;
+            }
+        } else if !in_record {
+            chunk.truncate(line_start);
+            lines_before = lines_read;
+        }
+    }
+    if chunk.is_empty() {
+        return Ok(());
+    }
+    emit(chunk, lines_before)
+}
+
+/// Returns `true` when the parser reads `line` as a header.
+fn is_header_line(line: &[u8]) -> bool {
+    trim_line(line).first() == Some(&b'>')
+}
+
+/// Opens `path` at the first line start of `part` and returns the reader with the part's range.
+///
+/// The range starts at that line rather than at the raw byte boundary, and still ends at the raw
+/// boundary, since a record belongs to the part where its header line starts.
+fn open_fasta_part(
+    path: &Path,
+    part: FilePart,
+) -> Result<(BufReader<File>, Range<u64>), RfastaError> {
+    let read_error = |source| {
         RfastaError::io(
             "parse",
             Some(path),
             source,
             "Check that the input file exists and is readable.",
         )
-    })?;
+    };
+    let file = File::open(path).map_err(read_error)?;
+    let size = file.metadata().map_err(read_error)?.len();
     let mut reader = BufReader::new(file);
-    let records = visit_fasta_reader_with_source(&mut reader, options, Some(path), &mut visit)?;
-    if verbose {
-        println!(
-            "[INFO]: Parsed file to recover {records} sequences from {}",
-            path.display()
-        );
+    let prefix = reader.fill_buf().map_err(read_error)?;
+    if !matches!(InputCompression::detect(prefix), InputCompression::None) {
+        return Err(RfastaError::invalid_input(
+            "parse",
+            format!("cannot read part {part} of compressed input"),
+            "Byte offsets in compressed files do not map to records; decompress the file first, or \
+             write shards with `rfasta split`.",
+        ));
     }
-    Ok(records)
+
+    let range = part.byte_range(size);
+    let mut start = range.start;
+    if start > 0 {
+        // Step back one byte so a boundary that falls exactly on a line start is kept.
+        reader
+            .seek(SeekFrom::Start(start - 1))
+            .map_err(read_error)?;
+        let mut skipped = Vec::new();
+        start += reader.read_until(b'\n', &mut skipped).map_err(read_error)? as u64 - 1;
+    }
+    Ok((reader, start..range.end))
 }
 
 /// Parses all FASTA records from a file path into memory.
@@ -404,23 +812,20 @@ pub fn write_fasta_writer<W: Write>(
     records: &[FastaRecord],
     options: &WriteOptions,
 ) -> Result<(), RfastaError> {
+    let mut fasta_writer = FastaWriter::new(&mut *writer, options)?;
     for record in records {
-        write_record_to_writer(writer, record, options.line_length, None)?;
+        fasta_writer.write_record(record)?;
     }
-    writer.flush().map_err(|source| {
-        RfastaError::io(
-            "write",
-            None,
-            source,
-            "Check that the output writer is still open and writable.",
-        )
-    })
+    fasta_writer.finish()?;
+    Ok(())
 }
 
 /// Writes FASTA records to a file.
 ///
 /// This helper opens a buffered writer, applies the requested line wrapping, and emits a blank
-/// line between records for compatibility with the existing CLI and Python interfaces.
+/// line between records for compatibility with the existing CLI and Python interfaces. When
+/// [`WriteOptions::compression`] is set, the file is compressed in the requested format; appending
+/// adds a new compressed member or frame, which standard decoders read as one stream.
 pub fn write_fasta_file<P: AsRef<Path>>(
     records: &[FastaRecord],
     path: P,
@@ -428,34 +833,13 @@ pub fn write_fasta_file<P: AsRef<Path>>(
     verbose: bool,
 ) -> Result<(), RfastaError> {
     let path = path.as_ref();
-    let file = OpenOptions::new()
-        .write(true)
-        .create(true)
-        .append(options.append)
-        .truncate(!options.append)
-        .open(path)
-        .map_err(|source| {
-            RfastaError::io(
-                "write",
-                Some(path),
-                source,
-                "Check that the output path is writable and that parent directories exist.",
-            )
-        })?;
-    let mut writer = BufWriter::new(file);
+    let mut writer = FastaWriter::create(path, &options)?;
     for record in records {
-        write_record_to_writer(&mut writer, record, options.line_length, Some(path))?;
+        writer.write_record(record)?;
     }
-    writer.flush().map_err(|source| {
-        RfastaError::io(
-            "write",
-            Some(path),
-            source,
-            "Check that the output path is writable and has enough free space.",
-        )
-    })?;
+    writer.finish()?;
     if verbose {
-        println!(
+        eprintln!(
             "[INFO]: Wrote {} sequences to {}",
             records.len(),
             path.display()
@@ -464,141 +848,126 @@ pub fn write_fasta_file<P: AsRef<Path>>(
     Ok(())
 }
 
-/// Splits records into round-robin shards in memory.
+/// Incremental FASTA writer that applies [`WriteOptions`] one record at a time.
 ///
-/// This helper is convenient for tests or small library workflows. For UniRef-scale files, prefer
-/// [`split_fasta_file_round_robin`] so records are streamed directly into shard writers.
-#[cfg_attr(not(test), allow(dead_code))]
-pub(crate) fn split_records_round_robin(
-    records: Vec<FastaRecord>,
-    chunks: usize,
-) -> Result<Vec<Vec<FastaRecord>>, RfastaError> {
-    if chunks == 0 {
-        return Err(RfastaError::invalid_chunk_count(
-            chunks,
-            "Pass a chunk count greater than zero.",
-        ));
-    }
-
-    let mut split = std::iter::repeat_with(Vec::new)
-        .take(chunks)
-        .collect::<Vec<_>>();
-    for (index, record) in records.into_iter().enumerate() {
-        split[index % chunks].push(record);
-    }
-    split.retain(|chunk| !chunk.is_empty());
-    Ok(split)
-}
-
-/// Streams a FASTA file into round-robin shard files without first collecting all records.
-///
-/// Shards are balanced by record order, not exact byte size. This avoids rereading the input file
-/// and keeps memory bounded for very large inputs such as UniRef FASTA distributions.
+/// Use this when records are produced by a stream rather than held in a slice. Call
+/// [`FastaWriter::finish`] when done so compression trailers are written and output is flushed.
 ///
 /// # Example
-/// ```no_run
-/// use rfasta::shard::split_fasta_file_round_robin;
+/// ```
+/// use rfasta::parse::FastaRecord;
+/// use rfasta::write::{FastaWriter, WriteOptions};
 ///
-/// let written = split_fasta_file_round_robin("proteins.fasta", "shards", 8, Some(60), true)?;
-/// println!("Wrote {written} shard files");
+/// let mut writer = FastaWriter::new(Vec::new(), &WriteOptions::default())?;
+/// writer.write_record(&FastaRecord::new("seq1", "ACDE"))?;
+/// let output = writer.finish()?;
+/// assert_eq!(output, b">seq1\nACDE\n\n");
 /// # Ok::<(), rfasta::RfastaError>(())
 /// ```
-pub fn split_fasta_file_round_robin<P: AsRef<Path>, Q: AsRef<Path>>(
-    input_path: P,
-    output_dir: Q,
-    chunks: usize,
+pub struct FastaWriter<W: Write> {
+    writer: CompressedWriter<W>,
     line_length: Option<usize>,
-    verbose: bool,
-) -> Result<usize, RfastaError> {
-    if chunks == 0 {
-        return Err(RfastaError::invalid_chunk_count(
-            chunks,
-            "Pass a chunk count greater than zero.",
-        ));
+    path: Option<PathBuf>,
+    records_written: usize,
+}
+
+impl<W: Write> FastaWriter<W> {
+    /// Wraps any writer. [`WriteOptions::append`] has no effect here.
+    pub fn new(writer: W, options: &WriteOptions) -> Result<Self, RfastaError> {
+        Self::with_path(writer, options, None)
     }
 
-    let input_path = input_path.as_ref();
-    let output_dir = output_dir.as_ref();
-    fs::create_dir_all(output_dir).map_err(|source| {
-        RfastaError::io(
-            "split",
-            Some(output_dir),
-            source,
-            "Check that the output directory is writable, or create it before running split.",
-        )
-    })?;
+    fn with_path(
+        writer: W,
+        options: &WriteOptions,
+        path: Option<PathBuf>,
+    ) -> Result<Self, RfastaError> {
+        let writer = CompressedWriter::new(writer, options.compression, options.compression_level)
+            .map_err(|source| {
+                RfastaError::io(
+                    "write",
+                    path.as_deref(),
+                    source,
+                    "Check that the compression level is supported by the selected format.",
+                )
+            })?;
+        Ok(Self {
+            writer,
+            line_length: options.line_length,
+            path,
+            records_written: 0,
+        })
+    }
 
-    let stem = input_path
-        .file_stem()
-        .and_then(|value| value.to_str())
-        .filter(|value| !value.is_empty())
-        .unwrap_or("rfasta_shard")
-        .to_string();
-
-    let mut shard_index = 0usize;
-    let mut writers: Vec<Option<ShardWriter>> =
-        std::iter::repeat_with(|| None).take(chunks).collect();
-
-    visit_fasta_file(
-        input_path,
-        ParseOptions {
-            expect_unique_header: false,
-        },
-        false,
-        |record| {
-            let target = shard_index % chunks;
-            if writers[target].is_none() {
-                let path = output_dir.join(format!("{stem}_{:06}.fasta", target + 1));
-                let file = File::create(&path).map_err(|source| {
-                    RfastaError::io(
-                        "split",
-                        Some(&path),
-                        source,
-                        "Check that the output directory is writable and has enough free space.",
-                    )
-                })?;
-                writers[target] = Some(ShardWriter {
-                    path,
-                    writer: BufWriter::new(file),
-                    records_written: 0,
-                });
-            }
+    /// Writes one record.
+    pub fn write_record(&mut self, record: &FastaRecord) -> Result<(), RfastaError> {
+        write_record_to_writer(
+            &mut self.writer,
+            record,
+            self.line_length,
+            self.path.as_deref(),
+        )?;
+        self.records_written += 1;
+        Ok(())
+    }
 
-            let shard = writers[target]
-                .as_mut()
-                .expect("writer is initialized before use");
-            write_record_to_writer(&mut shard.writer, &record, line_length, Some(&shard.path))?;
-            shard.records_written += 1;
-            shard_index += 1;
-            Ok(())
-        },
-    )?;
-
-    let mut files_written = 0usize;
-    for shard in writers.iter_mut().flatten() {
-        shard.writer.flush().map_err(|source| {
-            RfastaError::io(
-                "split",
-                Some(&shard.path),
-                source,
-                "Check that the output directory is writable and has enough free space.",
-            )
-        })?;
-        files_written += 1;
-        if verbose {
-            println!(
-                "[INFO]: Wrote {} sequences to {}",
-                shard.records_written,
-                shard.path.display()
-            );
-        }
+    /// Writes one record, writing an empty sequence as a header alone instead of failing.
+    pub(crate) fn write_record_allowing_empty(
+        &mut self,
+        record: &FastaRecord,
+    ) -> Result<(), RfastaError> {
+        write_record_allowing_empty(
+            &mut self.writer,
+            record,
+            self.line_length,
+            self.path.as_deref(),
+        )?;
+        self.records_written += 1;
+        Ok(())
     }
 
-    if verbose {
-        println!("[INFO]: Split FASTA into {files_written} chunks");
+    /// Returns the number of records written so far.
+    pub fn records_written(&self) -> usize {
+        self.records_written
+    }
+
+    /// Completes the compressed stream, flushes, and returns the underlying writer.
+    pub fn finish(self) -> Result<W, RfastaError> {
+        let path = self.path;
+        self.writer
+            .finish()
+            .and_then(|mut writer| writer.flush().map(|_| writer))
+            .map_err(|source| {
+                RfastaError::io(
+                    "write",
+                    path.as_deref(),
+                    source,
+                    "Check that the output path is writable and has enough free space.",
+                )
+            })
     }
+}
 
-    Ok(files_written)
+impl FastaWriter<BufWriter<File>> {
+    /// Creates or truncates a file, or appends to it when [`WriteOptions::append`] is set.
+    pub fn create<P: AsRef<Path>>(path: P, options: &WriteOptions) -> Result<Self, RfastaError> {
+        let path = path.as_ref();
+        let file = OpenOptions::new()
+            .write(true)
+            .create(true)
+            .append(options.append)
+            .truncate(!options.append)
+            .open(path)
+            .map_err(|source| {
+                RfastaError::io(
+                    "write",
+                    Some(path),
+                    source,
+                    "Check that the output path is writable and that parent directories exist.",
+                )
+            })?;
+        Self::with_path(BufWriter::new(file), options, Some(path.to_path_buf()))
+    }
 }
 
 #[cfg(test)]
@@ -606,18 +975,26 @@ mod tests {
     use super::*;
 
     #[test]
-    fn split_records_round_robin_distributes_records_by_order() {
-        let records = vec![
-            FastaRecord::new("seq1", "AAAA"),
-            FastaRecord::new("seq2", "CCCC"),
-            FastaRecord::new("seq3", "DDDD"),
-            FastaRecord::new("seq4", "EEEE"),
-        ];
-        let chunks = split_records_round_robin(records, 2).unwrap();
-        assert_eq!(chunks.len(), 2);
-        assert_eq!(chunks[0][0].header, "seq1");
-        assert_eq!(chunks[0][1].header, "seq3");
-        assert_eq!(chunks[1][0].header, "seq2");
-        assert_eq!(chunks[1][1].header, "seq4");
+    fn formatted_record_len_matches_written_output() {
+        let record = FastaRecord::new(
+            "seq1",
+            "ACDEFGHIKLMNPQRSTVWYACDEFGHIKLMNPQRSTVWYACDEFGHIKLMNPQRSTVWYAC",
+        );
+        for line_length in [None, Some(60), Some(20), Some(62)] {
+            let mut written = Vec::new();
+            write_record_to_writer(&mut written, &record, line_length, None).unwrap();
+            assert_eq!(formatted_record_len(&record, line_length), written.len());
+        }
+    }
+
+    #[test]
+    fn input_stem_strips_compression_and_fasta_extensions() {
+        assert_eq!(
+            input_stem(Path::new("uniref90.fasta.gz")).unwrap(),
+            "uniref90"
+        );
+        assert_eq!(input_stem(Path::new("dir/input.fa.zst")).unwrap(), "input");
+        assert_eq!(input_stem(Path::new("input.fasta")).unwrap(), "input");
+        assert_eq!(input_stem(Path::new("input")).unwrap(), "input");
     }
 }
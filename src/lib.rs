@@ -6,7 +6,8 @@
 //! - [`parse`] for reading FASTA data
 //! - [`clean`] for `protfasta`-style sequence cleanup policies
 //! - [`write`] for FASTA output
-//! - [`shard`] for one-pass round-robin sharding
+//! - [`shard`] for one-pass sharding, partitioning, and unsplitting
+//! - [`index`] for `.fai` indexing and random-access fetch
 //! - [`error`] for the common error type
 //!
 //! The full user guide is designed to live outside rustdoc as a guide-first docs site. Rustdoc is
@@ -77,15 +78,31 @@
 #[cfg(feature = "python")]
 use pyo3::prelude::*;
 
+mod byte_parser;
 mod cli;
+mod compression;
 mod configs;
+mod corrections;
 mod errors;
+mod explode;
+mod fai;
+mod grouping;
 mod io;
+mod manifest;
+mod mmap;
+mod partition;
+mod pipeline;
+mod rejection;
+mod sampling;
 mod sequence_processing;
+mod shard_pool;
+mod sharding;
+mod unsplit;
 mod utilities;
 
 pub mod clean;
 pub mod error;
+pub mod index;
 pub mod parse;
 pub mod shard;
 pub mod write;
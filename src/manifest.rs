@@ -0,0 +1,223 @@
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::compression::Compression;
+use crate::errors::RfastaError;
+use crate::partition::LeakageReport;
+use crate::sharding::ShardStrategy;
+
+/// File name of the manifest written next to every shard set.
+pub const MANIFEST_FILE_NAME: &str = "manifest.json";
+
+/// Description of a shard set, written to [`MANIFEST_FILE_NAME`] in the output directory.
+///
+/// Shard paths are relative to the manifest's directory, so a shard set can be moved or copied as
+/// a whole and still verify.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct ShardManifest {
+    /// Version of `rfasta` that wrote the shards.
+    pub rfasta_version: String,
+    /// Input FASTA path as given, or `None` when the input was a reader such as standard input.
+    pub input: Option<String>,
+    /// Layout used to assign records to shards.
+    pub strategy: ShardStrategy,
+    /// Residues per sequence line, or `None` for unwrapped sequences.
+    pub line_length: Option<usize>,
+    /// Compression applied to every shard.
+    pub compression: Compression,
+    /// Requested compression level, if any.
+    pub compression_level: Option<i32>,
+    /// One entry per shard file, in shard-number order.
+    pub shards: Vec<ShardEntry>,
+    /// Cross-partition similarity check, present for similarity-aware partitions only.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub leakage: Option<LeakageReport>,
+}
+
+/// One shard file listed in a [`ShardManifest`].
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct ShardEntry {
+    /// File name relative to the manifest's directory.
+    pub path: String,
+    /// Number of records in the shard.
+    pub records: usize,
+    /// Total residues across the shard's sequences.
+    pub residues: u64,
+    /// File size on disk, after compression.
+    pub bytes: u64,
+    /// Lowercase hex SHA-256 of the file as stored on disk.
+    pub sha256: String,
+    /// Group key of the shard's records when split by key; empty for records without a key.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub group: Option<String>,
+}
+
+impl ShardManifest {
+    /// Writes the manifest as pretty-printed JSON to [`MANIFEST_FILE_NAME`] in `dir`.
+    pub(crate) fn write(&self, dir: &Path) -> Result<(), RfastaError> {
+        let path = dir.join(MANIFEST_FILE_NAME);
+        let json = serde_json::to_string_pretty(self).expect("shard manifests always serialize");
+        fs::write(&path, format!("{json}\n")).map_err(|source| {
+            RfastaError::io(
+                "split",
+                Some(&path),
+                source,
+                "Check that the output directory is writable and has enough free space.",
+            )
+        })
+    }
+}
+
+/// Reads the [`ShardManifest`] stored in `dir`.
+pub fn read_shard_manifest<P: AsRef<Path>>(dir: P) -> Result<ShardManifest, RfastaError> {
+    let path = dir.as_ref().join(MANIFEST_FILE_NAME);
+    let contents = fs::read_to_string(&path).map_err(|source| {
+        RfastaError::io(
+            "verify-shards",
+            Some(&path),
+            source,
+            "Point at the directory `rfasta split` wrote; it contains manifest.json.",
+        )
+    })?;
+    serde_json::from_str(&contents).map_err(|error| {
+        RfastaError::invalid_input(
+            "verify-shards",
+            format!("could not read {}: {error}", path.display()),
+            "Regenerate the shard set with `rfasta split`; the manifest is not valid.",
+        )
+    })
+}
+
+/// Re-checks every shard in `dir` against the directory's manifest.
+///
+/// Each listed shard must exist with the recorded size and SHA-256. All problems are collected
+/// into a single error so one run reports every damaged or missing shard. Returns the manifest
+/// when the shard set is intact.
+///
+/// # Example
+/// ```no_run
+/// use rfasta::shard::verify_shards;
+///
+/// let manifest = verify_shards("shards")?;
+/// println!("{} shards verified", manifest.shards.len());
+/// # Ok::<(), rfasta::RfastaError>(())
+/// ```
+pub fn verify_shards<P: AsRef<Path>>(dir: P) -> Result<ShardManifest, RfastaError> {
+    let dir = dir.as_ref();
+    let manifest = read_shard_manifest(dir)?;
+    let mut problems = Vec::new();
+    for shard in &manifest.shards {
+        let path = dir.join(&shard.path);
+        match file_digest(&path) {
+            Ok((bytes, _)) if bytes != shard.bytes => problems.push(format!(
+                "{}: expected {} bytes, found {bytes}",
+                shard.path, shard.bytes
+            )),
+            Ok((_, sha256)) if sha256 != shard.sha256 => {
+                problems.push(format!("{}: SHA-256 mismatch", shard.path))
+            }
+            Ok(_) => {}
+            Err(error) if error.kind() == io::ErrorKind::NotFound => {
+                problems.push(format!("{}: missing", shard.path))
+            }
+            Err(source) => {
+                return Err(RfastaError::io(
+                    "verify-shards",
+                    Some(&path),
+                    source,
+                    "Check that the shard files are readable.",
+                ))
+            }
+        }
+    }
+    if !problems.is_empty() {
+        return Err(RfastaError::invalid_input(
+            "verify-shards",
+            format!(
+                "{} of {} shards in {} do not match the manifest: {}",
+                problems.len(),
+                manifest.shards.len(),
+                dir.display(),
+                problems.join("; ")
+            ),
+            "Re-run `rfasta split` or re-copy the listed shards before using this shard set.",
+        ));
+    }
+    Ok(manifest)
+}
+
+/// Returns the size and hex SHA-256 of a file.
+fn file_digest(path: &Path) -> io::Result<(u64, String)> {
+    let mut file = File::open(path)?;
+    let mut hasher = HashingWriter::new(io::sink());
+    io::copy(&mut file, &mut hasher)?;
+    Ok(hasher.finish())
+}
+
+/// Writer that passes bytes through while counting them and computing their SHA-256.
+pub(crate) struct HashingWriter<W> {
+    inner: W,
+    hasher: Sha256,
+    bytes: u64,
+}
+
+impl<W> HashingWriter<W> {
+    pub(crate) fn new(inner: W) -> Self {
+        Self {
+            inner,
+            hasher: Sha256::new(),
+            bytes: 0,
+        }
+    }
+
+    /// Swaps in a new underlying writer, keeping the running byte count and digest.
+    pub(crate) fn replace_inner<V>(self, inner: V) -> (HashingWriter<V>, W) {
+        let hashing = HashingWriter {
+            inner,
+            hasher: self.hasher,
+            bytes: self.bytes,
+        };
+        (hashing, self.inner)
+    }
+
+    /// Returns the byte count and lowercase hex SHA-256 of everything written.
+    pub(crate) fn finish(self) -> (u64, String) {
+        let digest = self.hasher.finalize();
+        let hex = digest.iter().map(|byte| format!("{byte:02x}")).collect();
+        (self.bytes, hex)
+    }
+}
+
+impl<W: Write> Write for HashingWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let written = self.inner.write(buf)?;
+        self.hasher.update(&buf[..written]);
+        self.bytes += written as u64;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hashing_writer_matches_reference_digest() {
+        let mut writer = HashingWriter::new(Vec::new());
+        writer.write_all(b"abc").unwrap();
+        let (bytes, sha256) = writer.finish();
+        assert_eq!(bytes, 3);
+        assert_eq!(
+            sha256,
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+    }
+}
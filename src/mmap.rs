@@ -0,0 +1,241 @@
+use std::fs::File;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+use memchr::memchr_iter;
+use memmap2::Mmap;
+
+use crate::byte_parser::trim_line;
+use crate::compression::InputCompression;
+use crate::errors::RfastaError;
+use crate::io::FastaRecord;
+
+/// Zero-copy random access to an uncompressed FASTA file through a memory map.
+///
+/// Opening only maps the file. The first call that needs record boundaries scans the file once and
+/// keeps one `u64` offset per record; after that, [`MmapFasta::get`] is a table lookup and the
+/// returned [`MmapRecord`] borrows straight from the map. The scan applies the parser's rules, so
+/// records match what [`parse_fasta_file`] returns, except that duplicate headers are allowed.
+///
+/// The file must not be modified while it is mapped: the records would change under the borrow,
+/// or the process could be killed if the file shrinks.
+///
+/// # Example
+/// ```no_run
+/// use rfasta::index::MmapFasta;
+///
+/// let fasta = MmapFasta::open("uniref50.fasta")?;
+/// let residues: usize = fasta.records()?.map(|record| record.len()).sum();
+/// let tenth = fasta.get(9)?.expect("at least ten records");
+/// println!("{residues} residues; record 10 is {}", tenth.header());
+/// # Ok::<(), rfasta::RfastaError>(())
+/// ```
+///
+/// [`parse_fasta_file`]: crate::parse::parse_fasta_file
+pub struct MmapFasta {
+    path: PathBuf,
+    map: Mmap,
+    /// Offsets of each record's header line, filled by the first scan.
+    headers: OnceLock<Vec<u64>>,
+}
+
+impl MmapFasta {
+    /// Memory-maps a FASTA file. Compressed files are rejected, since their bytes are not records.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, RfastaError> {
+        let path = path.as_ref().to_path_buf();
+        let map_error = |source| {
+            RfastaError::io(
+                "mmap",
+                Some(&path),
+                source,
+                "Check that the input file exists and is readable.",
+            )
+        };
+        let file = File::open(&path).map_err(map_error)?;
+        // SAFETY: the map is read-only, and the type's docs require that the file is not modified
+        // while it is mapped.
+        let map = unsafe { Mmap::map(&file) }.map_err(map_error)?;
+        if !matches!(InputCompression::detect(&map[..]), InputCompression::None) {
+            return Err(RfastaError::invalid_input(
+                "mmap",
+                format!("{} is compressed", path.display()),
+                "Memory-mapped access needs an uncompressed FASTA; decompress the file first.",
+            ));
+        }
+        Ok(Self {
+            path,
+            map,
+            headers: OnceLock::new(),
+        })
+    }
+
+    /// Returns the number of records, scanning the file on first use.
+    pub fn len(&self) -> Result<usize, RfastaError> {
+        Ok(self.headers()?.len())
+    }
+
+    /// Returns `true` when the file holds no records.
+    pub fn is_empty(&self) -> Result<bool, RfastaError> {
+        Ok(self.headers()?.is_empty())
+    }
+
+    /// Returns record `index`, counting from zero, or `None` past the last record.
+    pub fn get(&self, index: usize) -> Result<Option<MmapRecord<'_>>, RfastaError> {
+        let headers = self.headers()?;
+        let Some(&start) = headers.get(index) else {
+            return Ok(None);
+        };
+        let end = headers
+            .get(index + 1)
+            .map_or(self.map.len(), |&end| end as usize);
+        Ok(Some(MmapRecord::new(&self.map[start as usize..end])))
+    }
+
+    /// Iterates over every record in file order.
+    pub fn records(&self) -> Result<impl Iterator<Item = MmapRecord<'_>> + '_, RfastaError> {
+        let headers = self.headers()?;
+        let ends = headers
+            .iter()
+            .skip(1)
+            .map(|&end| end as usize)
+            .chain([self.map.len()]);
+        Ok(headers
+            .iter()
+            .zip(ends)
+            .map(|(&start, end)| MmapRecord::new(&self.map[start as usize..end])))
+    }
+
+    fn headers(&self) -> Result<&[u64], RfastaError> {
+        if let Some(headers) = self.headers.get() {
+            return Ok(headers);
+        }
+        let headers = self.scan()?;
+        Ok(self.headers.get_or_init(|| headers))
+    }
+
+    /// Finds every header line, checking the file the way the parser would.
+    fn scan(&self) -> Result<Vec<u64>, RfastaError> {
+        let bytes: &[u8] = &self.map;
+        if let Err(error) = std::str::from_utf8(bytes) {
+            let valid = &bytes[..error.valid_up_to()];
+            return Err(RfastaError::malformed_fasta(
+                Some(&self.path),
+                memchr_iter(b'\n', valid).count() + 1,
+                "line is not valid UTF-8",
+                "Re-encode the file as UTF-8, or fix the offending line.",
+            ));
+        }
+
+        let mut headers = Vec::new();
+        let mut has_sequence = false;
+        let line_ends = memchr_iter(b'\n', bytes).map(|end| end + 1);
+        let mut line_start = 0;
+        for (line_number, line_end) in (1..).zip(line_ends.chain([bytes.len()])) {
+            if line_start == line_end {
+                break;
+            }
+            let text = trim_line(&bytes[line_start..line_end]);
+            match text.first() {
+                Some(b'>') => {
+                    self.check_has_sequence(&headers, has_sequence)?;
+                    headers.push(line_start as u64);
+                    has_sequence = false;
+                }
+                Some(_) if headers.is_empty() => {
+                    return Err(RfastaError::malformed_fasta(
+                        Some(&self.path),
+                        line_number,
+                        "found sequence data before the first FASTA header",
+                        "Make sure the file starts with a header line beginning with `>`.",
+                    ));
+                }
+                Some(_) => has_sequence = true,
+                None => {}
+            }
+            line_start = line_end;
+        }
+        self.check_has_sequence(&headers, has_sequence)?;
+        Ok(headers)
+    }
+
+    /// Fails when the last record in `headers` has no sequence.
+    fn check_has_sequence(&self, headers: &[u64], has_sequence: bool) -> Result<(), RfastaError> {
+        match headers.last() {
+            Some(&start) if !has_sequence => Err(RfastaError::empty_sequence(
+                Some(&self.path),
+                MmapRecord::new(&self.map[start as usize..])
+                    .header()
+                    .to_string(),
+                "Ensure each FASTA header is followed by at least one sequence line.",
+            )),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// A record borrowed from an [`MmapFasta`], still laid out as it is in the file.
+///
+/// The sequence keeps the file's line breaks and letter case until it is read: [`MmapRecord::lines`]
+/// and [`MmapRecord::residues`] skip the breaks without copying, and [`MmapRecord::to_owned`]
+/// builds the uppercased [`FastaRecord`] the parser would return.
+#[derive(Clone, Debug)]
+pub struct MmapRecord<'a> {
+    /// The header line after `>`, trimmed.
+    header: &'a str,
+    /// Every line after the header line, up to the next record.
+    body: &'a [u8],
+}
+
+impl<'a> MmapRecord<'a> {
+    /// Splits a scanned record that starts with its header line.
+    fn new(bytes: &'a [u8]) -> Self {
+        let header_end = memchr::memchr(b'\n', bytes).map_or(bytes.len(), |end| end + 1);
+        let header = std::str::from_utf8(&trim_line(&bytes[..header_end])[1..])
+            .expect("the scan checked that the file is UTF-8");
+        Self {
+            header,
+            body: &bytes[header_end..],
+        }
+    }
+
+    /// FASTA header text without the leading `>`.
+    pub fn header(&self) -> &'a str {
+        self.header
+    }
+
+    /// Iterates over the trimmed, non-empty sequence lines as they appear in the file.
+    pub fn lines(&self) -> impl Iterator<Item = &'a [u8]> + 'a {
+        self.body
+            .split(|&byte| byte == b'\n')
+            .map(trim_line)
+            .filter(|line| !line.is_empty())
+    }
+
+    /// Iterates over the uppercased residues without allocating.
+    pub fn residues(&self) -> impl Iterator<Item = u8> + 'a {
+        self.lines()
+            .flat_map(|line| line.iter().map(u8::to_ascii_uppercase))
+    }
+
+    /// Returns the number of residues.
+    pub fn len(&self) -> usize {
+        self.lines().map(<[u8]>::len).sum()
+    }
+
+    /// Returns `true` when the record has no residues, which a scanned record never does.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Copies the record into an owned, uppercased [`FastaRecord`].
+    pub fn to_owned(&self) -> FastaRecord {
+        let mut sequence = Vec::with_capacity(self.body.len());
+        for line in self.lines() {
+            sequence.extend_from_slice(line);
+        }
+        sequence.make_ascii_uppercase();
+        let sequence =
+            String::from_utf8(sequence).expect("the scan checked that the file is UTF-8");
+        FastaRecord::new(self.header, sequence)
+    }
+}
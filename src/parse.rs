@@ -1,10 +1,13 @@
 //! Streaming and in-memory FASTA parsing.
 //!
 //! Use this module when you need to read protein FASTA records from a file or from any buffered
-//! reader. The streaming visitors are the right default for large files; the collection helpers are
-//! more convenient for workflows that need the full dataset in memory.
+//! reader. The streaming visitors and the [`FastaReader`] iterator are the right default for large
+//! files; the collection helpers are more convenient for workflows that need the full dataset in
+//! memory. The `_refs` visitors hand out borrowed [`RecordRef`] views over reused buffers for passes
+//! that do not keep the records.
 
+pub use crate::byte_parser::RecordRef;
 pub use crate::io::{
-    parse_fasta_file, parse_fasta_reader, visit_fasta_file, visit_fasta_reader, FastaRecord,
-    ParseOptions,
+    parse_fasta_file, parse_fasta_reader, visit_fasta_file, visit_fasta_file_refs,
+    visit_fasta_reader, visit_fasta_reader_refs, FastaReader, FastaRecord, FilePart, ParseOptions,
 };
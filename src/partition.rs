@@ -0,0 +1,667 @@
+use std::cmp::Reverse;
+use std::collections::HashSet;
+use std::io::BufRead;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::configs::{
+    DEFAULT_MAX_KMER_FREQUENCY, DEFAULT_MIN_SIMILARITY, DEFAULT_SIMILARITY_KMER_SIZE,
+};
+use crate::errors::RfastaError;
+use crate::io::{
+    input_stem, open_fasta_input, visit_fasta_reader_with_source, FastaRecord, ParseOptions,
+};
+use crate::manifest::{ShardEntry, ShardManifest};
+use crate::sampling::SplitMix64;
+use crate::shard_pool::HandlePool;
+use crate::sharding::{
+    prepare_output_dir, shard_hash, split_reader, ShardKey, ShardStrategy, ShardWriter,
+    SplitOptions, DEFAULT_SHARD_STEM,
+};
+use crate::utilities::{group_similar_sequences, max_cross_label_similarity};
+
+/// One named output of [`split_fasta_file_by_fraction`], such as `train`.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct Partition {
+    /// Name used in the output file name, `{stem}_{name}.fasta`. Must differ from every other
+    /// partition's name, ignoring case.
+    pub name: String,
+    /// Share of records expected in this partition. Fractions are normalized by their sum, so
+    /// `0.8, 0.1, 0.1` and `8, 1, 1` are equivalent.
+    pub fraction: f64,
+}
+
+impl Partition {
+    /// Creates a partition named `name` with the given share of records.
+    pub fn new(name: impl Into<String>, fraction: f64) -> Self {
+        Self {
+            name: name.into(),
+            fraction,
+        }
+    }
+
+    /// Returns `train`, `val`, and `test` partitions with the given fractions.
+    pub fn train_val_test(train: f64, val: f64, test: f64) -> Vec<Self> {
+        vec![
+            Self::new("train", train),
+            Self::new("val", val),
+            Self::new("test", test),
+        ]
+    }
+}
+
+/// How [`split_fasta_file_by_fraction`] picks a partition for each record.
+///
+/// Either way, each record draws a 64-bit value that is mapped onto the cumulative fractions, so
+/// partition sizes match the fractions up to sampling noise rather than exactly.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(tag = "method", rename_all = "snake_case")]
+pub enum PartitionAssignment {
+    /// One SplitMix64 draw per record, in input order. The same seed and input order give the same
+    /// partitions on every platform and in every release.
+    Random { seed: u64 },
+    /// [`shard_hash`] of `key`. Independent of input order, so records keep their partition when a
+    /// dataset is reordered or extended, and `ShardKey::Sequence` keeps identical sequences
+    /// together.
+    Hashed { key: ShardKey },
+}
+
+/// Settings for grouping similar sequences in [`split_fasta_file_by_similarity`].
+///
+/// Each sequence is compared with the earlier sequences it shares a k-mer with, found through an
+/// index from k-mer to sequences. The work per sequence is its number of distinct k-mers times the
+/// length of those index entries, so without a cap it grows with the dataset and the whole run is
+/// close to quadratic in the number of records. [`max_kmer_frequency`](Self::max_kmer_frequency)
+/// caps each index entry, which bounds the total at records × k-mers per record × the cap.
+/// The [`LeakageReport`] is computed by a separate, uncapped pass over pairs in different
+/// partitions, so it reports any leakage the cap causes.
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize)]
+pub struct SimilarityGrouping {
+    /// Length of the k-mers compared between sequences.
+    pub kmer_size: usize,
+    /// Sequences whose k-mer similarity reaches this value, from 0 to 1, are kept in one
+    /// partition. Similarity is the share of the smaller k-mer set found in the other sequence.
+    pub min_similarity: f64,
+    /// Number of sequences after which a k-mer is ignored for every later sequence.
+    ///
+    /// Very common k-mers, such as low-complexity repeats, then stop contributing to similarity,
+    /// so pairs that mostly share such k-mers score lower than they would uncapped and may be
+    /// split. Similarity is never overestimated. Use `usize::MAX` for exact similarities.
+    pub max_kmer_frequency: usize,
+}
+
+impl Default for SimilarityGrouping {
+    fn default() -> Self {
+        Self {
+            kmer_size: DEFAULT_SIMILARITY_KMER_SIZE,
+            min_similarity: DEFAULT_MIN_SIMILARITY,
+            max_kmer_frequency: DEFAULT_MAX_KMER_FREQUENCY,
+        }
+    }
+}
+
+impl SimilarityGrouping {
+    fn validate(&self) -> Result<(), RfastaError> {
+        if self.kmer_size == 0 {
+            return Err(RfastaError::invalid_input(
+                "partition",
+                "k-mer size must be at least 1",
+                "Pass a --kmer-size of 1 or more; 5 suits most protein datasets.",
+            ));
+        }
+        if !(self.min_similarity > 0.0 && self.min_similarity <= 1.0) {
+            return Err(RfastaError::invalid_input(
+                "partition",
+                format!("invalid minimum similarity {}", self.min_similarity),
+                "Pass a --min-similarity greater than 0 and at most 1.",
+            ));
+        }
+        if self.max_kmer_frequency == 0 {
+            return Err(RfastaError::invalid_input(
+                "partition",
+                "k-mer frequency cap must be at least 1",
+                "Pass a --max-kmer-frequency of 1 or more.",
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Leakage check recorded in the manifest of a [`split_fasta_file_by_similarity`] run.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct LeakageReport {
+    /// Number of similarity groups the records formed.
+    pub groups: usize,
+    /// Records in the largest group.
+    pub largest_group: usize,
+    /// Highest k-mer similarity between two records in different partitions, or 0 when none share
+    /// a k-mer.
+    ///
+    /// This is exact: it is computed after assignment by comparing every pair of records in
+    /// different partitions that share a k-mer, without
+    /// [`SimilarityGrouping::max_kmer_frequency`]. It stays below
+    /// [`SimilarityGrouping::min_similarity`] unless the cap split a group, so a higher value
+    /// means similar sequences leaked between partitions.
+    pub max_cross_similarity: f64,
+    /// Headers of a cross-partition pair with that similarity.
+    pub closest_pair: Option<(String, String)>,
+}
+
+/// Streams a FASTA file into one file per partition, `{stem}_{name}.fasta`.
+///
+/// The input is read once. Every partition file is created, even if no record lands in it, and
+/// `manifest.json` lists each partition's record and residue counts. Returns that manifest.
+///
+/// # Example
+/// ```no_run
+/// use rfasta::shard::{
+///     split_fasta_file_by_fraction, Partition, PartitionAssignment, SplitOptions,
+/// };
+///
+/// let manifest = split_fasta_file_by_fraction(
+///     "proteins.fasta",
+///     "splits",
+///     &Partition::train_val_test(0.8, 0.1, 0.1),
+///     PartitionAssignment::Random { seed: 7 },
+///     &SplitOptions::default(),
+///     true,
+/// )?;
+/// assert_eq!(manifest.shards[0].path, "proteins_train.fasta");
+/// # Ok::<(), rfasta::RfastaError>(())
+/// ```
+pub fn split_fasta_file_by_fraction<P: AsRef<Path>, Q: AsRef<Path>>(
+    input_path: P,
+    output_dir: Q,
+    partitions: &[Partition],
+    assignment: PartitionAssignment,
+    options: &SplitOptions,
+    verbose: bool,
+) -> Result<ShardManifest, RfastaError> {
+    let input_path = input_path.as_ref();
+    let stem = input_stem(input_path).unwrap_or_else(|| DEFAULT_SHARD_STEM.to_string());
+    let mut reader = open_fasta_input(input_path)?;
+    split_reader(
+        &mut reader,
+        Some(input_path),
+        output_dir.as_ref(),
+        &stem,
+        &ShardStrategy::Partitioned {
+            partitions: partitions.to_vec(),
+            assignment,
+        },
+        options,
+        verbose,
+    )
+}
+
+/// Streams FASTA records from any buffered reader into one file per partition.
+///
+/// This is the reader-based counterpart to [`split_fasta_file_by_fraction`].
+pub fn split_fasta_reader_by_fraction<R: BufRead, Q: AsRef<Path>>(
+    mut reader: R,
+    output_dir: Q,
+    stem: &str,
+    partitions: &[Partition],
+    assignment: PartitionAssignment,
+    options: &SplitOptions,
+    verbose: bool,
+) -> Result<ShardManifest, RfastaError> {
+    split_reader(
+        &mut reader,
+        None,
+        output_dir.as_ref(),
+        stem,
+        &ShardStrategy::Partitioned {
+            partitions: partitions.to_vec(),
+            assignment,
+        },
+        options,
+        verbose,
+    )
+}
+
+/// Splits a FASTA file into partitions without letting similar sequences cross between them.
+///
+/// Records with identical sequences, or with a k-mer similarity of at least
+/// `grouping.min_similarity`, are grouped (transitively), and each group goes to a single partition.
+/// Groups are placed largest first into the partition furthest below its share, so partition sizes
+/// stay close to the fractions; `seed` orders groups of equal size. The manifest's `leakage` entry
+/// reports the highest similarity left between partitions.
+///
+/// Unlike [`split_fasta_file_by_fraction`], this holds every record in memory, and comparison time
+/// grows with the number of sequence pairs that share a k-mer, up to the cap described on
+/// [`SimilarityGrouping`].
+///
+/// # Example
+/// ```no_run
+/// use rfasta::shard::{
+///     split_fasta_file_by_similarity, Partition, SimilarityGrouping, SplitOptions,
+/// };
+///
+/// let manifest = split_fasta_file_by_similarity(
+///     "proteins.fasta",
+///     "splits",
+///     &Partition::train_val_test(0.8, 0.1, 0.1),
+///     &SimilarityGrouping::default(),
+///     7,
+///     &SplitOptions::default(),
+///     true,
+/// )?;
+/// let leakage = manifest.leakage.expect("similarity splits report leakage");
+/// assert!(leakage.max_cross_similarity < 0.5);
+/// # Ok::<(), rfasta::RfastaError>(())
+/// ```
+pub fn split_fasta_file_by_similarity<P: AsRef<Path>, Q: AsRef<Path>>(
+    input_path: P,
+    output_dir: Q,
+    partitions: &[Partition],
+    grouping: &SimilarityGrouping,
+    seed: u64,
+    options: &SplitOptions,
+    verbose: bool,
+) -> Result<ShardManifest, RfastaError> {
+    let input_path = input_path.as_ref();
+    let stem = input_stem(input_path).unwrap_or_else(|| DEFAULT_SHARD_STEM.to_string());
+    let mut reader = open_fasta_input(input_path)?;
+    split_reader(
+        &mut reader,
+        Some(input_path),
+        output_dir.as_ref(),
+        &stem,
+        &ShardStrategy::Grouped {
+            partitions: partitions.to_vec(),
+            grouping: *grouping,
+            seed,
+        },
+        options,
+        verbose,
+    )
+}
+
+/// Reads FASTA records from any buffered reader into similarity-aware partitions.
+///
+/// This is the reader-based counterpart to [`split_fasta_file_by_similarity`].
+#[allow(clippy::too_many_arguments)]
+pub fn split_fasta_reader_by_similarity<R: BufRead, Q: AsRef<Path>>(
+    mut reader: R,
+    output_dir: Q,
+    stem: &str,
+    partitions: &[Partition],
+    grouping: &SimilarityGrouping,
+    seed: u64,
+    options: &SplitOptions,
+    verbose: bool,
+) -> Result<ShardManifest, RfastaError> {
+    split_reader(
+        &mut reader,
+        None,
+        output_dir.as_ref(),
+        stem,
+        &ShardStrategy::Grouped {
+            partitions: partitions.to_vec(),
+            grouping: *grouping,
+            seed,
+        },
+        options,
+        verbose,
+    )
+}
+
+/// Maps records to partitions through cumulative 64-bit thresholds.
+struct Partitioner {
+    /// Exclusive upper bound of each partition's range in `0..2^64`.
+    thresholds: Vec<u128>,
+    source: DrawSource,
+}
+
+enum DrawSource {
+    Random(SplitMix64),
+    Hashed(ShardKey),
+}
+
+impl Partitioner {
+    fn new(partitions: &[Partition], assignment: PartitionAssignment) -> Self {
+        const SPAN: f64 = 18_446_744_073_709_551_616.0; // 2^64
+        let total: f64 = partitions.iter().map(|partition| partition.fraction).sum();
+        let mut cumulative = 0.0;
+        let mut thresholds: Vec<u128> = partitions
+            .iter()
+            .map(|partition| {
+                cumulative += partition.fraction;
+                (cumulative / total * SPAN) as u128
+            })
+            .collect();
+        // Rounding must not leave the top of the range unassigned.
+        if let Some(last) = thresholds.last_mut() {
+            *last = 1 << 64;
+        }
+        let source = match assignment {
+            PartitionAssignment::Random { seed } => DrawSource::Random(SplitMix64::new(seed)),
+            PartitionAssignment::Hashed { key } => DrawSource::Hashed(key),
+        };
+        Self { thresholds, source }
+    }
+
+    fn assign(&mut self, record: &FastaRecord) -> usize {
+        let draw = match &mut self.source {
+            DrawSource::Random(rng) => rng.next_u64(),
+            DrawSource::Hashed(key) => shard_hash(key.value(record)),
+        };
+        self.thresholds
+            .iter()
+            .position(|&threshold| u128::from(draw) < threshold)
+            .expect("the last threshold covers every draw")
+    }
+}
+
+fn validate_partitions(partitions: &[Partition]) -> Result<(), RfastaError> {
+    if partitions.is_empty() {
+        return Err(RfastaError::invalid_input(
+            "partition",
+            "no partitions were given",
+            "Pass at least one ratio, e.g. --ratios 0.8,0.1,0.1.",
+        ));
+    }
+    let mut names = HashSet::new();
+    for partition in partitions {
+        let name = partition.name.as_str();
+        if name.is_empty()
+            || !name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.'))
+        {
+            return Err(RfastaError::invalid_input(
+                "partition",
+                format!("invalid partition name `{name}`"),
+                "Partition names become file names; use letters, digits, `_`, `-`, or `.`.",
+            ));
+        }
+        // Names that differ only in case map to the same file on case-insensitive file systems.
+        if !names.insert(name.to_ascii_lowercase()) {
+            return Err(RfastaError::invalid_input(
+                "partition",
+                format!("partition name `{name}` is used more than once"),
+                "Give every partition a distinct name; names that differ only in case count as the same.",
+            ));
+        }
+        if !partition.fraction.is_finite() || partition.fraction < 0.0 {
+            return Err(RfastaError::invalid_input(
+                "partition",
+                format!(
+                    "partition `{name}` has invalid fraction {}",
+                    partition.fraction
+                ),
+                "Fractions must be finite and non-negative.",
+            ));
+        }
+    }
+    if partitions.iter().all(|partition| partition.fraction == 0.0) {
+        return Err(RfastaError::invalid_input(
+            "partition",
+            "partition fractions sum to zero",
+            "Give at least one partition a positive fraction.",
+        ));
+    }
+    Ok(())
+}
+
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn split_reader_partitioned<R: BufRead>(
+    reader: &mut R,
+    source_name: Option<&Path>,
+    output_dir: &Path,
+    stem: &str,
+    partitions: &[Partition],
+    assignment: &PartitionAssignment,
+    options: &SplitOptions,
+    verbose: bool,
+) -> Result<Vec<ShardEntry>, RfastaError> {
+    validate_partitions(partitions)?;
+    let mut writers = create_partition_writers(output_dir, stem, partitions, options)?;
+    let mut partitioner = Partitioner::new(partitions, *assignment);
+
+    visit_fasta_reader_with_source(
+        reader,
+        ParseOptions {
+            expect_unique_header: false,
+            part: None,
+        },
+        source_name,
+        &mut |record| {
+            let target = partitioner.assign(&record);
+            writers[target].write(&record, options.write.line_length)
+        },
+    )?;
+
+    finish_partition_writers(writers, partitions, output_dir, verbose)
+}
+
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn split_reader_grouped<R: BufRead>(
+    reader: &mut R,
+    source_name: Option<&Path>,
+    output_dir: &Path,
+    stem: &str,
+    partitions: &[Partition],
+    grouping: &SimilarityGrouping,
+    seed: u64,
+    options: &SplitOptions,
+    verbose: bool,
+) -> Result<(Vec<ShardEntry>, LeakageReport), RfastaError> {
+    validate_partitions(partitions)?;
+    grouping.validate()?;
+    let mut records = Vec::new();
+    visit_fasta_reader_with_source(
+        reader,
+        ParseOptions {
+            expect_unique_header: false,
+            part: None,
+        },
+        source_name,
+        &mut |record| {
+            records.push(record);
+            Ok(())
+        },
+    )?;
+
+    if verbose {
+        eprintln!(
+            "[INFO]: Grouping {} sequences by {}-mer similarity",
+            records.len(),
+            grouping.kmer_size
+        );
+    }
+    let similar = group_similar_sequences(
+        &records,
+        grouping.kmer_size,
+        grouping.min_similarity,
+        grouping.max_kmer_frequency,
+    );
+    let groups = &similar.groups;
+    if verbose && similar.capped_kmers > 0 {
+        eprintln!(
+            "[WARNING]: {} k-mers reached --max-kmer-frequency {} and were ignored while grouping; \
+             similar sequences may be split (see max_cross_similarity)",
+            similar.capped_kmers, grouping.max_kmer_frequency
+        );
+    }
+    let targets = assign_groups(groups, partitions, seed);
+    let mut writers = create_partition_writers(output_dir, stem, partitions, options)?;
+    for (record, &target) in records.iter().zip(&targets) {
+        writers[target].write(record, options.write.line_length)?;
+    }
+    let shards = finish_partition_writers(writers, partitions, output_dir, verbose)?;
+
+    let mut group_sizes = vec![0; groups.iter().max().map_or(0, |&last| last + 1)];
+    groups.iter().for_each(|&group| group_sizes[group] += 1);
+    let closest = max_cross_label_similarity(&records, &targets, grouping.kmer_size);
+    let report = LeakageReport {
+        groups: group_sizes.len(),
+        largest_group: group_sizes.iter().copied().max().unwrap_or(0),
+        max_cross_similarity: closest.map_or(0.0, |(similarity, _, _)| similarity),
+        closest_pair: closest
+            .map(|(_, left, right)| (records[left].header.clone(), records[right].header.clone())),
+    };
+    if verbose {
+        eprintln!(
+            "[INFO]: Assigned {} similarity groups (largest: {} sequences)",
+            report.groups, report.largest_group
+        );
+        match &report.closest_pair {
+            Some((left, right)) => eprintln!(
+                "[INFO]: Max cross-partition similarity: {:.3} ({left} / {right})",
+                report.max_cross_similarity
+            ),
+            None => eprintln!("[INFO]: No sequences in different partitions share a k-mer"),
+        }
+    }
+    Ok((shards, report))
+}
+
+/// Creates one empty `{stem}_{name}` file per partition.
+fn create_partition_writers(
+    output_dir: &Path,
+    stem: &str,
+    partitions: &[Partition],
+    options: &SplitOptions,
+) -> Result<Vec<ShardWriter>, RfastaError> {
+    let extension = prepare_output_dir(output_dir, &options.write)?;
+    let pool = HandlePool::shared(options.max_open_files, &options.write);
+    partitions
+        .iter()
+        .enumerate()
+        .map(|(id, partition)| {
+            ShardWriter::create(
+                &pool,
+                id,
+                output_dir.join(format!("{stem}_{}.{extension}", partition.name)),
+            )
+        })
+        .collect()
+}
+
+/// Completes the partition files and logs their share of the records.
+fn finish_partition_writers(
+    writers: Vec<ShardWriter>,
+    partitions: &[Partition],
+    output_dir: &Path,
+    verbose: bool,
+) -> Result<Vec<ShardEntry>, RfastaError> {
+    let shards = writers
+        .into_iter()
+        .map(|writer| writer.finish(false))
+        .collect::<Result<Vec<_>, _>>()?;
+    if verbose {
+        let total: usize = shards.iter().map(|shard| shard.records).sum();
+        for (partition, shard) in partitions.iter().zip(&shards) {
+            let percent = if total == 0 {
+                0.0
+            } else {
+                shard.records as f64 * 100.0 / total as f64
+            };
+            eprintln!(
+                "[INFO]: {}: {} sequences ({percent:.1}%), {} residues -> {}",
+                partition.name,
+                shard.records,
+                shard.residues,
+                output_dir.join(&shard.path).display()
+            );
+        }
+    }
+    Ok(shards)
+}
+
+/// Returns the partition of every record, placing each group as a whole.
+///
+/// Groups are visited largest first, with ties in seeded random order, and each goes to the
+/// partition furthest below its target record count. Large groups therefore land in large
+/// partitions, and singletons fill the remaining gaps, keeping counts close to the fractions.
+fn assign_groups(groups: &[usize], partitions: &[Partition], seed: u64) -> Vec<usize> {
+    let mut sizes = vec![0usize; groups.iter().max().map_or(0, |&last| last + 1)];
+    groups.iter().for_each(|&group| sizes[group] += 1);
+    let mut order: Vec<usize> = (0..sizes.len()).collect();
+    SplitMix64::new(seed).shuffle(&mut order);
+    order.sort_by_key(|&group| Reverse(sizes[group]));
+
+    let total_fraction: f64 = partitions.iter().map(|partition| partition.fraction).sum();
+    let targets: Vec<f64> = partitions
+        .iter()
+        .map(|partition| partition.fraction / total_fraction * groups.len() as f64)
+        .collect();
+    let mut assigned = vec![0usize; partitions.len()];
+    let mut partition_of_group = vec![0; sizes.len()];
+    for group in order {
+        let deficit = |partition: usize| targets[partition] - assigned[partition] as f64;
+        let target = (0..partitions.len())
+            .max_by(|&left, &right| {
+                deficit(left)
+                    .total_cmp(&deficit(right))
+                    .then(right.cmp(&left))
+            })
+            .expect("partitions are validated to be non-empty");
+        assigned[target] += sizes[group];
+        partition_of_group[group] = target;
+    }
+    groups
+        .iter()
+        .map(|&group| partition_of_group[group])
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn thresholds_follow_normalized_fractions() {
+        let partitions = vec![
+            Partition::new("a", 2.0),
+            Partition::new("empty", 0.0),
+            Partition::new("b", 2.0),
+        ];
+        let partitioner = Partitioner::new(&partitions, PartitionAssignment::Random { seed: 1 });
+        assert_eq!(partitioner.thresholds, vec![1 << 63, 1 << 63, 1 << 64]);
+    }
+
+    #[test]
+    fn groups_are_placed_whole_and_largest_first() {
+        let partitions = Partition::train_val_test(0.6, 0.2, 0.2);
+        // Group 0 has four records, group 1 two, and groups 2-5 one each.
+        let groups = [0, 0, 1, 2, 0, 3, 1, 4, 0, 5];
+        let targets = assign_groups(&groups, &partitions, 3);
+        for (record, &group) in groups.iter().enumerate() {
+            let first = groups.iter().position(|&other| other == group).unwrap();
+            assert_eq!(targets[record], targets[first]);
+        }
+        assert_eq!(targets[0], 0);
+        let counts: Vec<usize> = (0..3)
+            .map(|partition| {
+                targets
+                    .iter()
+                    .filter(|&&target| target == partition)
+                    .count()
+            })
+            .collect();
+        assert_eq!(counts, vec![6, 2, 2]);
+        assert_eq!(targets, assign_groups(&groups, &partitions, 3));
+    }
+
+    #[test]
+    fn seeded_assignment_is_reproducible_and_close_to_the_ratios() {
+        let partitions = Partition::train_val_test(0.8, 0.1, 0.1);
+        let record = FastaRecord::new("seq", "ACDE");
+        let draw = |seed| {
+            let mut partitioner =
+                Partitioner::new(&partitions, PartitionAssignment::Random { seed });
+            (0..10_000)
+                .map(|_| partitioner.assign(&record))
+                .collect::<Vec<_>>()
+        };
+        let first = draw(7);
+        assert_eq!(first, draw(7));
+        assert_ne!(first, draw(8));
+        let train = first.iter().filter(|&&target| target == 0).count();
+        assert!((7_700..8_300).contains(&train), "{train}");
+    }
+}
@@ -0,0 +1,128 @@
+use std::num::NonZeroUsize;
+use std::sync::mpsc;
+use std::thread;
+
+use crate::errors::RfastaError;
+
+/// Resolves a requested thread count, where `0` means every available core.
+pub(crate) fn resolve_threads(threads: usize) -> usize {
+    match threads {
+        0 => thread::available_parallelism().map_or(1, NonZeroUsize::get),
+        threads => threads,
+    }
+}
+
+/// Runs `work` on `threads` worker threads over the items `produce` submits, and hands each
+/// result to `consume` in submission order.
+///
+/// Items are dealt to workers round-robin and results are collected in the same rotation, so no
+/// reordering buffer is needed. At most two items per worker are in flight, which bounds memory.
+/// `produce` and `consume` run on the calling thread, so neither needs to be `Send`. An error from
+/// `consume` stops the pipeline at once; an error from `produce` is returned after the items
+/// already submitted are consumed.
+pub(crate) fn ordered_pipeline<T, U, P, W, C>(
+    threads: usize,
+    produce: P,
+    work: W,
+    mut consume: C,
+) -> Result<(), RfastaError>
+where
+    T: Send,
+    U: Send,
+    P: FnOnce(&mut dyn FnMut(T) -> Result<(), RfastaError>) -> Result<(), RfastaError>,
+    W: Fn(T) -> U + Sync,
+    C: FnMut(U) -> Result<(), RfastaError>,
+{
+    let threads = threads.max(1);
+    thread::scope(|scope| {
+        let work = &work;
+        let (inputs, outputs): (Vec<_>, Vec<_>) = (0..threads)
+            .map(|_| {
+                let (item_sender, items) = mpsc::sync_channel::<T>(1);
+                let (result_sender, results) = mpsc::sync_channel::<U>(1);
+                scope.spawn(move || {
+                    for item in items {
+                        if result_sender.send(work(item)).is_err() {
+                            break;
+                        }
+                    }
+                });
+                (item_sender, results)
+            })
+            .unzip();
+
+        // Dropping the channels on return, including on error, lets every worker exit.
+        let mut submitted = 0;
+        let mut consumed = 0;
+        let next_result = |consumed: &mut usize| {
+            let result = outputs[*consumed % threads]
+                .recv()
+                .expect("workers run until their input channel closes");
+            *consumed += 1;
+            result
+        };
+        let mut consumer_failed = false;
+        let produced = produce(&mut |item| {
+            if submitted - consumed == 2 * threads {
+                consume(next_result(&mut consumed)).inspect_err(|_| consumer_failed = true)?;
+            }
+            inputs[submitted % threads]
+                .send(item)
+                .expect("workers run until their input channel closes");
+            submitted += 1;
+            Ok(())
+        });
+        if consumer_failed {
+            return produced;
+        }
+        // Items submitted before a producer error still come first, as in a serial run.
+        while consumed < submitted {
+            consume(next_result(&mut consumed))?;
+        }
+        produced
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn results_arrive_in_submission_order() {
+        let mut results = Vec::new();
+        ordered_pipeline(
+            3,
+            |submit| (0..100u64).try_for_each(submit),
+            |item| {
+                // Uneven work so later items often finish first.
+                thread::sleep(std::time::Duration::from_micros((item * 7919) % 200));
+                item * 2
+            },
+            |result| {
+                results.push(result);
+                Ok(())
+            },
+        )
+        .unwrap();
+        assert_eq!(results, (0..100).map(|item| item * 2).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn consumer_errors_stop_the_pipeline() {
+        let mut consumed = 0;
+        let error = ordered_pipeline(
+            2,
+            |submit| (0..1000).try_for_each(submit),
+            |item: usize| item,
+            |item| {
+                consumed += 1;
+                if item == 10 {
+                    return Err(RfastaError::invalid_input("clean", "stop", "Stop."));
+                }
+                Ok(())
+            },
+        );
+        assert!(error.is_err());
+        assert_eq!(consumed, 11);
+    }
+}
@@ -44,6 +44,7 @@ pub fn read_fasta(
         &filename,
         ParseOptions {
             expect_unique_header,
+            part: None,
         },
         verbose,
     )
@@ -68,6 +69,7 @@ pub fn write_fasta(
         WriteOptions {
             line_length,
             append: append_to_fasta,
+            ..WriteOptions::default()
         },
         verbose,
     )
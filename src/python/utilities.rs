@@ -3,6 +3,7 @@ use std::collections::HashMap;
 use pyo3::prelude::*;
 use pyo3::types::PyDict;
 
+use crate::corrections::{CorrectionRule, CorrectionRules};
 use crate::io::FastaRecord;
 use crate::utilities;
 
@@ -36,6 +37,45 @@ fn records_to_rows(records: Vec<FastaRecord>) -> Vec<Vec<String>> {
         .collect()
 }
 
+/// Converts `[{"pattern": ..., "replacement": ..., "priority": 0, "regex": False}, ...]` into
+/// compiled rules. `priority` and `regex` are optional.
+fn dicts_to_rules(rules: Option<Vec<&PyDict>>) -> PyResult<Option<CorrectionRules>> {
+    let Some(rules) = rules else {
+        return Ok(None);
+    };
+    let mut parsed = Vec::with_capacity(rules.len());
+    for rule in rules {
+        let required = |key: &str| -> PyResult<String> {
+            rule.get_item(key)
+                .ok_or_else(|| {
+                    pyo3::exceptions::PyKeyError::new_err(format!(
+                        "correction rule is missing `{key}`"
+                    ))
+                })?
+                .extract()
+        };
+        let pattern = required("pattern")?;
+        let replacement = required("replacement")?;
+        let priority = match rule.get_item("priority") {
+            Some(value) => value.extract()?,
+            None => 0,
+        };
+        let is_regex = match rule.get_item("regex") {
+            Some(value) => value.extract()?,
+            None => false,
+        };
+        let parsed_rule = if is_regex {
+            CorrectionRule::regex(pattern, replacement)
+        } else {
+            CorrectionRule::literal(pattern, replacement)
+        };
+        parsed.push(parsed_rule.with_priority(priority));
+    }
+    CorrectionRules::new(parsed)
+        .map(Some)
+        .map_err(crate::python::to_py_err)
+}
+
 #[pyfunction]
 pub fn build_custom_dictionary(
     additional_dictionary: &PyDict,
@@ -48,16 +88,18 @@ pub fn build_custom_dictionary(
 }
 
 #[pyfunction]
-#[pyo3(signature = (seq, alignment = false, correction_dictionary = None))]
+#[pyo3(signature = (seq, alignment = false, correction_dictionary = None, correction_rules = None))]
 pub fn convert_to_valid(
     seq: &str,
     alignment: bool,
     correction_dictionary: Option<HashMap<String, String>>,
+    correction_rules: Option<Vec<&PyDict>>,
 ) -> PyResult<String> {
     Ok(utilities::convert_to_valid(
         seq,
         alignment,
         correction_dictionary,
+        dicts_to_rules(correction_rules)?,
     ))
 }
 
@@ -68,15 +110,20 @@ pub fn check_sequence_is_valid(seq: &str, alignment: bool) -> PyResult<(bool, ch
 }
 
 #[pyfunction]
-#[pyo3(signature = (dataset, correction_dictionary = None, alignment = false))]
+#[pyo3(signature = (dataset, correction_dictionary = None, alignment = false, correction_rules = None))]
 pub fn convert_invalid_sequences(
     dataset: Vec<Vec<String>>,
     correction_dictionary: Option<HashMap<String, String>>,
     alignment: bool,
+    correction_rules: Option<Vec<&PyDict>>,
 ) -> PyResult<(Vec<Vec<String>>, usize)> {
     let records = rows_to_records(dataset)?;
-    let (converted, count) =
-        utilities::convert_invalid_sequences(records, correction_dictionary, alignment);
+    let (converted, count) = utilities::convert_invalid_sequences(
+        records,
+        correction_dictionary,
+        dicts_to_rules(correction_rules)?,
+        alignment,
+    );
     Ok((records_to_rows(converted), count))
 }
 
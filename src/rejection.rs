@@ -0,0 +1,182 @@
+use std::fmt;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+use crate::compression::Compression;
+use crate::errors::RfastaError;
+use crate::io::{tsv_field, FastaRecord, FastaWriter, WriteOptions};
+
+/// Destinations for records dropped by cleaning.
+///
+/// Records removed by duplicate handling, invalid-residue removal, or the length filters are
+/// written here together with the reason they were dropped. Records left out by
+/// `random_subsample` are not rejections and are never written.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct RejectedOutput {
+    /// FASTA file receiving each dropped record, with the reason appended to its header.
+    ///
+    /// Records are written as they were when dropped, after any residue conversion, so a record
+    /// whose sequence was converted away appears as a header with no sequence lines.
+    ///
+    /// Output is compressed when the path ends in `.gz`, `.bgz`, or `.zst`.
+    pub fasta: Option<PathBuf>,
+    /// TSV file with one row per dropped record: `header`, `stage`, `residue`, `position`,
+    /// `kept_header`, and `length`.
+    pub tsv: Option<PathBuf>,
+}
+
+const TSV_COLUMNS: &str = "header\tstage\tresidue\tposition\tkept_header\tlength\n";
+
+/// Why a record was dropped by the cleaner.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub(crate) enum RejectReason {
+    DuplicateRecord,
+    DuplicateSequence {
+        kept_header: String,
+    },
+    /// `position` is 1-based and refers to the sequence after residue conversion.
+    InvalidResidue {
+        residue: char,
+        position: usize,
+    },
+    TooShort {
+        min_len: usize,
+    },
+    TooLong {
+        max_len: usize,
+    },
+}
+
+impl RejectReason {
+    fn stage(&self) -> &'static str {
+        match self {
+            Self::DuplicateRecord => "duplicate_record",
+            Self::DuplicateSequence { .. } => "duplicate_sequence",
+            Self::InvalidResidue { .. } => "invalid_residue",
+            Self::TooShort { .. } => "too_short",
+            Self::TooLong { .. } => "too_long",
+        }
+    }
+}
+
+impl fmt::Display for RejectReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::DuplicateRecord => write!(f, "duplicate record"),
+            Self::DuplicateSequence { kept_header } => {
+                write!(f, "duplicate sequence of `{kept_header}`")
+            }
+            Self::InvalidResidue { residue, position } => {
+                write!(f, "invalid residue `{residue}` at position {position}")
+            }
+            Self::TooShort { min_len } => write!(f, "shorter than {min_len} residues"),
+            Self::TooLong { max_len } => write!(f, "longer than {max_len} residues"),
+        }
+    }
+}
+
+/// Writes rejected records to the sidecar files configured in [`RejectedOutput`].
+pub(crate) struct RejectedWriter {
+    fasta: Option<FastaWriter<BufWriter<File>>>,
+    tsv: Option<(BufWriter<File>, PathBuf)>,
+}
+
+impl RejectedWriter {
+    pub(crate) fn open(output: Option<&RejectedOutput>) -> Result<Self, RfastaError> {
+        let Some(output) = output else {
+            return Ok(Self {
+                fasta: None,
+                tsv: None,
+            });
+        };
+        let fasta = output
+            .fasta
+            .as_deref()
+            .map(|path| {
+                FastaWriter::create(
+                    path,
+                    &WriteOptions {
+                        compression: Compression::from_path(path),
+                        ..WriteOptions::default()
+                    },
+                )
+            })
+            .transpose()?;
+        let tsv = output
+            .tsv
+            .as_deref()
+            .map(|path| {
+                let mut writer = File::create(path)
+                    .map(BufWriter::new)
+                    .map_err(|source| tsv_error(path, source))?;
+                writer
+                    .write_all(TSV_COLUMNS.as_bytes())
+                    .map_err(|source| tsv_error(path, source))?;
+                Ok::<_, RfastaError>((writer, path.to_path_buf()))
+            })
+            .transpose()?;
+        Ok(Self { fasta, tsv })
+    }
+
+    /// Returns `true` when at least one sidecar file is being written.
+    pub(crate) fn is_enabled(&self) -> bool {
+        self.fasta.is_some() || self.tsv.is_some()
+    }
+
+    pub(crate) fn reject(
+        &mut self,
+        record: &FastaRecord,
+        reason: &RejectReason,
+    ) -> Result<(), RfastaError> {
+        if let Some(writer) = &mut self.fasta {
+            // Conversion can leave nothing of a sequence; such records are still reported.
+            writer.write_record_allowing_empty(&FastaRecord::new(
+                format!("{} [rejected: {reason}]", record.header),
+                record.sequence.as_str(),
+            ))?;
+        }
+        if let Some((writer, path)) = &mut self.tsv {
+            let (residue, position) = match reason {
+                RejectReason::InvalidResidue { residue, position } => {
+                    (residue.to_string(), position.to_string())
+                }
+                _ => (String::new(), String::new()),
+            };
+            let kept_header = match reason {
+                RejectReason::DuplicateRecord => record.header.as_str(),
+                RejectReason::DuplicateSequence { kept_header } => kept_header.as_str(),
+                _ => "",
+            };
+            writeln!(
+                writer,
+                "{}\t{}\t{residue}\t{position}\t{}\t{}",
+                tsv_field(&record.header),
+                reason.stage(),
+                tsv_field(kept_header),
+                record.sequence.len()
+            )
+            .map_err(|source| tsv_error(path, source))?;
+        }
+        Ok(())
+    }
+
+    pub(crate) fn finish(self) -> Result<(), RfastaError> {
+        if let Some(writer) = self.fasta {
+            writer.finish()?;
+        }
+        if let Some((mut writer, path)) = self.tsv {
+            writer.flush().map_err(|source| tsv_error(&path, source))?;
+        }
+        Ok(())
+    }
+}
+
+fn tsv_error(path: &Path, source: std::io::Error) -> RfastaError {
+    RfastaError::io(
+        "clean",
+        Some(path),
+        source,
+        "Check that the rejected-records TSV path is writable.",
+    )
+}
@@ -0,0 +1,121 @@
+use crate::io::FastaRecord;
+
+/// SplitMix64 pseudo-random generator used for random subsampling.
+///
+/// The output sequence is fixed by the published algorithm rather than by a dependency, so a given
+/// seed produces the same subsample on every platform and in every `rfasta` release.
+pub(crate) struct SplitMix64 {
+    state: u64,
+}
+
+impl SplitMix64 {
+    pub(crate) fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+
+    /// Uses `seed` when given, otherwise a seed drawn from the operating system.
+    pub(crate) fn from_seed_or_entropy(seed: Option<u64>) -> Self {
+        Self::new(seed.unwrap_or_else(rand::random))
+    }
+
+    pub(crate) fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// Returns a uniformly distributed value in `0..bound`.
+    pub(crate) fn below(&mut self, bound: u64) -> u64 {
+        // Lemire's multiply-shift reduction with rejection of the biased low range.
+        let threshold = bound.wrapping_neg() % bound;
+        loop {
+            let product = u128::from(self.next_u64()) * u128::from(bound);
+            if product as u64 >= threshold {
+                return (product >> 64) as u64;
+            }
+        }
+    }
+
+    /// Fisher-Yates shuffle, swapping each position from the back with an earlier one.
+    pub(crate) fn shuffle<T>(&mut self, items: &mut [T]) {
+        for index in (1..items.len()).rev() {
+            let other = self.below(index as u64 + 1) as usize;
+            items.swap(index, other);
+        }
+    }
+}
+
+/// Uniform fixed-size sample of a record stream (Algorithm R).
+///
+/// The batch and streaming cleaners both sample through this type, so the same seed and input
+/// order select the same records on either path.
+pub(crate) struct Reservoir {
+    sample_size: usize,
+    seen: usize,
+    records: Vec<FastaRecord>,
+}
+
+impl Reservoir {
+    pub(crate) fn new(sample_size: usize) -> Self {
+        Self {
+            sample_size,
+            seen: 0,
+            records: Vec::new(),
+        }
+    }
+
+    pub(crate) fn push(&mut self, record: FastaRecord, rng: &mut SplitMix64) {
+        if self.records.len() < self.sample_size {
+            self.records.push(record);
+        } else {
+            let slot = rng.below(self.seen as u64 + 1) as usize;
+            if slot < self.sample_size {
+                self.records[slot] = record;
+            }
+        }
+        self.seen += 1;
+    }
+
+    /// Returns the sample in random order along with the number of records left out.
+    pub(crate) fn finish(mut self, rng: &mut SplitMix64) -> (Vec<FastaRecord>, usize) {
+        rng.shuffle(&mut self.records);
+        let dropped = self.seen - self.records.len();
+        (self.records, dropped)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn splitmix64_matches_reference_output() {
+        let mut rng = SplitMix64::new(1_234_567);
+        let values: Vec<u64> = (0..5).map(|_| rng.next_u64()).collect();
+        assert_eq!(
+            values,
+            vec![
+                6_457_827_717_110_365_317,
+                3_203_168_211_198_807_973,
+                9_817_491_932_198_370_423,
+                4_593_380_528_125_082_431,
+                16_408_922_859_458_223_821,
+            ]
+        );
+    }
+
+    #[test]
+    fn seeded_reservoir_sample_is_pinned() {
+        let mut rng = SplitMix64::new(42);
+        let mut reservoir = Reservoir::new(3);
+        for index in 0..10 {
+            reservoir.push(FastaRecord::new(format!("seq{index}"), "ACDE"), &mut rng);
+        }
+        let (sample, dropped) = reservoir.finish(&mut rng);
+        let headers: Vec<&str> = sample.iter().map(|record| record.header.as_str()).collect();
+        assert_eq!(headers, vec!["seq5", "seq7", "seq9"]);
+        assert_eq!(dropped, 7);
+    }
+}
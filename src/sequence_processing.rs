@@ -1,13 +1,23 @@
-use std::collections::HashMap;
+use std::collections::hash_map::{DefaultHasher, Entry};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::hash::{Hash, Hasher};
+use std::io::BufRead;
+use std::path::Path;
 
 use clap::ValueEnum;
-use rand::rngs::StdRng;
-use rand::seq::SliceRandom;
-use rand::SeedableRng;
+use serde::Serialize;
 
+use crate::corrections::CorrectionRules;
 use crate::errors::RfastaError;
-use crate::io::FastaRecord;
-use crate::utilities;
+use crate::io::{
+    insert_unique_header, log_parsed_file, visit_fasta_chunk, visit_fasta_file,
+    visit_fasta_file_chunks, visit_fasta_reader, visit_fasta_reader_chunks, FastaChunk,
+    FastaRecord, FastaWriter, ParseOptions, WriteOptions,
+};
+use crate::pipeline::{ordered_pipeline, resolve_threads};
+use crate::rejection::{RejectReason, RejectedOutput, RejectedWriter};
+use crate::sampling::{Reservoir, SplitMix64};
+use crate::utilities::{self, ConversionCounts, ConversionStrategy};
 
 /// Action for duplicate record or duplicate sequence handling.
 #[derive(Clone, Copy, Debug, Eq, PartialEq, ValueEnum)]
@@ -52,8 +62,11 @@ pub struct CleanOptions {
     pub shortest_seq: Option<usize>,
     /// Maximum sequence length to keep.
     pub longest_seq: Option<usize>,
-    /// Number of records to retain after shuffling.
+    /// Number of records to retain, chosen uniformly at random and returned in random order.
     pub random_subsample: Option<usize>,
+    /// Seed for `random_subsample`. The same seed and input order select the same records, in the
+    /// same order, on every platform and release. `None` draws a seed from the operating system.
+    pub seed: Option<u64>,
     /// Replace commas in headers with semicolons.
     pub remove_comma_from_header: bool,
     /// Allow `-` as a valid residue for aligned sequences.
@@ -62,6 +75,21 @@ pub struct CleanOptions {
     pub verbose: bool,
     /// Optional custom residue conversion dictionary.
     pub correction_dictionary: Option<HashMap<String, String>>,
+    /// Optional ordered correction rules; when set, they replace `correction_dictionary`.
+    pub correction_rules: Option<CorrectionRules>,
+    /// Optional sidecar files recording every record dropped during cleaning and why.
+    ///
+    /// With duplicate-sequence removal, each rejected duplicate names the record it duplicates, so
+    /// the header of every distinct kept sequence is held in memory for the whole run. Memory then
+    /// grows with the total header length of the input rather than staying at one fingerprint per
+    /// sequence.
+    pub rejected: Option<RejectedOutput>,
+    /// Threads used by the streaming cleaners. With more than one, worker threads parse, convert,
+    /// and validate chunks of records while the calling thread reads the input and applies the
+    /// order-dependent stages, and the output is identical to a single-threaded run. `0` uses
+    /// every available core. [`clean_sequences`] works on records already in memory and runs on
+    /// the calling thread.
+    pub threads: usize,
 }
 
 impl Default for CleanOptions {
@@ -73,80 +101,224 @@ impl Default for CleanOptions {
             shortest_seq: None,
             longest_seq: None,
             random_subsample: None,
+            seed: None,
             remove_comma_from_header: false,
             alignment: false,
             verbose: false,
             correction_dictionary: None,
+            correction_rules: None,
+            rejected: None,
+            threads: 1,
         }
     }
 }
 
-fn deal_with_invalid_sequences(
-    data: Vec<FastaRecord>,
-    options: &CleanOptions,
-) -> Result<Vec<FastaRecord>, RfastaError> {
-    match options.invalid_sequence_action {
-        InvalidSequenceAction::Fail => {
-            utilities::fail_on_invalid_sequences(&data, options.alignment)?;
-            Ok(data)
+impl CleanOptions {
+    /// Returns `true` when these options can be applied by the streaming cleaner.
+    ///
+    /// Every policy is supported except `duplicate_sequence_action: DuplicateAction::Fail`, whose
+    /// error names the first record carrying the sequence and would therefore require keeping every
+    /// header in memory. Use [`clean_sequences`] for that policy.
+    pub fn supports_streaming(&self) -> bool {
+        self.duplicate_sequence_action != DuplicateAction::Fail
+    }
+}
+
+/// Per-stage counts recorded while cleaning.
+///
+/// Stages run in the order listed here, so each removal count is relative to the records that
+/// survived the previous stages. Serializes to JSON with [`CleanReport::to_json`] for audit logs.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize)]
+pub struct CleanReport {
+    /// Records passed to the cleaner.
+    pub records_in: usize,
+    /// Exact duplicate records removed.
+    pub duplicate_records_removed: usize,
+    /// Records removed because an earlier record had the same sequence.
+    pub duplicate_sequences_removed: usize,
+    /// Records whose sequence was changed by residue conversion.
+    pub sequences_converted: usize,
+    /// Replacements made per residue or correction pattern, keyed by the uppercased source.
+    pub residue_conversions: BTreeMap<String, usize>,
+    /// Records removed because they still contained invalid residues.
+    pub invalid_removed: usize,
+    /// Records removed by `shortest_seq` or `longest_seq`.
+    pub length_filtered: usize,
+    /// Records dropped by `random_subsample`.
+    pub subsampled_away: usize,
+    /// Records returned by the cleaner.
+    pub records_out: usize,
+}
+
+impl CleanReport {
+    /// Serializes the report as pretty-printed JSON.
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("clean reports always serialize")
+    }
+
+    fn log(&self, options: &CleanOptions) {
+        let mut remaining = self.records_in;
+        if options.duplicate_record_action == DuplicateAction::Remove {
+            eprintln!(
+                "[INFO]: Removed {} of {} sequences due to duplicate records",
+                self.duplicate_records_removed, remaining
+            );
         }
-        InvalidSequenceAction::Remove => {
-            let original_len = data.len();
-            let updated = utilities::remove_invalid_sequences(data, options.alignment);
-            if options.verbose {
-                println!(
-                    "[INFO]: Removed {} of {} sequences due to invalid characters",
-                    original_len - updated.len(),
-                    original_len
-                );
-            }
-            Ok(updated)
+        remaining -= self.duplicate_records_removed;
+        if options.duplicate_sequence_action == DuplicateAction::Remove {
+            eprintln!(
+                "[INFO]: Removed {} of {} sequences due to duplicate sequences",
+                self.duplicate_sequences_removed, remaining
+            );
         }
-        InvalidSequenceAction::Convert | InvalidSequenceAction::ConvertIgnore => {
-            let (updated, count) = utilities::convert_invalid_sequences(
-                data,
-                options.correction_dictionary.clone(),
-                options.alignment,
+        remaining -= self.duplicate_sequences_removed;
+        if converts_residues(options.invalid_sequence_action) {
+            eprintln!(
+                "[INFO]: Converted {} sequences to valid sequences",
+                self.sequences_converted
             );
-            if options.verbose {
-                println!("[INFO]: Converted {count} sequences to valid sequences");
-            }
-            if matches!(
-                options.invalid_sequence_action,
-                InvalidSequenceAction::Convert
-            ) {
-                utilities::fail_on_invalid_sequences(&updated, options.alignment)?;
-            }
-            Ok(updated)
         }
-        InvalidSequenceAction::ConvertRemove => {
-            let (updated, count) = utilities::convert_invalid_sequences(
-                data,
-                options.correction_dictionary.clone(),
-                options.alignment,
+        if matches!(
+            options.invalid_sequence_action,
+            InvalidSequenceAction::Remove | InvalidSequenceAction::ConvertRemove
+        ) {
+            eprintln!(
+                "[INFO]: Removed {} of {} sequences due to invalid characters",
+                self.invalid_removed, remaining
             );
-            if options.verbose {
-                println!("[INFO]: Converted {count} sequences to valid sequences");
+        }
+    }
+}
+
+fn converts_residues(action: InvalidSequenceAction) -> bool {
+    matches!(
+        action,
+        InvalidSequenceAction::Convert
+            | InvalidSequenceAction::ConvertIgnore
+            | InvalidSequenceAction::ConvertRemove
+    )
+}
+
+fn conversion_strategy_for(options: &CleanOptions) -> Option<ConversionStrategy> {
+    converts_residues(options.invalid_sequence_action).then(|| {
+        utilities::conversion_strategy(
+            options.correction_dictionary.clone(),
+            options.correction_rules.clone(),
+            options.alignment,
+        )
+    })
+}
+
+/// Removes records for which `check` returns a reason, counting and recording each rejection.
+fn remove_rejected(
+    data: Vec<FastaRecord>,
+    removed: &mut usize,
+    rejected: &mut RejectedWriter,
+    mut check: impl FnMut(&FastaRecord) -> Option<RejectReason>,
+) -> Result<Vec<FastaRecord>, RfastaError> {
+    let mut kept = Vec::with_capacity(data.len());
+    for record in data {
+        match check(&record) {
+            Some(reason) => {
+                *removed += 1;
+                rejected.reject(&record, &reason)?;
             }
-            let original_len = updated.len();
-            let filtered = utilities::remove_invalid_sequences(updated, options.alignment);
-            if options.verbose {
-                println!(
-                    "[INFO]: Removed {} of {} sequences due to invalid characters",
-                    original_len - filtered.len(),
-                    original_len
-                );
+            None => kept.push(record),
+        }
+    }
+    Ok(kept)
+}
+
+fn invalid_residue_rejection(sequence: &str, alignment: bool) -> Option<RejectReason> {
+    utilities::first_invalid_residue(sequence, alignment)
+        .map(|(position, residue)| RejectReason::InvalidResidue { residue, position })
+}
+
+fn length_rejection(record: &FastaRecord, options: &CleanOptions) -> Option<RejectReason> {
+    let length = record.sequence.len();
+    if let Some(min_len) = options.shortest_seq.filter(|&min_len| length < min_len) {
+        return Some(RejectReason::TooShort { min_len });
+    }
+    options
+        .longest_seq
+        .filter(|&max_len| length > max_len)
+        .map(|max_len| RejectReason::TooLong { max_len })
+}
+
+/// Tracks sequences already kept, remembering their headers only when rejections are written.
+///
+/// Headers are stored so that rejected duplicates can name the record they duplicate; this is the
+/// one part of cleaning whose memory grows with the input, and only when a sidecar is enabled.
+///
+/// The in-memory path keys on the sequence itself; the streaming path keys on its fingerprint.
+enum SequenceTracker<K> {
+    Keys(HashSet<K>),
+    Headers(HashMap<K, String>),
+}
+
+impl<K: Hash + Eq> SequenceTracker<K> {
+    fn new(keep_headers: bool) -> Self {
+        if keep_headers {
+            Self::Headers(HashMap::new())
+        } else {
+            Self::Keys(HashSet::new())
+        }
+    }
+
+    /// Returns a rejection when an earlier record had the sequence identified by `key`.
+    fn check(&mut self, key: K, header: &str) -> Option<RejectReason> {
+        match self {
+            Self::Keys(seen) => (!seen.insert(key)).then(|| RejectReason::DuplicateSequence {
+                kept_header: String::new(),
+            }),
+            Self::Headers(seen) => match seen.entry(key) {
+                Entry::Occupied(entry) => Some(RejectReason::DuplicateSequence {
+                    kept_header: entry.get().clone(),
+                }),
+                Entry::Vacant(entry) => {
+                    entry.insert(header.to_string());
+                    None
+                }
+            },
+        }
+    }
+}
+
+fn deal_with_invalid_sequences(
+    mut data: Vec<FastaRecord>,
+    options: &CleanOptions,
+    report: &mut CleanReport,
+    rejected: &mut RejectedWriter,
+) -> Result<Vec<FastaRecord>, RfastaError> {
+    if let Some(strategy) = conversion_strategy_for(options) {
+        for record in &mut data {
+            let updated = strategy.apply_counted(&record.sequence, &mut report.residue_conversions);
+            if updated != record.sequence {
+                report.sequences_converted += 1;
+                record.sequence = updated;
             }
-            Ok(filtered)
         }
-        InvalidSequenceAction::Ignore => Ok(data),
+    }
+
+    match options.invalid_sequence_action {
+        InvalidSequenceAction::Ignore | InvalidSequenceAction::ConvertIgnore => Ok(data),
+        InvalidSequenceAction::Fail | InvalidSequenceAction::Convert => {
+            utilities::fail_on_invalid_sequences(&data, options.alignment)?;
+            Ok(data)
+        }
+        InvalidSequenceAction::Remove | InvalidSequenceAction::ConvertRemove => {
+            remove_rejected(data, &mut report.invalid_removed, rejected, |record| {
+                invalid_residue_rejection(&record.sequence, options.alignment)
+            })
+        }
     }
 }
 
 fn deal_with_duplicate_records(
     data: Vec<FastaRecord>,
     action: DuplicateAction,
-    verbose: bool,
+    report: &mut CleanReport,
+    rejected: &mut RejectedWriter,
 ) -> Result<Vec<FastaRecord>, RfastaError> {
     match action {
         DuplicateAction::Ignore => Ok(data),
@@ -155,16 +327,16 @@ fn deal_with_duplicate_records(
             Ok(data)
         }
         DuplicateAction::Remove => {
-            let original_len = data.len();
-            let updated = utilities::remove_duplicates(data);
-            if verbose {
-                println!(
-                    "[INFO]: Removed {} of {} sequences due to duplicate records",
-                    original_len - updated.len(),
-                    original_len
-                );
-            }
-            Ok(updated)
+            let mut seen = HashSet::with_capacity(data.len());
+            remove_rejected(
+                data,
+                &mut report.duplicate_records_removed,
+                rejected,
+                |record| {
+                    let key = (record.header.clone(), record.sequence.clone());
+                    (!seen.insert(key)).then_some(RejectReason::DuplicateRecord)
+                },
+            )
         }
     }
 }
@@ -172,7 +344,8 @@ fn deal_with_duplicate_records(
 fn deal_with_duplicate_sequences(
     data: Vec<FastaRecord>,
     action: DuplicateAction,
-    verbose: bool,
+    report: &mut CleanReport,
+    rejected: &mut RejectedWriter,
 ) -> Result<Vec<FastaRecord>, RfastaError> {
     match action {
         DuplicateAction::Ignore => Ok(data),
@@ -181,16 +354,13 @@ fn deal_with_duplicate_sequences(
             Ok(data)
         }
         DuplicateAction::Remove => {
-            let original_len = data.len();
-            let updated = utilities::remove_duplicate_sequences(data);
-            if verbose {
-                println!(
-                    "[INFO]: Removed {} of {} sequences due to duplicate sequences",
-                    original_len - updated.len(),
-                    original_len
-                );
-            }
-            Ok(updated)
+            let mut tracker = SequenceTracker::new(rejected.is_enabled());
+            remove_rejected(
+                data,
+                &mut report.duplicate_sequences_removed,
+                rejected,
+                |record| tracker.check(record.sequence.clone(), &record.header),
+            )
         }
     }
 }
@@ -198,27 +368,43 @@ fn deal_with_duplicate_sequences(
 fn clean_sequences_with_rng(
     data: Vec<FastaRecord>,
     options: &CleanOptions,
-    rng: &mut StdRng,
-) -> Result<Vec<FastaRecord>, RfastaError> {
+    rng: &mut SplitMix64,
+) -> Result<(Vec<FastaRecord>, CleanReport), RfastaError> {
+    let mut report = CleanReport {
+        records_in: data.len(),
+        ..CleanReport::default()
+    };
+    let mut rejected = RejectedWriter::open(options.rejected.as_ref())?;
     let mut processed = data;
-    processed =
-        deal_with_duplicate_records(processed, options.duplicate_record_action, options.verbose)?;
+    processed = deal_with_duplicate_records(
+        processed,
+        options.duplicate_record_action,
+        &mut report,
+        &mut rejected,
+    )?;
     processed = deal_with_duplicate_sequences(
         processed,
         options.duplicate_sequence_action,
-        options.verbose,
+        &mut report,
+        &mut rejected,
     )?;
-    processed = deal_with_invalid_sequences(processed, options)?;
+    processed = deal_with_invalid_sequences(processed, options, &mut report, &mut rejected)?;
 
-    if let Some(min_len) = options.shortest_seq {
-        processed.retain(|record| record.sequence.len() >= min_len);
-    }
-    if let Some(max_len) = options.longest_seq {
-        processed.retain(|record| record.sequence.len() <= max_len);
+    if options.shortest_seq.is_some() || options.longest_seq.is_some() {
+        processed = remove_rejected(
+            processed,
+            &mut report.length_filtered,
+            &mut rejected,
+            |record| length_rejection(record, options),
+        )?;
     }
+    rejected.finish()?;
     if let Some(sample_size) = options.random_subsample {
-        processed.shuffle(rng);
-        processed.truncate(sample_size);
+        let mut reservoir = Reservoir::new(sample_size);
+        for record in processed {
+            reservoir.push(record, rng);
+        }
+        (processed, report.subsampled_away) = reservoir.finish(rng);
     }
     if options.remove_comma_from_header {
         for record in &mut processed {
@@ -226,7 +412,11 @@ fn clean_sequences_with_rng(
         }
     }
 
-    Ok(processed)
+    report.records_out = processed.len();
+    if options.verbose {
+        report.log(options);
+    }
+    Ok((processed, report))
 }
 
 /// Cleans FASTA records using the provided options.
@@ -238,10 +428,455 @@ pub fn clean_sequences(
     data: Vec<FastaRecord>,
     options: &CleanOptions,
 ) -> Result<Vec<FastaRecord>, RfastaError> {
-    let mut rng = StdRng::from_entropy();
+    clean_sequences_with_report(data, options).map(|(records, _)| records)
+}
+
+/// Cleans FASTA records and returns a [`CleanReport`] describing what each stage did.
+///
+/// # Example
+/// ```
+/// use rfasta::clean::{clean_sequences_with_report, CleanOptions, InvalidSequenceAction};
+/// use rfasta::parse::FastaRecord;
+///
+/// let (cleaned, report) = clean_sequences_with_report(
+///     vec![FastaRecord::new("seq1", "ACDX"), FastaRecord::new("seq2", "AC?")],
+///     &CleanOptions {
+///         invalid_sequence_action: InvalidSequenceAction::ConvertRemove,
+///         ..CleanOptions::default()
+///     },
+/// )?;
+/// assert_eq!(cleaned.len(), 1);
+/// assert_eq!(report.residue_conversions["X"], 1);
+/// assert_eq!(report.invalid_removed, 1);
+/// # Ok::<(), rfasta::RfastaError>(())
+/// ```
+pub fn clean_sequences_with_report(
+    data: Vec<FastaRecord>,
+    options: &CleanOptions,
+) -> Result<(Vec<FastaRecord>, CleanReport), RfastaError> {
+    let mut rng = SplitMix64::from_seed_or_entropy(options.seed);
     clean_sequences_with_rng(data, options, &mut rng)
 }
 
+/// Returns a 128-bit fingerprint of `parts`, used to track duplicates without keeping records.
+fn fingerprint(parts: &[&str]) -> u128 {
+    let mut low = DefaultHasher::new();
+    let mut high = DefaultHasher::new();
+    high.write_u8(0x5a);
+    for part in parts {
+        low.write(part.as_bytes());
+        low.write_u8(0xff);
+        high.write(part.as_bytes());
+        high.write_u8(0xff);
+    }
+    (u128::from(high.finish()) << 64) | u128::from(low.finish())
+}
+
+/// Order-independent results for one record, computed before the stages that depend on earlier
+/// records.
+///
+/// Preparing is the bulk of the per-record work, so the parallel cleaner runs it on worker threads.
+struct PreparedRecord {
+    record: FastaRecord,
+    /// Fingerprint of the header and sequence, when duplicate records are tracked.
+    record_key: Option<u128>,
+    /// Fingerprint of the sequence, when duplicate sequences are removed.
+    sequence_key: Option<u128>,
+    /// Converted sequence, when conversion changed it.
+    converted: Option<String>,
+    /// Replacements made by conversion.
+    conversions: ConversionCounts,
+    /// First invalid residue left after conversion, when the policy checks residues.
+    invalid: Option<RejectReason>,
+}
+
+struct RecordPreparer<'a> {
+    options: &'a CleanOptions,
+    strategy: Option<ConversionStrategy>,
+}
+
+impl<'a> RecordPreparer<'a> {
+    fn new(options: &'a CleanOptions) -> Self {
+        Self {
+            options,
+            strategy: conversion_strategy_for(options),
+        }
+    }
+
+    fn prepare(&self, record: FastaRecord) -> PreparedRecord {
+        let options = self.options;
+        let record_key = (options.duplicate_record_action != DuplicateAction::Ignore)
+            .then(|| fingerprint(&[&record.header, &record.sequence]));
+        let sequence_key = (options.duplicate_sequence_action == DuplicateAction::Remove)
+            .then(|| fingerprint(&[&record.sequence]));
+        let mut conversions = ConversionCounts::new();
+        let converted = self
+            .strategy
+            .as_ref()
+            .map(|strategy| strategy.apply_counted(&record.sequence, &mut conversions))
+            .filter(|updated| *updated != record.sequence);
+        let invalid = match options.invalid_sequence_action {
+            InvalidSequenceAction::Ignore | InvalidSequenceAction::ConvertIgnore => None,
+            _ => invalid_residue_rejection(
+                converted.as_deref().unwrap_or(&record.sequence),
+                options.alignment,
+            ),
+        };
+        PreparedRecord {
+            record,
+            record_key,
+            sequence_key,
+            converted,
+            conversions,
+            invalid,
+        }
+    }
+}
+
+/// Per-record implementation of the [`clean_sequences`] stages.
+///
+/// Duplicate tracking keeps one fingerprint per distinct record or sequence, plus its header when
+/// rejected records are written, and random subsampling keeps a reservoir of `random_subsample`
+/// records, so memory stays independent of the input size for every other policy.
+struct StreamingCleaner<'a> {
+    options: &'a CleanOptions,
+    preparer: RecordPreparer<'a>,
+    seen_records: HashSet<u128>,
+    seen_sequences: SequenceTracker<u128>,
+    reservoir: Option<Reservoir>,
+    rng: SplitMix64,
+    report: CleanReport,
+    rejected: RejectedWriter,
+}
+
+impl<'a> StreamingCleaner<'a> {
+    fn new(options: &'a CleanOptions) -> Result<Self, RfastaError> {
+        if !options.supports_streaming() {
+            return Err(RfastaError::invalid_input(
+                "clean",
+                "duplicate-sequence fail mode cannot be applied while streaming",
+                "Use clean_sequences for DuplicateAction::Fail on duplicate sequences, or choose ignore/remove.",
+            ));
+        }
+        let rejected = RejectedWriter::open(options.rejected.as_ref())?;
+        Ok(Self {
+            options,
+            preparer: RecordPreparer::new(options),
+            seen_records: HashSet::new(),
+            seen_sequences: SequenceTracker::new(rejected.is_enabled()),
+            reservoir: options.random_subsample.map(Reservoir::new),
+            rng: SplitMix64::from_seed_or_entropy(options.seed),
+            report: CleanReport::default(),
+            rejected,
+        })
+    }
+
+    fn push<F>(&mut self, record: FastaRecord, emit: &mut F) -> Result<(), RfastaError>
+    where
+        F: FnMut(FastaRecord) -> Result<(), RfastaError>,
+    {
+        let prepared = self.preparer.prepare(record);
+        self.accept(prepared, emit)
+    }
+
+    /// Runs the order-dependent stages on a record prepared by [`RecordPreparer::prepare`].
+    fn accept<F>(&mut self, prepared: PreparedRecord, emit: &mut F) -> Result<(), RfastaError>
+    where
+        F: FnMut(FastaRecord) -> Result<(), RfastaError>,
+    {
+        let PreparedRecord {
+            mut record,
+            record_key,
+            sequence_key,
+            converted,
+            conversions,
+            invalid,
+        } = prepared;
+        let options = self.options;
+        let report = &mut self.report;
+        report.records_in += 1;
+
+        if let Some(key) = record_key {
+            if !self.seen_records.insert(key) {
+                if options.duplicate_record_action == DuplicateAction::Fail {
+                    return Err(utilities::duplicate_record_error(&record));
+                }
+                report.duplicate_records_removed += 1;
+                return self
+                    .rejected
+                    .reject(&record, &RejectReason::DuplicateRecord);
+            }
+        }
+
+        if let Some(key) = sequence_key {
+            if let Some(reason) = self.seen_sequences.check(key, &record.header) {
+                report.duplicate_sequences_removed += 1;
+                return self.rejected.reject(&record, &reason);
+            }
+        }
+
+        for (from, count) in conversions {
+            *report.residue_conversions.entry(from).or_default() += count;
+        }
+        if let Some(sequence) = converted {
+            report.sequences_converted += 1;
+            record.sequence = sequence;
+        }
+        if let Some(reason) = invalid {
+            if matches!(
+                options.invalid_sequence_action,
+                InvalidSequenceAction::Fail | InvalidSequenceAction::Convert
+            ) {
+                return utilities::fail_on_invalid_record(&record, options.alignment);
+            }
+            report.invalid_removed += 1;
+            return self.rejected.reject(&record, &reason);
+        }
+
+        if let Some(reason) = length_rejection(&record, options) {
+            report.length_filtered += 1;
+            return self.rejected.reject(&record, &reason);
+        }
+
+        if options.remove_comma_from_header {
+            record.header = record.header.replace(',', ";");
+        }
+
+        match &mut self.reservoir {
+            Some(reservoir) => {
+                reservoir.push(record, &mut self.rng);
+                Ok(())
+            }
+            None => {
+                report.records_out += 1;
+                emit(record)
+            }
+        }
+    }
+
+    fn finish<F>(mut self, emit: &mut F) -> Result<CleanReport, RfastaError>
+    where
+        F: FnMut(FastaRecord) -> Result<(), RfastaError>,
+    {
+        self.rejected.finish()?;
+        if let Some(reservoir) = self.reservoir.take() {
+            let (sample, dropped) = reservoir.finish(&mut self.rng);
+            self.report.subsampled_away = dropped;
+            self.report.records_out += sample.len();
+            for record in sample {
+                emit(record)?;
+            }
+        }
+        if self.options.verbose {
+            self.report.log(self.options);
+        }
+        Ok(self.report)
+    }
+}
+
+fn visit_cleaned_records<F>(
+    options: &CleanOptions,
+    mut visit: F,
+    parse: impl FnOnce(
+        &mut dyn FnMut(FastaRecord) -> Result<(), RfastaError>,
+    ) -> Result<usize, RfastaError>,
+) -> Result<CleanReport, RfastaError>
+where
+    F: FnMut(FastaRecord) -> Result<(), RfastaError>,
+{
+    let mut cleaner = StreamingCleaner::new(options)?;
+    parse(&mut |record| cleaner.push(record, &mut visit))?;
+    cleaner.finish(&mut visit)
+}
+
+/// Parallel counterpart to [`visit_cleaned_records`] for `threads` workers.
+///
+/// `read_chunks` cuts the input into chunks of whole records on the calling thread. Workers parse
+/// and prepare each chunk, and the calling thread checks header uniqueness and runs the
+/// order-dependent stages on the results in input order, so the output and report match the
+/// serial cleaner's exactly. A `source_name` marks file input, whose parse is logged as
+/// [`visit_fasta_file`] logs it.
+fn visit_cleaned_chunks<F>(
+    options: &CleanOptions,
+    threads: usize,
+    parse_options: &ParseOptions,
+    source_name: Option<&Path>,
+    mut visit: F,
+    read_chunks: impl FnOnce(
+        &mut dyn FnMut(FastaChunk) -> Result<(), RfastaError>,
+    ) -> Result<(), RfastaError>,
+) -> Result<CleanReport, RfastaError>
+where
+    F: FnMut(FastaRecord) -> Result<(), RfastaError>,
+{
+    let mut cleaner = StreamingCleaner::new(options)?;
+    let preparer = RecordPreparer::new(options);
+    let mut seen_headers = parse_options.expect_unique_header.then(HashSet::new);
+    let mut records = 0;
+    ordered_pipeline(
+        threads,
+        read_chunks,
+        |chunk| {
+            let mut prepared = Vec::new();
+            let parsed = visit_fasta_chunk(&chunk, source_name, &mut |record| {
+                prepared.push(preparer.prepare(record));
+                Ok(())
+            });
+            (prepared, parsed.err())
+        },
+        |(prepared, error)| {
+            for record in prepared {
+                if let Some(seen_headers) = seen_headers.as_mut() {
+                    insert_unique_header(seen_headers, &record.record.header, source_name)?;
+                }
+                records += 1;
+                cleaner.accept(record, &mut visit)?;
+            }
+            error.map_or(Ok(()), Err)
+        },
+    )?;
+    if let Some(path) = source_name.filter(|_| options.verbose) {
+        log_parsed_file(path, parse_options.part, records);
+    }
+    cleaner.finish(&mut visit)
+}
+
+/// Streams FASTA records from a buffered reader, cleans them, and invokes `visit` for each kept
+/// record.
+///
+/// This applies the same stages as [`clean_sequences`] one record at a time, so memory stays
+/// bounded for very large inputs. Records are visited in input order, except with
+/// `random_subsample`, where the sample is visited in random order once the input is exhausted.
+/// Returns a [`CleanReport`] whose `records_out` is the number of records visited.
+///
+/// When several records would fail, the first failing record in input order is reported. Options
+/// for which [`CleanOptions::supports_streaming`] is `false` are rejected.
+///
+/// # Example
+/// ```
+/// use std::io::Cursor;
+///
+/// use rfasta::clean::{visit_cleaned_fasta_reader, CleanOptions, InvalidSequenceAction};
+/// use rfasta::parse::ParseOptions;
+///
+/// let mut sequences = Vec::new();
+/// visit_cleaned_fasta_reader(
+///     Cursor::new(b">seq1\nACDX\n>seq2\nACD?\n"),
+///     ParseOptions::default(),
+///     &CleanOptions {
+///         invalid_sequence_action: InvalidSequenceAction::ConvertRemove,
+///         ..CleanOptions::default()
+///     },
+///     |record| {
+///         sequences.push(record.sequence);
+///         Ok(())
+///     },
+/// )?;
+/// assert_eq!(sequences, vec!["ACDG".to_string()]);
+/// # Ok::<(), rfasta::RfastaError>(())
+/// ```
+pub fn visit_cleaned_fasta_reader<R, F>(
+    reader: R,
+    parse_options: ParseOptions,
+    options: &CleanOptions,
+    visit: F,
+) -> Result<CleanReport, RfastaError>
+where
+    R: BufRead,
+    F: FnMut(FastaRecord) -> Result<(), RfastaError>,
+{
+    match resolve_threads(options.threads) {
+        1 => visit_cleaned_records(options, visit, |push| {
+            visit_fasta_reader(reader, parse_options, push)
+        }),
+        threads => {
+            let mut reader = reader;
+            visit_cleaned_chunks(options, threads, &parse_options, None, visit, |submit| {
+                visit_fasta_reader_chunks(&mut reader, &parse_options, submit)
+            })
+        }
+    }
+}
+
+/// Streams and cleans FASTA records from a file path.
+///
+/// This is the file-based counterpart to [`visit_cleaned_fasta_reader`]; compressed inputs are
+/// decompressed transparently.
+pub fn visit_cleaned_fasta_file<P, F>(
+    path: P,
+    parse_options: ParseOptions,
+    options: &CleanOptions,
+    visit: F,
+) -> Result<CleanReport, RfastaError>
+where
+    P: AsRef<Path>,
+    F: FnMut(FastaRecord) -> Result<(), RfastaError>,
+{
+    match resolve_threads(options.threads) {
+        1 => visit_cleaned_records(options, visit, |push| {
+            visit_fasta_file(path, parse_options, options.verbose, push)
+        }),
+        threads => {
+            let path = path.as_ref();
+            visit_cleaned_chunks(
+                options,
+                threads,
+                &parse_options,
+                Some(path),
+                visit,
+                |submit| visit_fasta_file_chunks(path, &parse_options, submit),
+            )
+        }
+    }
+}
+
+/// Streams a FASTA file through the cleaner and writes kept records directly to `output_path`.
+///
+/// Only the records needed for duplicate tracking and subsampling are kept in memory. If cleaning
+/// fails partway through, the output file may contain the records written before the failure.
+///
+/// # Example
+/// ```no_run
+/// use rfasta::clean::{clean_fasta_file, CleanOptions, InvalidSequenceAction};
+/// use rfasta::parse::ParseOptions;
+/// use rfasta::write::WriteOptions;
+///
+/// let report = clean_fasta_file(
+///     "uniref100.fasta.gz",
+///     "uniref100.clean.fasta",
+///     ParseOptions::default(),
+///     &CleanOptions {
+///         invalid_sequence_action: InvalidSequenceAction::ConvertRemove,
+///         ..CleanOptions::default()
+///     },
+///     &WriteOptions::default(),
+/// )?;
+/// println!("Kept {} of {} records", report.records_out, report.records_in);
+/// # Ok::<(), rfasta::RfastaError>(())
+/// ```
+pub fn clean_fasta_file<P: AsRef<Path>, Q: AsRef<Path>>(
+    input_path: P,
+    output_path: Q,
+    parse_options: ParseOptions,
+    options: &CleanOptions,
+    write_options: &WriteOptions,
+) -> Result<CleanReport, RfastaError> {
+    let output_path = output_path.as_ref();
+    let mut writer = FastaWriter::create(output_path, write_options)?;
+    let report = visit_cleaned_fasta_file(input_path, parse_options, options, |record| {
+        writer.write_record(&record)
+    })?;
+    writer.finish()?;
+    if options.verbose {
+        eprintln!(
+            "[INFO]: Wrote {} sequences to {}",
+            report.records_out,
+            output_path.display()
+        );
+    }
+    Ok(report)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -258,8 +893,102 @@ mod tests {
             ..CleanOptions::default()
         };
 
-        let mut rng = StdRng::seed_from_u64(42);
-        let cleaned = clean_sequences_with_rng(records, &options, &mut rng).unwrap();
+        let mut rng = SplitMix64::new(42);
+        let (cleaned, report) = clean_sequences_with_rng(records, &options, &mut rng).unwrap();
         assert_eq!(cleaned.len(), 2);
+        assert_eq!(report.subsampled_away, 1);
+    }
+
+    #[test]
+    fn seeded_subsample_matches_between_batch_and_streaming() {
+        let records: Vec<FastaRecord> = (0..50)
+            .map(|index| FastaRecord::new(format!("seq{index}"), "ACDE"))
+            .collect();
+        let options = CleanOptions {
+            random_subsample: Some(5),
+            seed: Some(2024),
+            ..CleanOptions::default()
+        };
+
+        let mut cleaner = StreamingCleaner::new(&options).unwrap();
+        let mut streamed = Vec::new();
+        let mut emit = |record| {
+            streamed.push(record);
+            Ok(())
+        };
+        for record in records.clone() {
+            cleaner.push(record, &mut emit).unwrap();
+        }
+        cleaner.finish(&mut emit).unwrap();
+
+        assert_eq!(streamed, clean_sequences(records, &options).unwrap());
+    }
+
+    #[test]
+    fn streaming_cleaner_matches_batch_stages() {
+        let records = vec![
+            FastaRecord::new("a,1", "ACDX"),
+            FastaRecord::new("a,1", "ACDX"),
+            FastaRecord::new("b", "ACDX"),
+            FastaRecord::new("c", "AC?"),
+            FastaRecord::new("d", "AC"),
+            FastaRecord::new("e", "ACD*E"),
+        ];
+        let options = CleanOptions {
+            invalid_sequence_action: InvalidSequenceAction::ConvertRemove,
+            duplicate_record_action: DuplicateAction::Remove,
+            duplicate_sequence_action: DuplicateAction::Remove,
+            shortest_seq: Some(3),
+            remove_comma_from_header: true,
+            ..CleanOptions::default()
+        };
+
+        let mut cleaner = StreamingCleaner::new(&options).unwrap();
+        let mut streamed = Vec::new();
+        let mut emit = |record| {
+            streamed.push(record);
+            Ok(())
+        };
+        for record in records.clone() {
+            cleaner.push(record, &mut emit).unwrap();
+        }
+        let streamed_report = cleaner.finish(&mut emit).unwrap();
+
+        let (batch, batch_report) = clean_sequences_with_report(records, &options).unwrap();
+        assert_eq!(streamed, batch);
+        assert_eq!(streamed_report, batch_report);
+    }
+
+    #[test]
+    fn streaming_reservoir_keeps_requested_sample_size() {
+        let options = CleanOptions {
+            random_subsample: Some(3),
+            seed: Some(1),
+            ..CleanOptions::default()
+        };
+        let mut cleaner = StreamingCleaner::new(&options).unwrap();
+        let mut sampled = Vec::new();
+        let mut emit = |record| {
+            sampled.push(record);
+            Ok(())
+        };
+        for index in 0..100 {
+            cleaner
+                .push(FastaRecord::new(format!("seq{index}"), "ACDE"), &mut emit)
+                .unwrap();
+        }
+        let report = cleaner.finish(&mut emit).unwrap();
+        assert_eq!(sampled.len(), 3);
+        assert_eq!(report.subsampled_away, 97);
+    }
+
+    #[test]
+    fn streaming_rejects_duplicate_sequence_fail_mode() {
+        let options = CleanOptions {
+            duplicate_sequence_action: DuplicateAction::Fail,
+            ..CleanOptions::default()
+        };
+        assert!(!options.supports_streaming());
+        assert!(StreamingCleaner::new(&options).is_err());
     }
 }
@@ -1,6 +1,54 @@
 //! FASTA sharding for large sequential workloads.
 //!
-//! The default sharding strategy is one-pass round-robin splitting, which keeps I/O sequential and
-//! avoids rereading the source FASTA for UniRef-scale inputs.
+//! Every strategy reads the input once, and all but similarity grouping stream it, keeping I/O
+//! sequential for UniRef-scale inputs. [`split_fasta_file`] and [`split_fasta_reader`] take any
+//! [`ShardStrategy`]; the other entry points are shorthands for one strategy:
+//!
+//! - Round-robin (the default): [`split_fasta_file_round_robin`], deals records to shards in turn.
+//! - Balanced: [`split_fasta_file_balanced`], sends each record to the shard with the fewest
+//!   residues or bytes so far.
+//! - Hashed: [`split_fasta_file_hashed`], places records by a stable hash of a [`ShardKey`], so
+//!   assignments survive reordering and new releases.
+//! - Rolling: [`split_fasta_file_rolling`], starts a new shard at a [`ShardLimits`] record or byte
+//!   limit.
+//! - Partitioned: [`split_fasta_file_by_fraction`], writes named train/validation/test files by
+//!   seeded draw or stable hash.
+//! - Grouped: [`split_fasta_file_by_similarity`], keeps identical and k-mer-similar sequences in
+//!   one partition and reports leakage. Holds every record in memory.
+//! - Keyed: [`split_fasta_file_by_key`], writes one file per [`GroupKey`] value plus a
+//!   `groups.tsv` table.
+//! - Explode/gather: [`explode_fasta_file`] writes one file per record; [`gather_fasta_files`]
+//!   concatenates them back.
+//! - Unsplit: [`unsplit_shard_dir`], interleaves round-robin shards back into input order.
+//!
+//! [`split_fasta_file`], the strategy shorthands above, and [`explode_fasta_file`] each have a
+//! `_reader` counterpart for decompressed streams, such as [`split_fasta_reader_by_key`]. Gather,
+//! unsplit, and [`split_fasta_file_round_robin_with_options`] read files only.
+//!
+//! Every split also writes a [`ShardManifest`] to `manifest.json` in the output directory, listing
+//! each shard's record count, residue count, size, and SHA-256. [`verify_shards`] re-checks a shard
+//! set against it.
 
-pub use crate::io::split_fasta_file_round_robin;
+pub use crate::explode::{
+    explode_fasta_file, explode_fasta_reader, gather_fasta_files, list_fasta_files, ExplodeOptions,
+    RecordName,
+};
+pub use crate::grouping::{
+    split_fasta_file_by_key, split_fasta_reader_by_key, GroupKey, GROUP_TABLE_FILE_NAME,
+};
+pub use crate::manifest::{
+    read_shard_manifest, verify_shards, ShardEntry, ShardManifest, MANIFEST_FILE_NAME,
+};
+pub use crate::partition::{
+    split_fasta_file_by_fraction, split_fasta_file_by_similarity, split_fasta_reader_by_fraction,
+    split_fasta_reader_by_similarity, LeakageReport, Partition, PartitionAssignment,
+    SimilarityGrouping,
+};
+pub use crate::sharding::{
+    shard_hash, split_fasta_file, split_fasta_file_balanced, split_fasta_file_hashed,
+    split_fasta_file_rolling, split_fasta_file_round_robin,
+    split_fasta_file_round_robin_with_options, split_fasta_reader, split_fasta_reader_balanced,
+    split_fasta_reader_hashed, split_fasta_reader_rolling, split_fasta_reader_round_robin,
+    ShardBalance, ShardKey, ShardLimits, ShardStrategy, SplitOptions,
+};
+pub use crate::unsplit::{unsplit_fasta_files, unsplit_shard_dir};
@@ -0,0 +1,255 @@
+use std::cell::RefCell;
+use std::collections::{BTreeMap, HashMap};
+use std::fs::{File, OpenOptions};
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+use crate::compression::{CompressedWriter, Compression};
+use crate::io::WriteOptions;
+use crate::manifest::HashingWriter;
+
+/// Bytes buffered per open shard before they are written to its file.
+const SHARD_BUFFER_SIZE: usize = 8 * 1024;
+
+/// Least-recently-used set of open shard files, bounded by `max_open`.
+///
+/// The pool owns the file handle, write buffer, and compressor of every open shard. When a shard
+/// needs its file and the pool is full, the shard that wrote least recently is closed: its current
+/// gzip member, BGZF block, or zstd frame is completed, and only its running size and SHA-256 stay
+/// in memory. It is reopened in append mode, starting a new member, the next time it has data.
+/// Uncompressed shards are byte-identical for every limit; compressed shards may hold several
+/// concatenated members, which decode to the same records.
+pub(crate) struct HandlePool {
+    max_open: usize,
+    compression: Compression,
+    compression_level: Option<i32>,
+    open: HashMap<usize, OpenShard>,
+    closed: HashMap<usize, HashingWriter<()>>,
+    by_last_use: BTreeMap<u64, usize>,
+    clock: u64,
+}
+
+/// A shard whose file is open: compression over a running size and SHA-256 of the file bytes.
+struct OpenShard {
+    path: PathBuf,
+    stream: CompressedWriter<HashingWriter<BufWriter<File>>>,
+    last_use: u64,
+}
+
+impl OpenShard {
+    /// Completes the current member and closes the file, keeping the running size and digest.
+    fn close(self) -> io::Result<HashingWriter<()>> {
+        let mut hashing = self.stream.finish_member()?;
+        hashing.flush()?;
+        Ok(hashing.replace_inner(()).0)
+    }
+}
+
+impl HandlePool {
+    pub(crate) fn shared(max_open: usize, options: &WriteOptions) -> Rc<RefCell<Self>> {
+        Rc::new(RefCell::new(Self {
+            max_open: max_open.max(1),
+            compression: options.compression,
+            compression_level: options.compression_level,
+            open: HashMap::new(),
+            closed: HashMap::new(),
+            by_last_use: BTreeMap::new(),
+            clock: 0,
+        }))
+    }
+
+    /// Creates the file for shard `id` at `path` and opens it.
+    fn create(&mut self, id: usize, path: &Path) -> io::Result<()> {
+        self.make_room()?;
+        let file = File::create(path)?;
+        self.insert(id, path, HashingWriter::new(()), file)
+    }
+
+    /// Writes `buf` to shard `id`, reopening its file at `path` if it was closed.
+    fn write(&mut self, id: usize, path: &Path, buf: &[u8]) -> io::Result<()> {
+        if buf.is_empty() {
+            return Ok(());
+        }
+        if !self.open.contains_key(&id) {
+            self.make_room()?;
+            let hashing = self
+                .closed
+                .remove(&id)
+                .expect("shards are created before they are written");
+            let file = OpenOptions::new().append(true).open(path)?;
+            self.insert(id, path, hashing, file)?;
+        }
+        let shard = self.open.get_mut(&id).expect("shard was opened above");
+        // Consecutive writes to the same shard skip the LRU bookkeeping.
+        if shard.last_use != self.clock {
+            self.clock += 1;
+            self.by_last_use.remove(&shard.last_use);
+            self.by_last_use.insert(self.clock, id);
+            shard.last_use = self.clock;
+        }
+        shard.stream.write_all(buf)
+    }
+
+    /// Completes shard `id` and returns the size and SHA-256 of its file.
+    fn finish(&mut self, id: usize, path: &Path) -> io::Result<(u64, String)> {
+        if let Some(shard) = self.open.remove(&id) {
+            self.by_last_use.remove(&shard.last_use);
+            let mut hashing = shard.stream.finish()?;
+            hashing.flush()?;
+            return Ok(hashing.finish());
+        }
+        let hashing = self
+            .closed
+            .remove(&id)
+            .expect("shards are created before they are finished");
+        let trailer = self.compression.stream_trailer();
+        if trailer.is_empty() {
+            return Ok(hashing.finish());
+        }
+        self.make_room()?;
+        let file = OpenOptions::new().append(true).open(path)?;
+        let (mut hashing, ()) = hashing.replace_inner(file);
+        hashing.write_all(trailer)?;
+        Ok(hashing.finish())
+    }
+
+    fn insert(
+        &mut self,
+        id: usize,
+        path: &Path,
+        hashing: HashingWriter<()>,
+        file: File,
+    ) -> io::Result<()> {
+        let (hashing, ()) =
+            hashing.replace_inner(BufWriter::with_capacity(SHARD_BUFFER_SIZE, file));
+        let stream = CompressedWriter::new(hashing, self.compression, self.compression_level)?;
+        self.clock += 1;
+        self.by_last_use.insert(self.clock, id);
+        self.open.insert(
+            id,
+            OpenShard {
+                path: path.to_path_buf(),
+                stream,
+                last_use: self.clock,
+            },
+        );
+        Ok(())
+    }
+
+    /// Closes the least recently used shard when the pool is full.
+    fn make_room(&mut self) -> io::Result<()> {
+        if self.open.len() < self.max_open {
+            return Ok(());
+        }
+        let (_, evicted) = self
+            .by_last_use
+            .pop_first()
+            .expect("a full pool has a least recently used shard");
+        let shard = self.open.remove(&evicted).expect("LRU entries are open");
+        let path = shard.path.clone();
+        let hashing = shard.close().map_err(|source| {
+            io::Error::new(source.kind(), format!("{}: {source}", path.display()))
+        })?;
+        self.closed.insert(evicted, hashing);
+        Ok(())
+    }
+}
+
+/// Writer for one shard file whose handle and compressor are held by a [`HandlePool`].
+pub(crate) struct PooledFile {
+    pool: Rc<RefCell<HandlePool>>,
+    id: usize,
+    path: PathBuf,
+}
+
+impl PooledFile {
+    /// Creates the shard file at `path` and returns a writer for it.
+    pub(crate) fn create(
+        pool: Rc<RefCell<HandlePool>>,
+        id: usize,
+        path: PathBuf,
+    ) -> io::Result<Self> {
+        pool.borrow_mut().create(id, &path)?;
+        Ok(Self { pool, id, path })
+    }
+
+    /// Completes the compressed stream and returns the size and SHA-256 of the file.
+    pub(crate) fn finish(self) -> io::Result<(u64, String)> {
+        self.pool.borrow_mut().finish(self.id, &self.path)
+    }
+}
+
+impl Write for PooledFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.pool.borrow_mut().write(self.id, &self.path, buf)?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Read;
+
+    use flate2::read::MultiGzDecoder;
+
+    use super::*;
+
+    fn write_rounds(dir: &Path, compression: Compression) -> Rc<RefCell<HandlePool>> {
+        std::fs::create_dir_all(dir).unwrap();
+        let options = WriteOptions {
+            compression,
+            ..WriteOptions::default()
+        };
+        let pool = HandlePool::shared(1, &options);
+        let mut files: Vec<PooledFile> = (0..3)
+            .map(|id| PooledFile::create(Rc::clone(&pool), id, dir.join(format!("{id}.txt"))))
+            .collect::<io::Result<_>>()
+            .unwrap();
+        for round in 0..3 {
+            for file in &mut files {
+                write!(file, "{round}").unwrap();
+                assert_eq!(pool.borrow().open.len(), 1);
+                assert_eq!(pool.borrow().closed.len(), 2);
+            }
+        }
+        for file in files {
+            file.finish().unwrap();
+        }
+        pool
+    }
+
+    #[test]
+    fn evicted_shards_reopen_in_append_mode() {
+        let dir = std::env::temp_dir().join(format!("rfasta_shard_pool_{}", std::process::id()));
+        let pool = write_rounds(&dir, Compression::None);
+        assert!(pool.borrow().open.is_empty());
+        assert!(pool.borrow().closed.is_empty());
+        for id in 0..3 {
+            let contents = std::fs::read_to_string(dir.join(format!("{id}.txt"))).unwrap();
+            assert_eq!(contents, "012");
+        }
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn evicted_compressed_shards_end_their_member_and_decode_in_full() {
+        let dir =
+            std::env::temp_dir().join(format!("rfasta_shard_pool_bgzf_{}", std::process::id()));
+        write_rounds(&dir, Compression::Bgzf);
+        for id in 0..3 {
+            let encoded = std::fs::read(dir.join(format!("{id}.txt"))).unwrap();
+            assert!(encoded.ends_with(Compression::Bgzf.stream_trailer()));
+            let mut decoded = String::new();
+            MultiGzDecoder::new(encoded.as_slice())
+                .read_to_string(&mut decoded)
+                .unwrap();
+            assert_eq!(decoded, "012");
+        }
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
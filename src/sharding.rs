@@ -0,0 +1,991 @@
+use std::cell::RefCell;
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+use std::fs;
+use std::io::BufRead;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+
+use crate::configs::DEFAULT_MAX_OPEN_FILES;
+use crate::errors::RfastaError;
+use crate::grouping::{split_reader_keyed, GroupKey};
+use crate::io::{
+    formatted_record_len, input_stem, open_fasta_input, visit_fasta_reader_with_source,
+    write_record_to_writer, FastaRecord, ParseOptions, WriteOptions,
+};
+use crate::manifest::{ShardEntry, ShardManifest};
+use crate::partition::{
+    split_reader_grouped, split_reader_partitioned, Partition, PartitionAssignment,
+    SimilarityGrouping,
+};
+use crate::shard_pool::{HandlePool, PooledFile};
+
+/// Load measure used to balance a fixed number of shards.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq, Serialize, ValueEnum)]
+#[serde(rename_all = "snake_case")]
+pub enum ShardBalance {
+    /// Equal record counts. This is plain round-robin assignment.
+    #[default]
+    Records,
+    /// Equal residue counts, so per-shard compute is similar when sequence lengths vary.
+    Residues,
+    /// Equal output size in bytes, measured before compression.
+    Bytes,
+}
+
+/// Record field hashed to pick a shard in [`split_fasta_file_hashed`].
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize, ValueEnum)]
+#[serde(rename_all = "snake_case")]
+pub enum ShardKey {
+    /// The full header text.
+    Header,
+    /// The accession: the UniProt accession in `db|ACCESSION|NAME` headers, otherwise the header
+    /// up to the first whitespace.
+    Accession,
+    /// The uppercased sequence, so identical sequences always share a shard.
+    Sequence,
+}
+
+impl ShardKey {
+    pub(crate) fn value<'a>(&self, record: &'a FastaRecord) -> &'a str {
+        match self {
+            Self::Header => &record.header,
+            Self::Accession => header_accession(&record.header),
+            Self::Sequence => &record.sequence,
+        }
+    }
+}
+
+/// Returns the accession portion of a FASTA header.
+pub(crate) fn header_accession(header: &str) -> &str {
+    let token = header.split_whitespace().next().unwrap_or("");
+    let mut fields = token.split('|');
+    match (fields.next(), fields.next(), fields.next()) {
+        (Some(_), Some(accession), Some(_)) if !accession.is_empty() => accession,
+        _ => token,
+    }
+}
+
+/// Stable 64-bit hash used for shard partitioning.
+///
+/// This is 64-bit FNV-1a over the UTF-8 key bytes, followed by the SplitMix64 finalizer so that
+/// every output bit depends on every input byte. The algorithm is part of the public contract: the
+/// same key maps to the same shard for a given shard count in every `rfasta` release.
+pub fn shard_hash(key: &str) -> u64 {
+    hash_bytes(key.as_bytes())
+}
+
+/// [`shard_hash`] over raw bytes.
+pub(crate) fn hash_bytes(bytes: &[u8]) -> u64 {
+    let mut hash = 0xcbf2_9ce4_8422_2325_u64;
+    for &byte in bytes {
+        hash ^= u64::from(byte);
+        hash = hash.wrapping_mul(0x0000_0100_0000_01b3);
+    }
+    hash = (hash ^ (hash >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    hash = (hash ^ (hash >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    hash ^ (hash >> 31)
+}
+
+/// How [`split_fasta_file`] distributes records across shard files.
+///
+/// Recorded in the shard manifest as an object tagged with `"kind"`, for example
+/// `{"kind": "hashed", "chunks": 64, "key": "sequence"}`.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ShardStrategy {
+    /// `chunks` shards, each record going to the least-loaded shard; see
+    /// [`split_fasta_file_balanced`].
+    Balanced {
+        chunks: usize,
+        balance: ShardBalance,
+    },
+    /// `chunks` shards chosen by a stable hash of `key`; see [`split_fasta_file_hashed`].
+    Hashed { chunks: usize, key: ShardKey },
+    /// As many shards as `limits` require; see [`split_fasta_file_rolling`].
+    Rolling(ShardLimits),
+    /// One named file per partition, sized by fraction; see
+    /// [`split_fasta_file_by_fraction`](crate::shard::split_fasta_file_by_fraction).
+    Partitioned {
+        partitions: Vec<Partition>,
+        assignment: PartitionAssignment,
+    },
+    /// Named partitions holding whole groups of similar sequences; see
+    /// [`split_fasta_file_by_similarity`](crate::shard::split_fasta_file_by_similarity).
+    Grouped {
+        partitions: Vec<Partition>,
+        grouping: SimilarityGrouping,
+        seed: u64,
+    },
+    /// One file per distinct record key; see
+    /// [`split_fasta_file_by_key`](crate::shard::split_fasta_file_by_key).
+    Keyed { key: GroupKey },
+}
+
+/// Options for writing shard files.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SplitOptions {
+    /// Line length and compression applied to every shard. [`WriteOptions::append`] is ignored;
+    /// shard files are always recreated.
+    pub write: WriteOptions,
+    /// Maximum shard files held open at once.
+    ///
+    /// Shards beyond the limit are closed least-recently-used first and reopened in append mode
+    /// when they next receive data, and only their running size and SHA-256 stay in memory while
+    /// closed. Uncompressed shards are byte-identical for any limit. A compressed shard closed
+    /// mid-split ends its gzip member, BGZF block, or zstd frame and starts a new one on reopening,
+    /// so it decodes to the same records but its bytes and checksum depend on the limit.
+    pub max_open_files: usize,
+}
+
+impl Default for SplitOptions {
+    fn default() -> Self {
+        Self {
+            write: WriteOptions::default(),
+            max_open_files: DEFAULT_MAX_OPEN_FILES,
+        }
+    }
+}
+
+impl From<WriteOptions> for SplitOptions {
+    fn from(write: WriteOptions) -> Self {
+        Self {
+            write,
+            ..Self::default()
+        }
+    }
+}
+
+/// Size limits for [`split_fasta_file_rolling`]. At least one limit must be set.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+pub struct ShardLimits {
+    /// Maximum records per shard.
+    pub max_records: Option<usize>,
+    /// Maximum bytes per shard, measured before compression. A record larger than the limit is
+    /// written to a shard of its own rather than split.
+    pub max_bytes: Option<u64>,
+}
+
+impl ShardLimits {
+    fn validate(&self) -> Result<(), RfastaError> {
+        if self.max_records.is_none() && self.max_bytes.is_none() {
+            return Err(RfastaError::invalid_input(
+                "split",
+                "rolling shards need a record or byte limit",
+                "Set ShardLimits::max_records or ShardLimits::max_bytes (--max-records / --max-bytes).",
+            ));
+        }
+        if self.max_records == Some(0) || self.max_bytes == Some(0) {
+            return Err(RfastaError::invalid_input(
+                "split",
+                "shard limits must be greater than zero",
+                "Pass a --max-records or --max-bytes value of at least 1.",
+            ));
+        }
+        Ok(())
+    }
+
+    /// Returns `true` when adding a record of `record_bytes` to `shard` would exceed a limit.
+    fn is_full(&self, shard: &ShardWriter, record_bytes: u64) -> bool {
+        self.max_records
+            .is_some_and(|max_records| shard.records_written >= max_records)
+            || self.max_bytes.is_some_and(|max_bytes| {
+                shard.records_written > 0 && shard.bytes_written + record_bytes > max_bytes
+            })
+    }
+}
+
+/// Shard file stem used when the input name cannot provide one.
+pub(crate) const DEFAULT_SHARD_STEM: &str = "rfasta_shard";
+
+/// Writer for one shard file that tracks the counts recorded in its manifest entry.
+pub(crate) struct ShardWriter {
+    path: PathBuf,
+    file: PooledFile,
+    records_written: usize,
+    residues_written: u64,
+    /// Bytes written before compression.
+    bytes_written: u64,
+}
+
+impl ShardWriter {
+    /// Creates the shard file at `path`, held by `pool` under `id`.
+    pub(crate) fn create(
+        pool: &Rc<RefCell<HandlePool>>,
+        id: usize,
+        path: PathBuf,
+    ) -> Result<Self, RfastaError> {
+        let file = PooledFile::create(Rc::clone(pool), id, path.clone())
+            .map_err(|source| shard_write_error(&path, source))?;
+        Ok(Self {
+            path,
+            file,
+            records_written: 0,
+            residues_written: 0,
+            bytes_written: 0,
+        })
+    }
+
+    pub(crate) fn write(
+        &mut self,
+        record: &FastaRecord,
+        line_length: Option<usize>,
+    ) -> Result<(), RfastaError> {
+        write_record_to_writer(&mut self.file, record, line_length, Some(&self.path))?;
+        self.records_written += 1;
+        self.residues_written += record.sequence.len() as u64;
+        self.bytes_written += formatted_record_len(record, line_length) as u64;
+        Ok(())
+    }
+
+    /// Completes the shard file and returns its manifest entry.
+    pub(crate) fn finish(self, verbose: bool) -> Result<ShardEntry, RfastaError> {
+        let (bytes, sha256) = self
+            .file
+            .finish()
+            .map_err(|source| shard_write_error(&self.path, source))?;
+        if verbose {
+            eprintln!(
+                "[INFO]: Wrote {} sequences to {}",
+                self.records_written,
+                self.path.display()
+            );
+        }
+        Ok(ShardEntry {
+            path: self
+                .path
+                .file_name()
+                .expect("shard paths end in a file name")
+                .to_string_lossy()
+                .into_owned(),
+            records: self.records_written,
+            residues: self.residues_written,
+            bytes,
+            sha256,
+            group: None,
+        })
+    }
+}
+
+pub(crate) fn shard_write_error(path: &Path, source: std::io::Error) -> RfastaError {
+    RfastaError::io(
+        "split",
+        Some(path),
+        source,
+        "Check that the output directory is writable and has enough free space.",
+    )
+}
+
+/// Splits records into round-robin shards in memory.
+///
+/// This helper is convenient for tests or small library workflows. For UniRef-scale files, prefer
+/// [`split_fasta_file_round_robin`] so records are streamed directly into shard writers.
+#[cfg_attr(not(test), allow(dead_code))]
+pub(crate) fn split_records_round_robin(
+    records: Vec<FastaRecord>,
+    chunks: usize,
+) -> Result<Vec<Vec<FastaRecord>>, RfastaError> {
+    if chunks == 0 {
+        return Err(RfastaError::invalid_chunk_count(
+            chunks,
+            "Pass a chunk count greater than zero.",
+        ));
+    }
+
+    let mut split = std::iter::repeat_with(Vec::new)
+        .take(chunks)
+        .collect::<Vec<_>>();
+    for (index, record) in records.into_iter().enumerate() {
+        split[index % chunks].push(record);
+    }
+    split.retain(|chunk| !chunk.is_empty());
+    Ok(split)
+}
+
+/// Streams a FASTA file into round-robin shard files without first collecting all records.
+///
+/// Shards are balanced by record order, not exact byte size. This avoids rereading the input file
+/// and keeps memory bounded for very large inputs such as UniRef FASTA distributions. Compressed
+/// inputs are decompressed transparently, and shard names drop the compression extension, so
+/// `uniref90.fasta.gz` produces `uniref90_000001.fasta`, `uniref90_000002.fasta`, and so on.
+///
+/// # Example
+/// ```no_run
+/// use rfasta::shard::split_fasta_file_round_robin;
+///
+/// let written = split_fasta_file_round_robin("proteins.fasta", "shards", 8, Some(60), true)?;
+/// println!("Wrote {written} shard files");
+/// # Ok::<(), rfasta::RfastaError>(())
+/// ```
+pub fn split_fasta_file_round_robin<P: AsRef<Path>, Q: AsRef<Path>>(
+    input_path: P,
+    output_dir: Q,
+    chunks: usize,
+    line_length: Option<usize>,
+    verbose: bool,
+) -> Result<usize, RfastaError> {
+    split_fasta_file_round_robin_with_options(
+        input_path,
+        output_dir,
+        chunks,
+        &SplitOptions::from(WriteOptions {
+            line_length,
+            ..WriteOptions::default()
+        }),
+        verbose,
+    )
+}
+
+/// Streams a FASTA file into round-robin shard files using explicit write options.
+///
+/// This behaves like [`split_fasta_file_round_robin`], but applies the line length and compression
+/// from `options` to every shard. Compressed shards carry the matching extension, for example
+/// `proteins_000001.fasta.gz`.
+///
+/// # Example
+/// ```no_run
+/// use rfasta::shard::{split_fasta_file_round_robin_with_options, SplitOptions};
+/// use rfasta::write::{Compression, WriteOptions};
+///
+/// let options = SplitOptions::from(WriteOptions {
+///     compression: Compression::Zstd,
+///     ..WriteOptions::default()
+/// });
+/// split_fasta_file_round_robin_with_options("uniref90.fasta.gz", "shards", 32, &options, true)?;
+/// # Ok::<(), rfasta::RfastaError>(())
+/// ```
+pub fn split_fasta_file_round_robin_with_options<P: AsRef<Path>, Q: AsRef<Path>>(
+    input_path: P,
+    output_dir: Q,
+    chunks: usize,
+    options: &SplitOptions,
+    verbose: bool,
+) -> Result<usize, RfastaError> {
+    split_fasta_file_balanced(
+        input_path,
+        output_dir,
+        chunks,
+        ShardBalance::Records,
+        options,
+        verbose,
+    )
+}
+
+/// Streams FASTA records from any buffered reader into round-robin shard files.
+///
+/// This is the reader-based counterpart to [`split_fasta_file_round_robin_with_options`], useful
+/// for standard input or other non-file sources. Shards are named `{stem}_000001.fasta` and so on.
+///
+/// # Example
+/// ```no_run
+/// use rfasta::shard::{split_fasta_reader_round_robin, SplitOptions};
+///
+/// let stdin = std::io::stdin().lock();
+/// split_fasta_reader_round_robin(stdin, "shards", "proteins", 8, &SplitOptions::default(), false)?;
+/// # Ok::<(), rfasta::RfastaError>(())
+/// ```
+pub fn split_fasta_reader_round_robin<R: BufRead, Q: AsRef<Path>>(
+    reader: R,
+    output_dir: Q,
+    stem: &str,
+    chunks: usize,
+    options: &SplitOptions,
+    verbose: bool,
+) -> Result<usize, RfastaError> {
+    split_fasta_reader_balanced(
+        reader,
+        output_dir,
+        stem,
+        chunks,
+        ShardBalance::Records,
+        options,
+        verbose,
+    )
+}
+
+/// Streams a FASTA file into `chunks` shard files, balancing them by `balance`.
+///
+/// Each record goes to the shard with the smallest load so far, where load is the record count,
+/// residue count, or formatted output size selected by `balance`; ties go to the lowest shard
+/// number. Assignment happens in a single pass, so a record is placed before later records are
+/// seen. [`ShardBalance::Records`] reproduces [`split_fasta_file_round_robin_with_options`]
+/// exactly. Shard naming and compression follow [`split_fasta_file_round_robin_with_options`].
+///
+/// # Example
+/// ```no_run
+/// use rfasta::shard::{split_fasta_file_balanced, ShardBalance, SplitOptions};
+///
+/// split_fasta_file_balanced(
+///     "uniref90.fasta.gz",
+///     "shards",
+///     64,
+///     ShardBalance::Residues,
+///     &SplitOptions::default(),
+///     true,
+/// )?;
+/// # Ok::<(), rfasta::RfastaError>(())
+/// ```
+pub fn split_fasta_file_balanced<P: AsRef<Path>, Q: AsRef<Path>>(
+    input_path: P,
+    output_dir: Q,
+    chunks: usize,
+    balance: ShardBalance,
+    options: &SplitOptions,
+    verbose: bool,
+) -> Result<usize, RfastaError> {
+    split_fasta_file(
+        input_path,
+        output_dir,
+        &ShardStrategy::Balanced { chunks, balance },
+        options,
+        verbose,
+    )
+}
+
+/// Streams FASTA records from any buffered reader into `chunks` shard files balanced by `balance`.
+///
+/// This is the reader-based counterpart to [`split_fasta_file_balanced`]. Shards are named
+/// `{stem}_000001.fasta` and so on.
+pub fn split_fasta_reader_balanced<R: BufRead, Q: AsRef<Path>>(
+    reader: R,
+    output_dir: Q,
+    stem: &str,
+    chunks: usize,
+    balance: ShardBalance,
+    options: &SplitOptions,
+    verbose: bool,
+) -> Result<usize, RfastaError> {
+    split_fasta_reader(
+        reader,
+        output_dir,
+        stem,
+        &ShardStrategy::Balanced { chunks, balance },
+        options,
+        verbose,
+    )
+}
+
+/// Streams a FASTA file into `chunks` shard files chosen by hashing `key`.
+///
+/// Record `r` goes to shard `shard_hash(key(r)) % chunks` (numbered from 1 in the file name), so
+/// the assignment depends only on the record and the shard count, not on input order. Re-splitting
+/// a new release keeps unchanged records in the same shard, and with [`ShardKey::Sequence`]
+/// identical sequences always land together. Shard sizes are balanced only statistically, and
+/// shards that receive no records are not created. Naming and compression follow
+/// [`split_fasta_file_round_robin_with_options`].
+///
+/// # Example
+/// ```no_run
+/// use rfasta::shard::{split_fasta_file_hashed, ShardKey, SplitOptions};
+///
+/// split_fasta_file_hashed(
+///     "uniref90.fasta.gz",
+///     "shards",
+///     256,
+///     ShardKey::Accession,
+///     &SplitOptions::default(),
+///     true,
+/// )?;
+/// # Ok::<(), rfasta::RfastaError>(())
+/// ```
+pub fn split_fasta_file_hashed<P: AsRef<Path>, Q: AsRef<Path>>(
+    input_path: P,
+    output_dir: Q,
+    chunks: usize,
+    key: ShardKey,
+    options: &SplitOptions,
+    verbose: bool,
+) -> Result<usize, RfastaError> {
+    split_fasta_file(
+        input_path,
+        output_dir,
+        &ShardStrategy::Hashed { chunks, key },
+        options,
+        verbose,
+    )
+}
+
+/// Streams FASTA records from any buffered reader into `chunks` shard files chosen by hashing
+/// `key`.
+///
+/// This is the reader-based counterpart to [`split_fasta_file_hashed`].
+pub fn split_fasta_reader_hashed<R: BufRead, Q: AsRef<Path>>(
+    reader: R,
+    output_dir: Q,
+    stem: &str,
+    chunks: usize,
+    key: ShardKey,
+    options: &SplitOptions,
+    verbose: bool,
+) -> Result<usize, RfastaError> {
+    split_fasta_reader(
+        reader,
+        output_dir,
+        stem,
+        &ShardStrategy::Hashed { chunks, key },
+        options,
+        verbose,
+    )
+}
+
+/// Streams a FASTA file into as many shard files as needed to stay within `limits`.
+///
+/// Records are written in input order to `{stem}_000001.fasta` until the next record would exceed
+/// a limit, then `{stem}_000002.fasta` is started, and so on; only one shard is open at a time.
+/// Naming and compression follow [`split_fasta_file_round_robin_with_options`]. Returns the number
+/// of shard files written.
+///
+/// # Example
+/// ```no_run
+/// use rfasta::shard::{split_fasta_file_rolling, ShardLimits, SplitOptions};
+///
+/// let limits = ShardLimits {
+///     max_records: Some(100_000),
+///     max_bytes: Some(512 * 1024 * 1024),
+/// };
+/// split_fasta_file_rolling("uniref90.fasta.gz", "shards", &limits, &SplitOptions::default(), true)?;
+/// # Ok::<(), rfasta::RfastaError>(())
+/// ```
+pub fn split_fasta_file_rolling<P: AsRef<Path>, Q: AsRef<Path>>(
+    input_path: P,
+    output_dir: Q,
+    limits: &ShardLimits,
+    options: &SplitOptions,
+    verbose: bool,
+) -> Result<usize, RfastaError> {
+    split_fasta_file(
+        input_path,
+        output_dir,
+        &ShardStrategy::Rolling(*limits),
+        options,
+        verbose,
+    )
+}
+
+/// Streams FASTA records from any buffered reader into shard files that stay within `limits`.
+///
+/// This is the reader-based counterpart to [`split_fasta_file_rolling`].
+pub fn split_fasta_reader_rolling<R: BufRead, Q: AsRef<Path>>(
+    reader: R,
+    output_dir: Q,
+    stem: &str,
+    limits: &ShardLimits,
+    options: &SplitOptions,
+    verbose: bool,
+) -> Result<usize, RfastaError> {
+    split_fasta_reader(
+        reader,
+        output_dir,
+        stem,
+        &ShardStrategy::Rolling(*limits),
+        options,
+        verbose,
+    )
+}
+
+/// Streams a FASTA file into shard files using `strategy`.
+///
+/// This is the common entry point behind the strategy-specific functions; the shard stem comes
+/// from the input file name, as in [`split_fasta_file_round_robin_with_options`]. Returns the
+/// number of shard files written.
+///
+/// # Example
+/// ```no_run
+/// use rfasta::shard::{split_fasta_file, ShardKey, ShardStrategy, SplitOptions};
+///
+/// let strategy = ShardStrategy::Hashed {
+///     chunks: 64,
+///     key: ShardKey::Sequence,
+/// };
+/// split_fasta_file("proteins.fasta", "shards", &strategy, &SplitOptions::default(), true)?;
+/// # Ok::<(), rfasta::RfastaError>(())
+/// ```
+pub fn split_fasta_file<P: AsRef<Path>, Q: AsRef<Path>>(
+    input_path: P,
+    output_dir: Q,
+    strategy: &ShardStrategy,
+    options: &SplitOptions,
+    verbose: bool,
+) -> Result<usize, RfastaError> {
+    let input_path = input_path.as_ref();
+    let stem = input_stem(input_path).unwrap_or_else(|| DEFAULT_SHARD_STEM.to_string());
+    let mut reader = open_fasta_input(input_path)?;
+    let manifest = split_reader(
+        &mut reader,
+        Some(input_path),
+        output_dir.as_ref(),
+        &stem,
+        strategy,
+        options,
+        verbose,
+    )?;
+    Ok(manifest.shards.len())
+}
+
+/// Streams FASTA records from any buffered reader into shard files using `strategy`.
+///
+/// This is the reader-based counterpart to [`split_fasta_file`].
+pub fn split_fasta_reader<R: BufRead, Q: AsRef<Path>>(
+    mut reader: R,
+    output_dir: Q,
+    stem: &str,
+    strategy: &ShardStrategy,
+    options: &SplitOptions,
+    verbose: bool,
+) -> Result<usize, RfastaError> {
+    let manifest = split_reader(
+        &mut reader,
+        None,
+        output_dir.as_ref(),
+        stem,
+        strategy,
+        options,
+        verbose,
+    )?;
+    Ok(manifest.shards.len())
+}
+
+/// Runs `strategy` over `reader`, then writes and returns the shard manifest.
+pub(crate) fn split_reader<R: BufRead>(
+    reader: &mut R,
+    source_name: Option<&Path>,
+    output_dir: &Path,
+    stem: &str,
+    strategy: &ShardStrategy,
+    options: &SplitOptions,
+    verbose: bool,
+) -> Result<ShardManifest, RfastaError> {
+    let mut leakage = None;
+    let shards = match strategy {
+        &ShardStrategy::Balanced { chunks, balance } => split_reader_into_chunks(
+            reader,
+            source_name,
+            output_dir,
+            stem,
+            chunks,
+            Assignment::LeastLoaded(LeastLoaded::new(chunks, balance, options.write.line_length)),
+            options,
+            verbose,
+        ),
+        &ShardStrategy::Hashed { chunks, key } => split_reader_into_chunks(
+            reader,
+            source_name,
+            output_dir,
+            stem,
+            chunks,
+            Assignment::hash(key, chunks),
+            options,
+            verbose,
+        ),
+        ShardStrategy::Rolling(limits) => split_reader_rolling(
+            reader,
+            source_name,
+            output_dir,
+            stem,
+            limits,
+            options,
+            verbose,
+        ),
+        ShardStrategy::Partitioned {
+            partitions,
+            assignment,
+        } => split_reader_partitioned(
+            reader,
+            source_name,
+            output_dir,
+            stem,
+            partitions,
+            assignment,
+            options,
+            verbose,
+        ),
+        ShardStrategy::Grouped {
+            partitions,
+            grouping,
+            seed,
+        } => split_reader_grouped(
+            reader,
+            source_name,
+            output_dir,
+            stem,
+            partitions,
+            grouping,
+            *seed,
+            options,
+            verbose,
+        )
+        .map(|(shards, report)| {
+            leakage = Some(report);
+            shards
+        }),
+        ShardStrategy::Keyed { key } => {
+            split_reader_keyed(reader, source_name, output_dir, stem, key, options, verbose)
+        }
+    }?;
+
+    let manifest = ShardManifest {
+        rfasta_version: env!("CARGO_PKG_VERSION").to_string(),
+        input: source_name.map(|path| path.display().to_string()),
+        strategy: strategy.clone(),
+        line_length: options.write.line_length,
+        compression: options.write.compression,
+        compression_level: options.write.compression_level,
+        shards,
+        leakage,
+    };
+    manifest.write(output_dir)?;
+
+    if verbose {
+        eprintln!("[INFO]: Split FASTA into {} chunks", manifest.shards.len());
+    }
+
+    Ok(manifest)
+}
+
+/// How records are mapped to a fixed set of shards.
+enum Assignment {
+    LeastLoaded(LeastLoaded),
+    Hash { key: ShardKey, chunks: u64 },
+}
+
+impl Assignment {
+    fn hash(key: ShardKey, chunks: usize) -> Self {
+        Self::Hash {
+            key,
+            chunks: chunks as u64,
+        }
+    }
+
+    fn assign(&mut self, record: &FastaRecord) -> usize {
+        match self {
+            Self::LeastLoaded(least_loaded) => least_loaded.assign(record),
+            Self::Hash { key, chunks } => (shard_hash(key.value(record)) % *chunks) as usize,
+        }
+    }
+}
+
+/// Greedy least-loaded shard assignment.
+struct LeastLoaded {
+    balance: ShardBalance,
+    line_length: Option<usize>,
+    loads: BinaryHeap<Reverse<(u64, usize)>>,
+}
+
+impl LeastLoaded {
+    fn new(chunks: usize, balance: ShardBalance, line_length: Option<usize>) -> Self {
+        Self {
+            balance,
+            line_length,
+            loads: (0..chunks).map(|shard| Reverse((0, shard))).collect(),
+        }
+    }
+
+    /// Returns the shard for `record` and adds the record's weight to it.
+    fn assign(&mut self, record: &FastaRecord) -> usize {
+        let weight = match self.balance {
+            ShardBalance::Records => 1,
+            ShardBalance::Residues => record.sequence.len(),
+            ShardBalance::Bytes => formatted_record_len(record, self.line_length),
+        };
+        let mut lightest = self
+            .loads
+            .peek_mut()
+            .expect("shard count is validated to be non-zero");
+        let Reverse((load, shard)) = *lightest;
+        *lightest = Reverse((load + weight as u64, shard));
+        shard
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn split_reader_into_chunks<R: BufRead>(
+    reader: &mut R,
+    source_name: Option<&Path>,
+    output_dir: &Path,
+    stem: &str,
+    chunks: usize,
+    mut assignment: Assignment,
+    options: &SplitOptions,
+    verbose: bool,
+) -> Result<Vec<ShardEntry>, RfastaError> {
+    if chunks == 0 {
+        return Err(RfastaError::invalid_chunk_count(
+            chunks,
+            "Pass a chunk count greater than zero.",
+        ));
+    }
+
+    let extension = prepare_output_dir(output_dir, &options.write)?;
+    let pool = HandlePool::shared(options.max_open_files, &options.write);
+    let mut writers: Vec<Option<ShardWriter>> =
+        std::iter::repeat_with(|| None).take(chunks).collect();
+
+    visit_fasta_reader_with_source(
+        reader,
+        ParseOptions {
+            expect_unique_header: false,
+            part: None,
+        },
+        source_name,
+        &mut |record| {
+            let target = assignment.assign(&record);
+            if writers[target].is_none() {
+                let number = target + 1;
+                writers[target] = Some(ShardWriter::create(
+                    &pool,
+                    number,
+                    output_dir.join(format!("{stem}_{number:06}.{extension}")),
+                )?);
+            }
+            writers[target]
+                .as_mut()
+                .expect("writer is initialized before use")
+                .write(&record, options.write.line_length)
+        },
+    )?;
+
+    writers
+        .into_iter()
+        .flatten()
+        .map(|shard| shard.finish(verbose))
+        .collect()
+}
+
+fn split_reader_rolling<R: BufRead>(
+    reader: &mut R,
+    source_name: Option<&Path>,
+    output_dir: &Path,
+    stem: &str,
+    limits: &ShardLimits,
+    options: &SplitOptions,
+    verbose: bool,
+) -> Result<Vec<ShardEntry>, RfastaError> {
+    limits.validate()?;
+    let extension = prepare_output_dir(output_dir, &options.write)?;
+    // Only one rolling shard is ever open.
+    let pool = HandlePool::shared(1, &options.write);
+    let mut current: Option<ShardWriter> = None;
+    let mut shards = Vec::new();
+
+    visit_fasta_reader_with_source(
+        reader,
+        ParseOptions {
+            expect_unique_header: false,
+            part: None,
+        },
+        source_name,
+        &mut |record| {
+            let record_bytes = formatted_record_len(&record, options.write.line_length) as u64;
+            if current
+                .as_ref()
+                .is_some_and(|shard| limits.is_full(shard, record_bytes))
+            {
+                shards.push(current.take().expect("checked above").finish(verbose)?);
+            }
+            if current.is_none() {
+                let number = shards.len() + 1;
+                current = Some(ShardWriter::create(
+                    &pool,
+                    number,
+                    output_dir.join(format!("{stem}_{number:06}.{extension}")),
+                )?);
+            }
+            current
+                .as_mut()
+                .expect("writer is initialized before use")
+                .write(&record, options.write.line_length)
+        },
+    )?;
+
+    if let Some(shard) = current {
+        shards.push(shard.finish(verbose)?);
+    }
+    Ok(shards)
+}
+
+/// Creates `output_dir` and returns the shard file extension for `options`.
+pub(crate) fn prepare_output_dir(
+    output_dir: &Path,
+    options: &WriteOptions,
+) -> Result<String, RfastaError> {
+    fs::create_dir_all(output_dir).map_err(|source| {
+        RfastaError::io(
+            "split",
+            Some(output_dir),
+            source,
+            "Check that the output directory is writable, or create it before running split.",
+        )
+    })?;
+
+    Ok(fasta_extension(options))
+}
+
+/// Returns the output file extension for `options`, such as `fasta` or `fasta.gz`.
+pub(crate) fn fasta_extension(options: &WriteOptions) -> String {
+    match options.compression.extension() {
+        Some(compressed) => format!("fasta.{compressed}"),
+        None => "fasta".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn split_records_round_robin_distributes_records_by_order() {
+        let records = vec![
+            FastaRecord::new("seq1", "AAAA"),
+            FastaRecord::new("seq2", "CCCC"),
+            FastaRecord::new("seq3", "DDDD"),
+            FastaRecord::new("seq4", "EEEE"),
+        ];
+        let chunks = split_records_round_robin(records, 2).unwrap();
+        assert_eq!(chunks.len(), 2);
+        assert_eq!(chunks[0][0].header, "seq1");
+        assert_eq!(chunks[0][1].header, "seq3");
+        assert_eq!(chunks[1][0].header, "seq2");
+        assert_eq!(chunks[1][1].header, "seq4");
+    }
+
+    #[test]
+    fn least_loaded_by_records_is_round_robin() {
+        let record = FastaRecord::new("seq", "ACDE");
+        let mut assigner = LeastLoaded::new(3, ShardBalance::Records, None);
+        let shards: Vec<usize> = (0..7).map(|_| assigner.assign(&record)).collect();
+        assert_eq!(shards, vec![0, 1, 2, 0, 1, 2, 0]);
+    }
+
+    #[test]
+    fn least_loaded_by_residues_fills_the_lightest_shard() {
+        let mut assigner = LeastLoaded::new(2, ShardBalance::Residues, None);
+        let shards: Vec<usize> = [100, 10, 10, 10, 100]
+            .into_iter()
+            .map(|len| assigner.assign(&FastaRecord::new("seq", "A".repeat(len))))
+            .collect();
+        assert_eq!(shards, vec![0, 1, 1, 1, 1]);
+    }
+
+    #[test]
+    fn shard_hash_is_pinned() {
+        assert_eq!(shard_hash(""), 17_665_956_581_633_026_203);
+        assert_eq!(shard_hash("P69905"), 10_537_771_969_989_805_561);
+        assert_eq!(shard_hash("MKTAYIAKQR"), 8_527_928_975_350_589_130);
+    }
+
+    #[test]
+    fn accession_key_reads_uniprot_and_plain_headers() {
+        assert_eq!(
+            header_accession("sp|P69905|HBA_HUMAN Hemoglobin subunit alpha"),
+            "P69905"
+        );
+        assert_eq!(
+            header_accession("UniRef90_P69905 Hemoglobin"),
+            "UniRef90_P69905"
+        );
+        assert_eq!(header_accession("seq1"), "seq1");
+    }
+}
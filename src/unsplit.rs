@@ -0,0 +1,246 @@
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver};
+use std::thread;
+
+use crate::errors::RfastaError;
+use crate::io::{visit_fasta_file, FastaRecord, FastaWriter, ParseOptions};
+use crate::manifest::read_shard_manifest;
+use crate::sharding::{ShardBalance, ShardStrategy};
+
+/// Records each shard reader parses ahead of the writer.
+const SHARD_READ_AHEAD: usize = 64;
+
+type ShardRecords = Receiver<Result<FastaRecord, RfastaError>>;
+
+/// Interleaves round-robin shards back into the original record order.
+///
+/// `shards` must be listed in shard-number order, as `rfasta split` numbers them. Record `i` of the
+/// original file is read from shard `i % shards.len()`, so shards may have been rewritten, for
+/// example re-cleaned or re-wrapped, as long as no records were added or removed. The shards are
+/// read in parallel, one record at a time, so memory use stays small for any input size. Fails
+/// when the shard record counts cannot come from a round-robin split. Returns the number of
+/// records written.
+///
+/// # Example
+/// ```no_run
+/// use rfasta::shard::unsplit_fasta_files;
+/// use rfasta::write::{FastaWriter, WriteOptions};
+///
+/// let shards = ["shards/proteins_000001.fasta", "shards/proteins_000002.fasta"];
+/// let mut writer = FastaWriter::create("proteins.fasta", &WriteOptions::default())?;
+/// unsplit_fasta_files(&shards, &mut writer, true)?;
+/// writer.finish()?;
+/// # Ok::<(), rfasta::RfastaError>(())
+/// ```
+pub fn unsplit_fasta_files<P: AsRef<Path>, W: Write>(
+    shards: &[P],
+    writer: &mut FastaWriter<W>,
+    verbose: bool,
+) -> Result<usize, RfastaError> {
+    let shards: Vec<&Path> = shards.iter().map(AsRef::as_ref).collect();
+    unsplit_shards(&shards, None, writer, verbose)
+}
+
+/// Interleaves the round-robin shard set in `dir` back into the original record order.
+///
+/// Shard paths and their order come from the directory's [`ShardManifest`], which must describe a
+/// round-robin split (`Balanced` by records). Checksums are not verified, since the shards may
+/// have been processed since the split, but each shard must still hold the record count the
+/// manifest lists. Returns the number of records written.
+///
+/// [`ShardManifest`]: crate::shard::ShardManifest
+pub fn unsplit_shard_dir<P: AsRef<Path>, W: Write>(
+    dir: P,
+    writer: &mut FastaWriter<W>,
+    verbose: bool,
+) -> Result<usize, RfastaError> {
+    let dir = dir.as_ref();
+    let manifest = read_shard_manifest(dir)?;
+    if !matches!(
+        manifest.strategy,
+        ShardStrategy::Balanced {
+            balance: ShardBalance::Records,
+            ..
+        }
+    ) {
+        return Err(RfastaError::invalid_input(
+            "unsplit",
+            format!(
+                "{} was not split round-robin, so the original order cannot be recovered",
+                dir.display()
+            ),
+            "Only shards written by `rfasta split --chunks N` with the default balance can be \
+             unsplit; use `rfasta gather` to concatenate other shard sets.",
+        ));
+    }
+    let paths: Vec<PathBuf> = manifest
+        .shards
+        .iter()
+        .map(|shard| dir.join(&shard.path))
+        .collect();
+    let paths: Vec<&Path> = paths.iter().map(PathBuf::as_path).collect();
+    let expected: Vec<usize> = manifest.shards.iter().map(|shard| shard.records).collect();
+    unsplit_shards(&paths, Some(&expected), writer, verbose)
+}
+
+fn unsplit_shards<W: Write>(
+    shards: &[&Path],
+    expected: Option<&[usize]>,
+    writer: &mut FastaWriter<W>,
+    verbose: bool,
+) -> Result<usize, RfastaError> {
+    if shards.is_empty() {
+        return Err(RfastaError::invalid_input(
+            "unsplit",
+            "no shards to interleave",
+            "Pass the shard files in shard-number order, or a directory with a manifest.json.",
+        ));
+    }
+    let counts = thread::scope(|scope| {
+        let readers = shards
+            .iter()
+            .map(|&path| {
+                let (sender, receiver) = mpsc::sync_channel(SHARD_READ_AHEAD);
+                scope.spawn(move || {
+                    let parsed = visit_fasta_file(
+                        path,
+                        ParseOptions {
+                            expect_unique_header: false,
+                            part: None,
+                        },
+                        false,
+                        |record| sender.send(Ok(record)).map_err(|_| writer_stopped()),
+                    );
+                    // When the writer has stopped, nobody is left to receive the error.
+                    if let Err(error) = parsed {
+                        let _ = sender.send(Err(error));
+                    }
+                });
+                receiver
+            })
+            .collect();
+        interleave(shards, readers, writer)
+    })?;
+
+    if let Some(expected) = expected {
+        let changed: Vec<String> = shards
+            .iter()
+            .zip(counts.iter().zip(expected))
+            .filter(|(_, (found, expected))| found != expected)
+            .map(|(path, (found, expected))| {
+                format!("{}: expected {expected}, found {found}", path.display())
+            })
+            .collect();
+        if !changed.is_empty() {
+            return Err(RfastaError::invalid_input(
+                "unsplit",
+                format!(
+                    "shard record counts differ from the manifest: {}",
+                    changed.join("; ")
+                ),
+                "Records were added or removed after the split, so the original order cannot \
+                 be restored.",
+            ));
+        }
+    }
+
+    let records = counts.iter().sum();
+    if verbose {
+        eprintln!(
+            "[INFO]: Interleaved {records} sequences from {} shards",
+            shards.len()
+        );
+    }
+    Ok(records)
+}
+
+/// Writes records from `readers` in round-robin order and returns the count read from each.
+fn interleave<W: Write>(
+    shards: &[&Path],
+    readers: Vec<ShardRecords>,
+    writer: &mut FastaWriter<W>,
+) -> Result<Vec<usize>, RfastaError> {
+    let mut counts = vec![0; readers.len()];
+    let exhausted = 'rounds: loop {
+        for (index, reader) in readers.iter().enumerate() {
+            match reader.recv() {
+                Ok(record) => {
+                    writer.write_record(&record?)?;
+                    counts[index] += 1;
+                }
+                Err(_) => break 'rounds index,
+            }
+        }
+    };
+
+    // Once one shard runs out, every shard has given all of its records.
+    for (index, reader) in readers.iter().enumerate() {
+        if index == exhausted {
+            continue;
+        }
+        if let Ok(record) = reader.recv() {
+            record?;
+            return Err(RfastaError::invalid_input(
+                "unsplit",
+                format!(
+                    "{} has more records than round-robin assignment allows: {} ran out after \
+                     {} records",
+                    shards[index].display(),
+                    shards[exhausted].display(),
+                    counts[exhausted]
+                ),
+                "Pass every shard in shard-number order, and make sure no records were added or \
+                 removed after the split.",
+            ));
+        }
+    }
+    Ok(counts)
+}
+
+/// Error a shard reader returns to stop parsing once the writer has failed.
+fn writer_stopped() -> RfastaError {
+    RfastaError::invalid_input(
+        "unsplit",
+        "the output stopped before every shard was read",
+        "See the earlier error for the cause.",
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn shard_readers(records: &[usize]) -> Vec<ShardRecords> {
+        records
+            .iter()
+            .map(|&count| {
+                let (sender, receiver) = mpsc::sync_channel(count);
+                for index in 0..count {
+                    sender
+                        .send(Ok(FastaRecord::new(format!("seq{index}"), "M")))
+                        .unwrap();
+                }
+                receiver
+            })
+            .collect()
+    }
+
+    #[test]
+    fn shard_counts_must_fit_round_robin() {
+        let shards = [Path::new("a"), Path::new("b"), Path::new("c")];
+        for (counts, consistent) in [
+            ([3, 3, 3], true),
+            ([3, 3, 2], true),
+            ([3, 2, 2], true),
+            ([0, 0, 0], true),
+            ([3, 2, 3], false),
+            ([2, 3, 3], false),
+            ([3, 3, 1], false),
+        ] {
+            let mut writer = FastaWriter::new(Vec::new(), &Default::default()).unwrap();
+            let result = interleave(&shards, shard_readers(&counts), &mut writer);
+            assert_eq!(result.is_ok(), consistent, "{counts:?}");
+        }
+    }
+}
@@ -1,28 +1,51 @@
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs;
+use std::path::Path;
 
 use crate::configs::{is_valid_residue, standard_conversion_map, standard_replacement};
+use crate::corrections::CorrectionRules;
 use crate::errors::RfastaError;
 use crate::io::FastaRecord;
+use crate::sharding::hash_bytes;
 
 /// Python-compatible correction dictionary type.
 pub type CorrectionDictionary = HashMap<String, String>;
 
-enum ConversionStrategy {
+pub(crate) enum ConversionStrategy {
     Standard { alignment: bool },
     SingleChar(HashMap<char, String>),
-    MultiPattern(Vec<(String, String)>),
+    Rules(CorrectionRules),
 }
 
+/// Number of replacements applied per residue or correction pattern, keyed by uppercased source.
+pub(crate) type ConversionCounts = BTreeMap<String, usize>;
+
 impl ConversionStrategy {
-    fn apply(&self, seq: &str) -> String {
+    pub(crate) fn apply(&self, seq: &str) -> String {
+        self.apply_with(seq, |_, _| {})
+    }
+
+    /// Applies the strategy and adds each replacement made to `counts`.
+    pub(crate) fn apply_counted(&self, seq: &str, counts: &mut ConversionCounts) -> String {
+        self.apply_with(seq, |from, occurrences| match counts.get_mut(from) {
+            Some(count) => *count += occurrences,
+            None => {
+                counts.insert(from.to_string(), occurrences);
+            }
+        })
+    }
+
+    fn apply_with(&self, seq: &str, mut on_replace: impl FnMut(&str, usize)) -> String {
         match self {
             Self::Standard { alignment } => {
                 let mut converted = String::with_capacity(seq.len());
                 for residue in seq.chars() {
+                    let residue = residue.to_ascii_uppercase();
                     if let Some(replacement) = standard_replacement(residue, *alignment) {
+                        on_replace(residue.encode_utf8(&mut [0; 4]), 1);
                         converted.push_str(replacement);
                     } else {
-                        converted.push(residue.to_ascii_uppercase());
+                        converted.push(residue);
                     }
                 }
                 converted
@@ -32,6 +55,7 @@ impl ConversionStrategy {
                 for residue in seq.chars() {
                     let residue = residue.to_ascii_uppercase();
                     if let Some(replacement) = map.get(&residue) {
+                        on_replace(residue.encode_utf8(&mut [0; 4]), 1);
                         converted.push_str(replacement);
                     } else {
                         converted.push(residue);
@@ -39,21 +63,20 @@ impl ConversionStrategy {
                 }
                 converted
             }
-            Self::MultiPattern(patterns) => {
-                let mut converted = seq.to_ascii_uppercase();
-                for (from, to) in patterns {
-                    converted = converted.replace(from, to);
-                }
-                converted
-            }
+            Self::Rules(rules) => rules.apply_with(seq, |pattern| on_replace(pattern, 1)),
         }
     }
 }
 
-fn conversion_strategy(
+/// Picks the conversion strategy; ordered `correction_rules` take precedence over a dictionary.
+pub(crate) fn conversion_strategy(
     correction_dictionary: Option<CorrectionDictionary>,
+    correction_rules: Option<CorrectionRules>,
     alignment: bool,
 ) -> ConversionStrategy {
+    if let Some(rules) = correction_rules {
+        return ConversionStrategy::Rules(rules);
+    }
     match correction_dictionary {
         Some(dictionary) => {
             if dictionary.keys().all(|key| key.chars().count() == 1) {
@@ -68,12 +91,7 @@ fn conversion_strategy(
                 }
                 ConversionStrategy::SingleChar(map)
             } else {
-                ConversionStrategy::MultiPattern(
-                    dictionary
-                        .into_iter()
-                        .map(|(from, to)| (from.to_ascii_uppercase(), to.to_ascii_uppercase()))
-                        .collect(),
-                )
+                ConversionStrategy::Rules(CorrectionRules::from_dictionary(&dictionary))
             }
         }
         None => ConversionStrategy::Standard { alignment },
@@ -85,41 +103,124 @@ fn conversion_strategy(
 pub fn build_custom_dictionary(
     additional_dictionary: CorrectionDictionary,
 ) -> CorrectionDictionary {
-    let mut final_dict = standard_conversion_map(false);
-    for (key, value) in additional_dictionary {
-        final_dict.insert(key, value);
-    }
+    overlay_standard_conversions(additional_dictionary, false)
+}
+
+/// Overlays custom entries on the standard conversion map for the selected mode.
+pub(crate) fn overlay_standard_conversions(
+    additional_dictionary: CorrectionDictionary,
+    alignment: bool,
+) -> CorrectionDictionary {
+    let mut final_dict = standard_conversion_map(alignment);
+    final_dict.extend(additional_dictionary);
     final_dict
 }
 
-/// Converts a sequence according to either the default conversions or a custom correction dictionary.
+/// Reads a residue correction dictionary from a JSON or TSV file.
+///
+/// Files ending in `.json` must contain a single object mapping each residue or pattern to its
+/// replacement. Any other file is read as TSV with one `from<TAB>to` pair per line; an empty `to`
+/// column deletes the match. Blank lines, lines starting with `#`, and a leading `from<TAB>to`
+/// header row are skipped.
+///
+/// # Example
+/// ```no_run
+/// use rfasta::clean::{read_correction_file, CleanOptions, InvalidSequenceAction};
+///
+/// let options = CleanOptions {
+///     invalid_sequence_action: InvalidSequenceAction::Convert,
+///     correction_dictionary: Some(read_correction_file("corrections.tsv")?),
+///     ..CleanOptions::default()
+/// };
+/// # Ok::<(), rfasta::RfastaError>(())
+/// ```
+pub fn read_correction_file<P: AsRef<Path>>(path: P) -> Result<CorrectionDictionary, RfastaError> {
+    let path = path.as_ref();
+    let contents = fs::read_to_string(path).map_err(|source| {
+        RfastaError::io(
+            "clean",
+            Some(path),
+            source,
+            "Check that the correction file exists and is readable UTF-8 text.",
+        )
+    })?;
+
+    let is_json = path
+        .extension()
+        .is_some_and(|extension| extension.eq_ignore_ascii_case("json"));
+    if is_json {
+        return serde_json::from_str(&contents).map_err(|error| {
+            RfastaError::invalid_input(
+                "clean",
+                format!("could not read correction file {}: {error}", path.display()),
+                "JSON correction files must be a single object of string-to-string mappings, e.g. {\"X\": \"G\"}.",
+            )
+        });
+    }
+
+    let mut dictionary = CorrectionDictionary::new();
+    for (index, line) in contents.lines().enumerate() {
+        if line.trim().is_empty() || line.starts_with('#') || (index == 0 && line == "from\tto") {
+            continue;
+        }
+        match line.split_once('\t') {
+            Some((from, to)) if !from.is_empty() && !to.contains('\t') => {
+                dictionary.insert(from.to_string(), to.to_string());
+            }
+            _ => {
+                return Err(RfastaError::invalid_input(
+                    "clean",
+                    format!(
+                        "correction file {} line {}: expected `from<TAB>to`, found `{line}`",
+                        path.display(),
+                        index + 1
+                    ),
+                    "Write one tab-separated pair per line; leave the second column empty to delete a residue.",
+                ));
+            }
+        }
+    }
+    Ok(dictionary)
+}
+
+/// Converts a sequence according to the default conversions, a custom correction dictionary, or
+/// ordered correction rules.
 #[cfg_attr(not(feature = "python"), allow(dead_code))]
 pub fn convert_to_valid(
     seq: &str,
     alignment: bool,
     correction_dictionary: Option<CorrectionDictionary>,
+    correction_rules: Option<CorrectionRules>,
 ) -> String {
-    conversion_strategy(correction_dictionary, alignment).apply(seq)
+    conversion_strategy(correction_dictionary, correction_rules, alignment).apply(seq)
 }
 
 /// Validates a protein sequence and returns the first invalid residue when present.
 pub fn check_sequence_is_valid(seq: &str, alignment: bool) -> (bool, char) {
-    for residue in seq.chars() {
-        let residue = residue.to_ascii_uppercase();
-        if !is_valid_residue(residue, alignment) {
-            return (false, residue);
-        }
+    match first_invalid_residue(seq, alignment) {
+        Some((_, residue)) => (false, residue),
+        None => (true, '0'),
     }
-    (true, '0')
+}
+
+/// Returns the 1-based position and uppercased value of the first invalid residue.
+pub(crate) fn first_invalid_residue(seq: &str, alignment: bool) -> Option<(usize, char)> {
+    seq.chars()
+        .map(|residue| residue.to_ascii_uppercase())
+        .enumerate()
+        .find(|(_, residue)| !is_valid_residue(*residue, alignment))
+        .map(|(index, residue)| (index + 1, residue))
 }
 
 /// Converts invalid sequences in place and returns the number of changed records.
+#[cfg_attr(not(feature = "python"), allow(dead_code))]
 pub fn convert_invalid_sequences(
     mut dataset: Vec<FastaRecord>,
     correction_dictionary: Option<CorrectionDictionary>,
+    correction_rules: Option<CorrectionRules>,
     alignment: bool,
 ) -> (Vec<FastaRecord>, usize) {
-    let strategy = conversion_strategy(correction_dictionary, alignment);
+    let strategy = conversion_strategy(correction_dictionary, correction_rules, alignment);
     let mut converted_count = 0;
 
     for record in &mut dataset {
@@ -134,6 +235,7 @@ pub fn convert_invalid_sequences(
 }
 
 /// Removes records that contain invalid residues.
+#[cfg_attr(not(feature = "python"), allow(dead_code))]
 pub fn remove_invalid_sequences(dataset: Vec<FastaRecord>, alignment: bool) -> Vec<FastaRecord> {
     dataset
         .into_iter()
@@ -147,15 +249,24 @@ pub fn fail_on_invalid_sequences(
     alignment: bool,
 ) -> Result<(), RfastaError> {
     for record in sequences {
-        let (is_valid, invalid_char) = check_sequence_is_valid(&record.sequence, alignment);
-        if !is_valid {
-            return Err(RfastaError::InvalidSequence {
-                header: record.header.clone(),
-                invalid_char,
-                alignment,
-                hint: "Use InvalidSequenceAction::Convert, InvalidSequenceAction::ConvertRemove, or InvalidSequenceAction::Remove if you want rfasta to sanitize invalid residues.",
-            });
-        }
+        fail_on_invalid_record(record, alignment)?;
+    }
+    Ok(())
+}
+
+/// Fails when a single record contains an invalid residue.
+pub(crate) fn fail_on_invalid_record(
+    record: &FastaRecord,
+    alignment: bool,
+) -> Result<(), RfastaError> {
+    let (is_valid, invalid_char) = check_sequence_is_valid(&record.sequence, alignment);
+    if !is_valid {
+        return Err(RfastaError::InvalidSequence {
+            header: record.header.clone(),
+            invalid_char,
+            alignment,
+            hint: "Use InvalidSequenceAction::Convert, InvalidSequenceAction::ConvertRemove, or InvalidSequenceAction::Remove if you want rfasta to sanitize invalid residues.",
+        });
     }
     Ok(())
 }
@@ -176,16 +287,16 @@ pub fn convert_records_to_dictionary(
         {
             warning_count += 1;
             if verbose {
-                println!("[WARNING]: Overwriting entry [count = {warning_count}]");
+                eprintln!("[WARNING]: Overwriting entry [count = {warning_count}]");
             }
         }
     }
 
     if verbose {
         if warning_count > 0 {
-            println!("[INFO]: If you want to avoid overwriting duplicate headers, request list-style output.");
+            eprintln!("[INFO]: If you want to avoid overwriting duplicate headers, request list-style output.");
         } else {
-            println!("[INFO]: All processed sequences uniquely added to the returning dictionary");
+            eprintln!("[INFO]: All processed sequences uniquely added to the returning dictionary");
         }
     }
 
@@ -198,16 +309,22 @@ pub fn fail_on_duplicates(dataset: &[FastaRecord]) -> Result<(), RfastaError> {
     for record in dataset {
         let key = (record.header.clone(), record.sequence.clone());
         if !seen.insert(key) {
-            return Err(RfastaError::DuplicateRecord {
-                header: record.header.clone(),
-                hint: "Use DuplicateAction::Remove to keep the first occurrence, or enable unique headers during parsing if duplicates are unexpected.",
-            });
+            return Err(duplicate_record_error(record));
         }
     }
     Ok(())
 }
 
+/// Builds the error reported for an exact duplicate record.
+pub(crate) fn duplicate_record_error(record: &FastaRecord) -> RfastaError {
+    RfastaError::DuplicateRecord {
+        header: record.header.clone(),
+        hint: "Use DuplicateAction::Remove to keep the first occurrence, or enable unique headers during parsing if duplicates are unexpected.",
+    }
+}
+
 /// Removes duplicate records while keeping the first occurrence.
+#[cfg_attr(not(feature = "python"), allow(dead_code))]
 pub fn remove_duplicates(dataset: Vec<FastaRecord>) -> Vec<FastaRecord> {
     let mut seen: HashSet<(String, String)> = HashSet::with_capacity(dataset.len());
     let mut updated = Vec::with_capacity(dataset.len());
@@ -238,16 +355,246 @@ pub fn fail_on_duplicate_sequences(dataset: &[FastaRecord]) -> Result<(), Rfasta
     Ok(())
 }
 
-/// Removes duplicate sequences while keeping the first occurrence.
-pub fn remove_duplicate_sequences(dataset: Vec<FastaRecord>) -> Vec<FastaRecord> {
-    let mut seen = HashSet::with_capacity(dataset.len());
-    let mut updated = Vec::with_capacity(dataset.len());
+/// Similarity groups of a dataset.
+pub(crate) struct SimilarGroups {
+    /// Group of each record, numbered in order of first record.
+    pub(crate) groups: Vec<usize>,
+    /// Number of k-mers that reached the `max_kmer_frequency` cap and were ignored for later
+    /// sequences. When zero, the groups are the same as without a cap.
+    pub(crate) capped_kmers: usize,
+}
 
-    for record in dataset {
-        if seen.insert(record.sequence.clone()) {
-            updated.push(record);
+/// Assigns every record a group so that records with identical sequences, or with a k-mer
+/// similarity of at least `min_similarity`, share a group, directly or through a chain of such
+/// records.
+///
+/// Groups are numbered in order of their first record. See [`visit_kmer_neighbors`] for the
+/// similarity measure, the `max_kmer_frequency` cap, and the cost.
+pub(crate) fn group_similar_sequences(
+    dataset: &[FastaRecord],
+    kmer_size: usize,
+    min_similarity: f64,
+    max_kmer_frequency: usize,
+) -> SimilarGroups {
+    let (distinct, distinct_of) = distinct_sequences(dataset);
+    let mut sets = DisjointSets::new(distinct.len());
+    let capped_kmers = visit_kmer_neighbors(
+        &distinct,
+        kmer_size,
+        max_kmer_frequency,
+        None,
+        |right, neighbors| {
+            for &(left, similarity) in neighbors {
+                if similarity >= min_similarity {
+                    sets.union(left, right);
+                }
+            }
+        },
+    );
+
+    let mut group_of_root = HashMap::new();
+    let groups = distinct_of
+        .iter()
+        .map(|&index| {
+            let next = group_of_root.len();
+            *group_of_root.entry(sets.find(index)).or_insert(next)
+        })
+        .collect();
+    SimilarGroups {
+        groups,
+        capped_kmers,
+    }
+}
+
+/// Returns the highest k-mer similarity between records with different `labels`, with the indices
+/// of the first such pair found, or `None` when no differently labelled records share a k-mer.
+///
+/// This is exact: no k-mer frequency cap applies. Only pairs with different labels are compared,
+/// but the time still grows with the number of such pairs that share a k-mer. Records with
+/// identical sequences must share a label.
+pub(crate) fn max_cross_label_similarity(
+    dataset: &[FastaRecord],
+    labels: &[usize],
+    kmer_size: usize,
+) -> Option<(f64, usize, usize)> {
+    let (distinct, distinct_of) = distinct_sequences(dataset);
+    let mut first_record = vec![usize::MAX; distinct.len()];
+    for (record, &index) in distinct_of.iter().enumerate().rev() {
+        first_record[index] = record;
+    }
+    let distinct_labels: Vec<usize> = first_record.iter().map(|&record| labels[record]).collect();
+    let mut closest: Option<(f64, usize, usize)> = None;
+    visit_kmer_neighbors(
+        &distinct,
+        kmer_size,
+        usize::MAX,
+        Some(&distinct_labels),
+        |right, neighbors| {
+            for &(left, similarity) in neighbors {
+                if closest.is_none_or(|(best, _, _)| similarity > best) {
+                    closest = Some((similarity, first_record[left], first_record[right]));
+                }
+            }
+        },
+    );
+    closest
+}
+
+/// Returns the distinct sequences in order of first appearance and the index of each record's
+/// sequence among them.
+fn distinct_sequences(dataset: &[FastaRecord]) -> (Vec<&str>, Vec<usize>) {
+    let mut index_of: HashMap<&str, usize> = HashMap::with_capacity(dataset.len());
+    let mut distinct = Vec::new();
+    let distinct_of = dataset
+        .iter()
+        .map(|record| {
+            *index_of.entry(&record.sequence).or_insert_with(|| {
+                distinct.push(record.sequence.as_str());
+                distinct.len() - 1
+            })
+        })
+        .collect();
+    (distinct, distinct_of)
+}
+
+/// Calls `visit(right, neighbors)` for every index in `sequences`, where `neighbors` lists each
+/// earlier sequence `left` that shares a k-mer with it as `(left, similarity)`, in `left` order.
+/// With `labels`, only earlier sequences with a different label are listed.
+///
+/// Similarity is the number of distinct k-mers the two sequences share, divided by the number of
+/// distinct k-mers in the sequence with fewer of them, ignoring case. It is 1.0 when one sequence's
+/// k-mers are all found in the other, so fragments score as highly as full-length copies. Sequences
+/// shorter than `kmer_size` have no k-mers and have no neighbors.
+///
+/// A k-mer already found in `max_kmer_frequency` sequences of a label is ignored for every later
+/// sequence. This bounds the work per sequence at its k-mer count times `max_kmer_frequency`,
+/// instead of growing with the number of sequences, at the cost of undercounting shared k-mers that
+/// are very common, such as low-complexity repeats or large families. Similarities are therefore
+/// never overestimated. Returns the number of k-mers that reached the cap.
+fn visit_kmer_neighbors(
+    sequences: &[&str],
+    kmer_size: usize,
+    max_kmer_frequency: usize,
+    labels: Option<&[usize]>,
+    mut visit: impl FnMut(usize, &[(usize, f64)]),
+) -> usize {
+    let label_of = |index: usize| labels.map_or(0, |labels| labels[index]);
+    let mut all_labels: Vec<usize> = labels.map_or_else(|| vec![0], |labels| labels.to_vec());
+    all_labels.sort_unstable();
+    all_labels.dedup();
+
+    // Postings are kept per label so that cross-label queries skip same-label sequences.
+    let mut postings: HashMap<(usize, u64), Vec<usize>> = HashMap::new();
+    let mut kmer_counts: Vec<usize> = Vec::with_capacity(sequences.len());
+    let mut neighbors = Vec::new();
+    let mut capped_kmers = 0;
+    for (right, sequence) in sequences.iter().enumerate() {
+        let own_label = label_of(right);
+        let upper = sequence.to_ascii_uppercase();
+        let kmers: HashSet<u64> = upper
+            .as_bytes()
+            .windows(kmer_size)
+            .map(hash_bytes)
+            .collect();
+        let mut shared: HashMap<usize, usize> = HashMap::new();
+        for kmer in &kmers {
+            for &label in &all_labels {
+                if labels.is_some() && label == own_label {
+                    continue;
+                }
+                match postings.get(&(label, *kmer)) {
+                    Some(lefts) if lefts.len() < max_kmer_frequency => {
+                        for &left in lefts {
+                            *shared.entry(left).or_default() += 1;
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        }
+        neighbors.clear();
+        neighbors.extend(shared.into_iter().map(|(left, count)| {
+            let smaller = kmer_counts[left].min(kmers.len());
+            (left, count as f64 / smaller as f64)
+        }));
+        neighbors.sort_unstable_by_key(|&(left, _)| left);
+        visit(right, &neighbors);
+        kmer_counts.push(kmers.len());
+        for kmer in kmers {
+            let lefts = postings.entry((own_label, kmer)).or_default();
+            if lefts.len() < max_kmer_frequency {
+                lefts.push(right);
+                if lefts.len() == max_kmer_frequency {
+                    capped_kmers += 1;
+                }
+            }
         }
     }
+    capped_kmers
+}
 
-    updated
+/// Union-find over `0..len` where each set is represented by its smallest member.
+struct DisjointSets {
+    parent: Vec<usize>,
+}
+
+impl DisjointSets {
+    fn new(len: usize) -> Self {
+        Self {
+            parent: (0..len).collect(),
+        }
+    }
+
+    fn find(&mut self, mut index: usize) -> usize {
+        while self.parent[index] != index {
+            self.parent[index] = self.parent[self.parent[index]];
+            index = self.parent[index];
+        }
+        index
+    }
+
+    fn union(&mut self, left: usize, right: usize) {
+        let (left, right) = (self.find(left), self.find(right));
+        self.parent[left.max(right)] = left.min(right);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn similar_and_identical_sequences_share_a_group() {
+        let records = vec![
+            FastaRecord::new("a", "MKTAYIAKQRQISFVKSHFSRQ"),
+            FastaRecord::new("b", "WWWWWWWWPPPPPPPP"),
+            FastaRecord::new("c", "mktayiakqrqisfvk"),
+            FastaRecord::new("d", "WWWWWWWWPPPPPPPP"),
+            FastaRecord::new("e", "GGGGHHHHCCCC"),
+        ];
+        let similar = group_similar_sequences(&records, 5, 0.5, usize::MAX);
+        assert_eq!(similar.groups, vec![0, 1, 0, 1, 2]);
+        assert_eq!(similar.capped_kmers, 0);
+
+        let labels = [0, 0, 0, 0, 1];
+        assert_eq!(max_cross_label_similarity(&records, &labels, 5), None);
+        let labels = [0, 1, 1, 1, 0];
+        let (similarity, left, right) = max_cross_label_similarity(&records, &labels, 5).unwrap();
+        assert_eq!((similarity, left, right), (1.0, 0, 2));
+    }
+
+    #[test]
+    fn kmers_past_the_frequency_cap_are_not_compared() {
+        let records = vec![
+            FastaRecord::new("a", "MKTAYIAK"),
+            FastaRecord::new("b", "MKTAYIAKQ"),
+            FastaRecord::new("c", "MKTAYIAKQR"),
+        ];
+        let uncapped = group_similar_sequences(&records, 5, 0.5, usize::MAX);
+        assert_eq!(uncapped.groups, vec![0, 0, 0]);
+        // With a cap of 2, `c` only sees the k-mer it shares with `b` alone.
+        let capped = group_similar_sequences(&records, 5, 0.5, 2);
+        assert_eq!(capped.groups, vec![0, 0, 1]);
+        assert_eq!(capped.capped_kmers, 5);
+    }
 }
@@ -1,6 +1,7 @@
 //! FASTA writing APIs.
 //!
 //! The functions in this module write records to either an arbitrary writer or a filesystem path,
-//! using buffered output and optional line wrapping.
+//! using buffered output, optional line wrapping, and optional gzip, BGZF, or zstd compression.
 
-pub use crate::io::{write_fasta_file, write_fasta_writer, WriteOptions};
+pub use crate::compression::Compression;
+pub use crate::io::{write_fasta_file, write_fasta_writer, FastaWriter, WriteOptions};
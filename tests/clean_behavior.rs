@@ -1,5 +1,15 @@
-use rfasta::clean::{clean_sequences, CleanOptions, DuplicateAction, InvalidSequenceAction};
-use rfasta::parse::FastaRecord;
+mod common;
+
+use std::io::Cursor;
+
+use common::{unique_temp_dir, write_text_file};
+use rfasta::clean::{
+    clean_fasta_file, clean_sequences, clean_sequences_with_report, read_correction_file,
+    visit_cleaned_fasta_reader, CleanOptions, CorrectionRule, CorrectionRules, DuplicateAction,
+    InvalidSequenceAction, RejectedOutput,
+};
+use rfasta::parse::{parse_fasta_file, FastaRecord, ParseOptions};
+use rfasta::write::WriteOptions;
 
 #[test]
 fn duplicate_record_removal_only_drops_exact_duplicate_records() {
@@ -113,3 +123,425 @@ fn alignment_mode_accepts_gap_residues() {
     .unwrap_err();
     assert!(error.to_string().contains("invalid residue"));
 }
+
+#[test]
+fn streaming_clean_matches_batch_clean() {
+    let input = ">seq1\nAAAA\n>seq1\nAAAA\n>seq2\nACDBX\n>seq3\nAAAA\n>seq,4\nMKLL\n";
+    let options = CleanOptions {
+        duplicate_record_action: DuplicateAction::Remove,
+        duplicate_sequence_action: DuplicateAction::Remove,
+        invalid_sequence_action: InvalidSequenceAction::ConvertRemove,
+        remove_comma_from_header: true,
+        ..CleanOptions::default()
+    };
+    let parse_options = ParseOptions {
+        expect_unique_header: false,
+        part: None,
+    };
+
+    let mut streamed = Vec::new();
+    let report = visit_cleaned_fasta_reader(
+        Cursor::new(input),
+        parse_options.clone(),
+        &options,
+        |record| {
+            streamed.push(record);
+            Ok(())
+        },
+    )
+    .unwrap();
+
+    let dir = unique_temp_dir("clean-stream");
+    let path = write_text_file(&dir, "input.fasta", input);
+    let (batch, batch_report) = clean_sequences_with_report(
+        parse_fasta_file(&path, parse_options, false).unwrap(),
+        &options,
+    )
+    .unwrap();
+
+    assert_eq!(report.records_out, batch.len());
+    assert_eq!(streamed, batch);
+    assert_eq!(report, batch_report);
+
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn clean_fasta_file_writes_cleaned_records() {
+    let dir = unique_temp_dir("clean-file");
+    let input = write_text_file(&dir, "input.fasta", ">seq1\nAAAA\n>seq2\nCC\n");
+    let output = dir.join("cleaned.fasta");
+
+    let report = clean_fasta_file(
+        &input,
+        &output,
+        ParseOptions::default(),
+        &CleanOptions {
+            shortest_seq: Some(3),
+            ..CleanOptions::default()
+        },
+        &WriteOptions::default(),
+    )
+    .unwrap();
+
+    assert_eq!(report.records_out, 1);
+    assert_eq!(report.length_filtered, 1);
+    assert_eq!(std::fs::read_to_string(output).unwrap(), ">seq1\nAAAA\n\n");
+
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn clean_fasta_file_rejects_duplicate_sequence_fail_mode() {
+    let dir = unique_temp_dir("clean-file-fail");
+    let input = write_text_file(&dir, "input.fasta", ">seq1\nAAAA\n\n");
+    let error = clean_fasta_file(
+        &input,
+        dir.join("cleaned.fasta"),
+        ParseOptions::default(),
+        &CleanOptions {
+            duplicate_sequence_action: DuplicateAction::Fail,
+            ..CleanOptions::default()
+        },
+        &WriteOptions::default(),
+    )
+    .unwrap_err();
+    assert!(error.to_string().contains("duplicate"));
+
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn clean_report_counts_each_stage() {
+    let records = vec![
+        FastaRecord::new("dup", "AAAA"),
+        FastaRecord::new("dup", "AAAA"),
+        FastaRecord::new("same", "AAAA"),
+        FastaRecord::new("x", "ACXXB"),
+        FastaRecord::new("bad", "AC?D"),
+        FastaRecord::new("short", "AC"),
+        FastaRecord::new("keep1", "MKLV"),
+        FastaRecord::new("keep2", "MKLW"),
+    ];
+    let (cleaned, report) = clean_sequences_with_report(
+        records,
+        &CleanOptions {
+            duplicate_record_action: DuplicateAction::Remove,
+            duplicate_sequence_action: DuplicateAction::Remove,
+            invalid_sequence_action: InvalidSequenceAction::ConvertRemove,
+            shortest_seq: Some(3),
+            random_subsample: Some(2),
+            ..CleanOptions::default()
+        },
+    )
+    .unwrap();
+
+    assert_eq!(cleaned.len(), 2);
+    assert_eq!(report.records_in, 8);
+    assert_eq!(report.duplicate_records_removed, 1);
+    assert_eq!(report.duplicate_sequences_removed, 1);
+    assert_eq!(report.sequences_converted, 1);
+    assert_eq!(report.residue_conversions.get("X"), Some(&2));
+    assert_eq!(report.residue_conversions.get("B"), Some(&1));
+    assert_eq!(report.invalid_removed, 1);
+    assert_eq!(report.length_filtered, 1);
+    assert_eq!(report.subsampled_away, 2);
+    assert_eq!(report.records_out, 2);
+
+    let json = report.to_json();
+    assert!(json.contains("\"duplicate_records_removed\": 1"));
+    assert!(json.contains("\"X\": 2"));
+}
+
+#[test]
+fn rejected_records_are_written_with_reasons() {
+    let dir = unique_temp_dir("clean-rejected");
+    let input = ">seq1\nMKLV\n>seq1\nMKLV\n>seq2\nMKLV\n>seq3\nMK?V\n>seq4\nMK\n>seq5\nMKLVW\n";
+    let batch_tsv = dir.join("batch.tsv");
+    let stream_tsv = dir.join("stream.tsv");
+    let rejected_fasta = dir.join("rejected.fasta");
+    let options = |tsv: &std::path::Path| CleanOptions {
+        duplicate_record_action: DuplicateAction::Remove,
+        duplicate_sequence_action: DuplicateAction::Remove,
+        invalid_sequence_action: InvalidSequenceAction::Remove,
+        shortest_seq: Some(3),
+        rejected: Some(RejectedOutput {
+            fasta: Some(rejected_fasta.clone()),
+            tsv: Some(tsv.to_path_buf()),
+        }),
+        ..CleanOptions::default()
+    };
+    let parse_options = ParseOptions {
+        expect_unique_header: false,
+        part: None,
+    };
+
+    let path = write_text_file(&dir, "input.fasta", input);
+    let records = parse_fasta_file(&path, parse_options.clone(), false).unwrap();
+    let (cleaned, _) = clean_sequences_with_report(records, &options(&batch_tsv)).unwrap();
+    assert_eq!(cleaned.len(), 2);
+    visit_cleaned_fasta_reader(
+        Cursor::new(input),
+        parse_options,
+        &options(&stream_tsv),
+        |_| Ok(()),
+    )
+    .unwrap();
+
+    let expected = "header\tstage\tresidue\tposition\tkept_header\tlength\n\
+                    seq1\tduplicate_record\t\t\tseq1\t4\n\
+                    seq2\tduplicate_sequence\t\t\tseq1\t4\n\
+                    seq3\tinvalid_residue\t?\t3\t\t4\n\
+                    seq4\ttoo_short\t\t\t\t2\n";
+    assert_eq!(std::fs::read_to_string(&batch_tsv).unwrap(), expected);
+    assert_eq!(std::fs::read_to_string(&stream_tsv).unwrap(), expected);
+    let rejected = std::fs::read_to_string(&rejected_fasta).unwrap();
+    assert!(rejected.contains(">seq2 [rejected: duplicate sequence of `seq1`]\nMKLV\n"));
+    assert!(rejected.contains(">seq3 [rejected: invalid residue `?` at position 3]\nMK?V\n"));
+
+    // Tabs and carriage returns inside headers must not add TSV columns or rows.
+    let records = vec![
+        FastaRecord::new("seq1\tcopy\r1", "MKLV"),
+        FastaRecord::new("seq2\tcopy", "MKLV"),
+    ];
+    clean_sequences_with_report(records, &options(&batch_tsv)).unwrap();
+    assert_eq!(
+        std::fs::read_to_string(&batch_tsv).unwrap(),
+        "header\tstage\tresidue\tposition\tkept_header\tlength\n\
+         seq2 copy\tduplicate_sequence\t\t\tseq1 copy 1\t4\n"
+    );
+
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn seeded_random_subsample_output_is_pinned() {
+    let records: Vec<FastaRecord> = (0..10)
+        .map(|index| FastaRecord::new(format!("seq{index}"), "ACDE"))
+        .collect();
+    let cleaned = clean_sequences(
+        records,
+        &CleanOptions {
+            random_subsample: Some(3),
+            seed: Some(42),
+            ..CleanOptions::default()
+        },
+    )
+    .unwrap();
+
+    // Changing these headers breaks reproducibility of published subsamples.
+    let headers: Vec<&str> = cleaned
+        .iter()
+        .map(|record| record.header.as_str())
+        .collect();
+    assert_eq!(headers, vec!["seq5", "seq7", "seq9"]);
+}
+
+#[test]
+fn correction_files_load_from_tsv_and_json() {
+    let dir = unique_temp_dir("clean-corrections");
+    let tsv = write_text_file(
+        &dir,
+        "corrections.tsv",
+        "from\tto\n# drop stop codons\n*\t\nX\tA\n\nb\tN\n",
+    );
+    let json = write_text_file(&dir, "corrections.json", r#"{"X": "A", "*": ""}"#);
+
+    let from_tsv = read_correction_file(&tsv).unwrap();
+    assert_eq!(from_tsv.len(), 3);
+    assert_eq!(from_tsv["*"], "");
+    assert_eq!(from_tsv["X"], "A");
+    assert_eq!(from_tsv["b"], "N");
+
+    let from_json = read_correction_file(&json).unwrap();
+    assert_eq!(from_json["X"], "A");
+    assert_eq!(from_json["*"], "");
+
+    let cleaned = clean_sequences(
+        vec![FastaRecord::new("seq1", "MKX*b")],
+        &CleanOptions {
+            invalid_sequence_action: InvalidSequenceAction::Convert,
+            correction_dictionary: Some(from_tsv),
+            ..CleanOptions::default()
+        },
+    )
+    .unwrap();
+    assert_eq!(cleaned[0].sequence, "MKAN");
+
+    let malformed = write_text_file(&dir, "bad.tsv", "X\tA\nZ Q\n");
+    let error = read_correction_file(&malformed).unwrap_err().to_string();
+    assert!(error.contains("line 2"));
+
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn ordered_correction_rules_are_deterministic_on_both_paths() {
+    let rules = CorrectionRules::new(vec![
+        CorrectionRule::literal("X", "G"),
+        CorrectionRule::literal("XX", "A"),
+        CorrectionRule::literal("B", "D").with_priority(1),
+        CorrectionRule::regex("B+", "N"),
+        CorrectionRule::regex(r"\*+$", ""),
+    ])
+    .unwrap();
+    let options = CleanOptions {
+        invalid_sequence_action: InvalidSequenceAction::Convert,
+        correction_rules: Some(rules),
+        ..CleanOptions::default()
+    };
+    let records = vec![
+        FastaRecord::new("seq1", "MXXXK**"),
+        FastaRecord::new("seq2", "MBBK"),
+    ];
+
+    let (batch, report) = clean_sequences_with_report(records, &options).unwrap();
+    assert_eq!(batch[0].sequence, "MAGK");
+    assert_eq!(batch[1].sequence, "MDDK");
+    assert_eq!(report.residue_conversions["XX"], 1);
+    assert_eq!(report.residue_conversions["X"], 1);
+    assert_eq!(report.residue_conversions["B"], 2);
+    assert_eq!(report.residue_conversions[r"\*+$"], 1);
+
+    let mut streamed = Vec::new();
+    visit_cleaned_fasta_reader(
+        Cursor::new(">seq1\nMXXXK**\n>seq2\nMBBK\n"),
+        ParseOptions::default(),
+        &options,
+        |record| {
+            streamed.push(record);
+            Ok(())
+        },
+    )
+    .unwrap();
+    assert_eq!(streamed, batch);
+}
+
+#[test]
+fn threaded_cleaning_matches_single_threaded_output() {
+    use rfasta::clean::visit_cleaned_fasta_file;
+    use rfasta::parse::FilePart;
+
+    let dir = unique_temp_dir("clean-threads");
+    // About two megabytes, so the input spans several parse chunks.
+    let mut input = String::new();
+    for index in 0..12_000 {
+        // Spell the index in residues so most sequences are distinct.
+        let tag: String = (index / 3)
+            .to_string()
+            .bytes()
+            .map(|digit| char::from(b"ACDEFGHIKL"[usize::from(digit - b'0')]))
+            .collect();
+        let residues = match index % 7 {
+            0 => format!("{}{tag}", "mkvlaagx".repeat(40)),
+            1 => format!("MKV?LA{tag}"),
+            2 => "MK".to_string(),
+            _ => format!("{}W{tag}", "ACDEFGHIKLMNPQRSTVWY".repeat(index % 13 + 1)),
+        };
+        let header = if index % 11 == 0 { 0 } else { index };
+        input.push_str(&format!(">seq{header} sample,{}\n", index % 3));
+        for line in residues.as_bytes().chunks(60 + index % 17) {
+            input.push_str(std::str::from_utf8(line).unwrap());
+            input.push('\n');
+        }
+        if index % 5 == 0 {
+            input.push('\n');
+        }
+    }
+    let path = write_text_file(&dir, "input.fasta", &input);
+
+    let clean = |threads: usize, subsample: Option<usize>, part: Option<FilePart>| {
+        let tsv = dir.join(format!("rejected_{threads}.tsv"));
+        let options = CleanOptions {
+            invalid_sequence_action: InvalidSequenceAction::ConvertRemove,
+            duplicate_record_action: DuplicateAction::Remove,
+            duplicate_sequence_action: DuplicateAction::Remove,
+            shortest_seq: Some(3),
+            remove_comma_from_header: true,
+            random_subsample: subsample,
+            seed: Some(7),
+            rejected: Some(RejectedOutput {
+                fasta: None,
+                tsv: Some(tsv.clone()),
+            }),
+            threads,
+            ..CleanOptions::default()
+        };
+        let parse_options = ParseOptions {
+            expect_unique_header: false,
+            part,
+        };
+        let mut records = Vec::new();
+        let report = visit_cleaned_fasta_file(&path, parse_options, &options, |record| {
+            records.push(record);
+            Ok(())
+        })
+        .unwrap();
+        (records, report, std::fs::read_to_string(tsv).unwrap())
+    };
+
+    let serial = clean(1, None, None);
+    assert!(serial.0.len() > 1000);
+    assert!(serial.1.duplicate_records_removed > 0);
+    assert!(serial.1.sequences_converted > 0);
+    for threads in [2, 3, 0] {
+        assert_eq!(clean(threads, None, None), serial, "{threads} threads");
+    }
+    assert_eq!(clean(4, Some(50), None), clean(1, Some(50), None));
+    let part = Some(FilePart::new(1, 3).unwrap());
+    assert_eq!(clean(4, None, part), clean(1, None, part));
+
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn threaded_cleaning_reports_the_first_error_in_input_order() {
+    let mut input: String = (0..40_000)
+        .map(|index| format!(">seq{index}\nMKVLAAGWCDEFHIKLNPQRSTVY\n"))
+        .collect();
+    // A duplicate header late in the input and an invalid residue after it.
+    input.push_str(">seq12\nMKV\n>bad\nMK?V\n");
+    let clean = |threads: usize| {
+        let mut visited = 0;
+        let error = visit_cleaned_fasta_reader(
+            Cursor::new(input.as_bytes()),
+            ParseOptions::default(),
+            &CleanOptions {
+                threads,
+                ..CleanOptions::default()
+            },
+            |_| {
+                visited += 1;
+                Ok(())
+            },
+        )
+        .unwrap_err();
+        (visited, error.to_string())
+    };
+
+    let serial = clean(1);
+    assert_eq!(serial.0, 40_000);
+    assert!(serial.1.contains("seq12"));
+    assert_eq!(clean(4), serial);
+
+    // Errors with line numbers count lines from the start of the input, not of the chunk.
+    let mut input = input.into_bytes();
+    input.truncate(input.len() - b">seq12\nMKV\n>bad\nMK?V\n".len());
+    input.extend_from_slice(b">bad\xff\nMKV\n");
+    let clean = |threads: usize| {
+        visit_cleaned_fasta_reader(
+            Cursor::new(&input),
+            ParseOptions::default(),
+            &CleanOptions {
+                threads,
+                ..CleanOptions::default()
+            },
+            |_| Ok(()),
+        )
+        .unwrap_err()
+        .to_string()
+    };
+    let serial = clean(1);
+    assert!(serial.contains("line 80001"), "{serial}");
+    assert_eq!(clean(4), serial);
+}
@@ -70,3 +70,800 @@ fn cli_split_writes_round_robin_shards() {
 
     fs::remove_dir_all(dir).unwrap();
 }
+
+#[test]
+fn cli_split_balances_by_bytes() {
+    let dir = common::unique_temp_dir("rfasta_cli_split_balance");
+    let input = common::write_text_file(
+        &dir,
+        "input.fasta",
+        &format!(">big\n{}\n>seq2\nCCCC\n>seq3\nDDDD\n", "A".repeat(500)),
+    );
+    let output_dir = dir.join("shards");
+
+    let output = Command::new(env!("CARGO_BIN_EXE_rfasta"))
+        .args([
+            "split",
+            input.to_str().unwrap(),
+            "--output-dir",
+            output_dir.to_str().unwrap(),
+            "--chunks",
+            "2",
+            "--balance",
+            "bytes",
+            "--silent",
+        ])
+        .output()
+        .expect("run split");
+
+    assert!(output.status.success());
+    let shard1 = fs::read_to_string(output_dir.join("input_000001.fasta")).unwrap();
+    let shard2 = fs::read_to_string(output_dir.join("input_000002.fasta")).unwrap();
+    assert!(shard1.starts_with(">big\n"));
+    assert!(!shard1.contains(">seq"));
+    assert_eq!(shard2, ">seq2\nCCCC\n\n>seq3\nDDDD\n\n");
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn cli_split_by_hash_header_matches_library_placement() {
+    let dir = common::unique_temp_dir("rfasta_cli_split_hash");
+    let input = common::write_text_file(
+        &dir,
+        "input.fasta",
+        ">sp|P69905|HBA_HUMAN\nMVLS\n>seq2\nCCCC\n",
+    );
+    let output_dir = dir.join("shards");
+
+    let output = Command::new(env!("CARGO_BIN_EXE_rfasta"))
+        .args([
+            "split",
+            input.to_str().unwrap(),
+            "--output-dir",
+            output_dir.to_str().unwrap(),
+            "--chunks",
+            "8",
+            "--by",
+            "hash-accession",
+            "--silent",
+        ])
+        .output()
+        .expect("run split");
+
+    assert!(output.status.success());
+    let shard = rfasta::shard::shard_hash("P69905") % 8 + 1;
+    let contents = fs::read_to_string(output_dir.join(format!("input_{shard:06}.fasta"))).unwrap();
+    assert!(contents.contains(">sp|P69905|HBA_HUMAN\n"));
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn cli_split_rolls_over_at_max_bytes() {
+    let dir = common::unique_temp_dir("rfasta_cli_split_rolling");
+    let input = common::write_text_file(
+        &dir,
+        "input.fasta",
+        &format!(">big\n{}\n>seq2\nCCCC\n>seq3\nDDDD\n", "A".repeat(2000)),
+    );
+    let output_dir = dir.join("shards");
+
+    let output = Command::new(env!("CARGO_BIN_EXE_rfasta"))
+        .args([
+            "split",
+            input.to_str().unwrap(),
+            "--output-dir",
+            output_dir.to_str().unwrap(),
+            "--max-bytes",
+            "1K",
+            "--silent",
+        ])
+        .output()
+        .expect("run split");
+
+    assert!(output.status.success());
+    let shard1 = fs::read_to_string(output_dir.join("input_000001.fasta")).unwrap();
+    let shard2 = fs::read_to_string(output_dir.join("input_000002.fasta")).unwrap();
+    assert!(shard1.starts_with(">big\n"));
+    assert_eq!(shard2, ">seq2\nCCCC\n\n>seq3\nDDDD\n\n");
+    assert!(!output_dir.join("input_000003.fasta").exists());
+
+    let missing_limit = Command::new(env!("CARGO_BIN_EXE_rfasta"))
+        .args([
+            "split",
+            input.to_str().unwrap(),
+            "--output-dir",
+            output_dir.to_str().unwrap(),
+        ])
+        .output()
+        .expect("run split");
+    assert!(!missing_limit.status.success());
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn cli_verify_shards_checks_manifest() {
+    let dir = common::unique_temp_dir("rfasta_cli_verify_shards");
+    let input = common::write_text_file(
+        &dir,
+        "input.fasta",
+        ">seq1\nAAAA\n>seq2\nCCCC\n>seq3\nDDDD\n",
+    );
+    let output_dir = dir.join("shards");
+
+    let split = Command::new(env!("CARGO_BIN_EXE_rfasta"))
+        .args([
+            "split",
+            input.to_str().unwrap(),
+            "--output-dir",
+            output_dir.to_str().unwrap(),
+            "--chunks",
+            "2",
+            "--compress",
+            "gzip",
+            "--silent",
+        ])
+        .output()
+        .expect("run split");
+    assert!(split.status.success());
+
+    let verify = |dir: &std::path::Path| {
+        Command::new(env!("CARGO_BIN_EXE_rfasta"))
+            .args(["verify-shards", dir.to_str().unwrap()])
+            .output()
+            .expect("run verify-shards")
+    };
+    let verified = verify(&output_dir);
+    assert!(verified.status.success());
+    assert!(String::from_utf8_lossy(&verified.stderr).contains("Verified 2 shards (3 sequences)"));
+
+    let shard = output_dir.join("input_000002.fasta.gz");
+    let mut bytes = fs::read(&shard).unwrap();
+    bytes.push(0);
+    fs::write(&shard, bytes).unwrap();
+    let corrupted = verify(&output_dir);
+    assert!(!corrupted.status.success());
+    assert!(String::from_utf8_lossy(&corrupted.stderr).contains("input_000002.fasta.gz"));
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn cli_clean_infers_gzip_output_from_extension() {
+    let dir = common::unique_temp_dir("rfasta_cli_clean_gz");
+    let input = common::write_text_file(&dir, "input.fasta", ">seq1\nAAAA\n>seq2\nCCCC\n");
+    let output_path = dir.join("cleaned.fasta.gz");
+
+    let output = Command::new(env!("CARGO_BIN_EXE_rfasta"))
+        .args([
+            "clean",
+            input.to_str().unwrap(),
+            "-o",
+            output_path.to_str().unwrap(),
+            "--silent",
+        ])
+        .output()
+        .expect("run clean");
+
+    assert!(output.status.success());
+    let bytes = fs::read(&output_path).unwrap();
+    assert_eq!(&bytes[..2], &[0x1f, 0x8b]);
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn cli_faidx_and_fetch_retrieve_regions() {
+    let dir = common::unique_temp_dir("rfasta_cli_fetch");
+    let input = common::write_text_file(
+        &dir,
+        "input.fasta",
+        ">seq1 desc\nACDEF\nGHIK\n>seq2\nTTTT\n",
+    );
+
+    let output = Command::new(env!("CARGO_BIN_EXE_rfasta"))
+        .args(["faidx", input.to_str().unwrap(), "--silent"])
+        .output()
+        .expect("run faidx");
+    assert!(output.status.success());
+    assert!(dir.join("input.fasta.fai").exists());
+
+    let output = Command::new(env!("CARGO_BIN_EXE_rfasta"))
+        .args(["fetch", input.to_str().unwrap(), "seq2", "seq1:3-7"])
+        .output()
+        .expect("run fetch");
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert_eq!(stdout, ">seq2\nTTTT\n\n>seq1:3-7\nDEFGH\n\n");
+
+    let output = Command::new(env!("CARGO_BIN_EXE_rfasta"))
+        .args(["fetch", input.to_str().unwrap(), "seq1:6-100"])
+        .output()
+        .expect("run fetch");
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        ">seq1:6-100\nGHIK\n\n"
+    );
+
+    let output = Command::new(env!("CARGO_BIN_EXE_rfasta"))
+        .args(["fetch", input.to_str().unwrap(), "seq1:10-12"])
+        .output()
+        .expect("run fetch");
+    assert!(!output.status.success());
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(
+        stderr.contains("region `seq1:10-12` starts at 10, past the end of `seq1` (9 residues)")
+    );
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn cli_clean_streams_stdin_to_stdout_without_info_lines() {
+    use std::io::Write;
+    use std::process::Stdio;
+
+    let mut child = Command::new(env!("CARGO_BIN_EXE_rfasta"))
+        .args([
+            "clean",
+            "-",
+            "-o",
+            "-",
+            "--invalid-sequence",
+            "convert",
+            "--print-statistics",
+        ])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("run clean");
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(b">seq1\nacdx\n>seq2\nTTTT\n")
+        .unwrap();
+    let output = child.wait_with_output().unwrap();
+
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert_eq!(stdout, ">seq1\nACDG\n\n>seq2\nTTTT\n\n");
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("[INFO]"));
+    assert!(stderr.contains("Total sequences: 2"));
+}
+
+#[test]
+fn cli_split_reads_stdin() {
+    use std::io::Write;
+    use std::process::Stdio;
+
+    let dir = common::unique_temp_dir("rfasta_cli_split_stdin");
+    let output_dir = dir.join("shards");
+
+    let mut child = Command::new(env!("CARGO_BIN_EXE_rfasta"))
+        .args([
+            "split",
+            "-",
+            "--output-dir",
+            output_dir.to_str().unwrap(),
+            "--chunks",
+            "2",
+        ])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .expect("run split");
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(b">seq1\nAAAA\n>seq2\nCCCC\n>seq3\nDDDD\n")
+        .unwrap();
+    let output = child.wait_with_output().unwrap();
+
+    assert!(output.status.success());
+    assert!(output.stdout.is_empty());
+    let shard1 = fs::read_to_string(output_dir.join("stdin_000001.fasta")).unwrap();
+    assert!(shard1.contains(">seq3"));
+    assert!(output_dir.join("stdin_000002.fasta").exists());
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn cli_clean_writes_json_report() {
+    let dir = common::unique_temp_dir("rfasta_cli_report");
+    let input = common::write_text_file(
+        &dir,
+        "input.fasta",
+        ">seq1\nACDX\n>seq1\nACDX\n>seq2\nAC?\n>seq3\nMKLV\n",
+    );
+    let report = dir.join("report.json");
+
+    let output = Command::new(env!("CARGO_BIN_EXE_rfasta"))
+        .args([
+            "clean",
+            input.to_str().unwrap(),
+            "--non-unique-header",
+            "--duplicate-record",
+            "remove",
+            "--invalid-sequence",
+            "convert-remove",
+            "--no-outputfile",
+            "--silent",
+            "--report",
+            report.to_str().unwrap(),
+        ])
+        .output()
+        .expect("run clean");
+
+    assert!(output.status.success());
+    let report = fs::read_to_string(report).unwrap();
+    assert!(report.contains("\"records_in\": 4"));
+    assert!(report.contains("\"duplicate_records_removed\": 1"));
+    assert!(report.contains("\"invalid_removed\": 1"));
+    assert!(report.contains("\"X\": 1"));
+    assert!(report.contains("\"records_out\": 2"));
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn cli_clean_writes_rejected_sidecars() {
+    let dir = common::unique_temp_dir("rfasta_cli_rejected");
+    let input = common::write_text_file(
+        &dir,
+        "input.fasta",
+        ">seq1\nMKLV\n>seq2\nMK\n>seq3\nMKLVWWWW\n",
+    );
+    let rejected = dir.join("rejected.fasta");
+    let rejected_tsv = dir.join("rejected.tsv");
+
+    let output = Command::new(env!("CARGO_BIN_EXE_rfasta"))
+        .args([
+            "clean",
+            input.to_str().unwrap(),
+            "--shortest-seq",
+            "3",
+            "--longest-seq",
+            "6",
+            "--no-outputfile",
+            "--silent",
+            "--rejected",
+            rejected.to_str().unwrap(),
+            "--rejected-tsv",
+            rejected_tsv.to_str().unwrap(),
+        ])
+        .output()
+        .expect("run clean");
+
+    assert!(output.status.success());
+    assert_eq!(
+        fs::read_to_string(rejected).unwrap(),
+        ">seq2 [rejected: shorter than 3 residues]\nMK\n\n>seq3 [rejected: longer than 6 residues]\nMKLVWWWW\n\n"
+    );
+    let rows = fs::read_to_string(rejected_tsv).unwrap();
+    assert!(rows.contains("seq2\ttoo_short\t\t\t\t2\n"));
+    assert!(rows.contains("seq3\ttoo_long\t\t\t\t8\n"));
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn cli_clean_rejects_records_converted_to_empty_sequences() {
+    let dir = common::unique_temp_dir("rfasta_cli_rejected_empty");
+    let input = common::write_text_file(&dir, "input.fasta", ">a\n**\n>b\nACDE\n");
+    let output_file = dir.join("output.fasta");
+    let rejected = dir.join("rejected.fasta");
+
+    let output = Command::new(env!("CARGO_BIN_EXE_rfasta"))
+        .args([
+            "clean",
+            input.to_str().unwrap(),
+            "-o",
+            output_file.to_str().unwrap(),
+            "--invalid-sequence",
+            "convert-remove",
+            "--shortest-seq",
+            "1",
+            "--silent",
+            "--rejected",
+            rejected.to_str().unwrap(),
+        ])
+        .output()
+        .expect("run clean");
+
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert_eq!(fs::read_to_string(output_file).unwrap(), ">b\nACDE\n\n");
+    assert_eq!(
+        fs::read_to_string(rejected).unwrap(),
+        ">a [rejected: shorter than 1 residues]\n\n"
+    );
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn cli_clean_rejects_stdout_for_rejected_sidecars() {
+    let dir = common::unique_temp_dir("rfasta_cli_rejected_stdout");
+    let input = common::write_text_file(&dir, "input.fasta", ">seq1\nMKLV\n");
+
+    for flag in ["--rejected", "--rejected-tsv"] {
+        let output = Command::new(env!("CARGO_BIN_EXE_rfasta"))
+            .current_dir(&dir)
+            .args([
+                "clean",
+                input.to_str().unwrap(),
+                "--no-outputfile",
+                flag,
+                "-",
+            ])
+            .output()
+            .expect("run clean");
+
+        assert!(!output.status.success());
+        let stderr = String::from_utf8(output.stderr).unwrap();
+        assert!(stderr.contains(&format!("{flag} cannot write to standard output")));
+        assert!(!dir.join("-").exists());
+    }
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn cli_clean_seeded_subsample_is_reproducible() {
+    let dir = common::unique_temp_dir("rfasta_cli_seed");
+    let records: String = (0..20)
+        .map(|index| format!(">seq{index}\nMKLV{}\n", "W".repeat(index)))
+        .collect();
+    let input = common::write_text_file(&dir, "input.fasta", &records);
+
+    let run = || {
+        let output = Command::new(env!("CARGO_BIN_EXE_rfasta"))
+            .args([
+                "clean",
+                input.to_str().unwrap(),
+                "-o",
+                "-",
+                "--random-subsample",
+                "4",
+                "--seed",
+                "7",
+                "--silent",
+            ])
+            .output()
+            .expect("run clean");
+        assert!(output.status.success());
+        String::from_utf8(output.stdout).unwrap()
+    };
+
+    let first = run();
+    assert_eq!(first.matches('>').count(), 4);
+    assert_eq!(first, run());
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn cli_clean_applies_alignment_and_correction_file() {
+    let dir = common::unique_temp_dir("rfasta_cli_corrections");
+    let input = common::write_text_file(&dir, "input.fasta", ">seq1\nMK-JX*\n");
+    let corrections = common::write_text_file(&dir, "corrections.tsv", "J\tL\n");
+
+    let run = |extra: &[&str]| {
+        let output = Command::new(env!("CARGO_BIN_EXE_rfasta"))
+            .args([
+                "clean",
+                input.to_str().unwrap(),
+                "-o",
+                "-",
+                "--silent",
+                "--alignment",
+                "--invalid-sequence",
+                "convert-ignore",
+                "--correction-file",
+                corrections.to_str().unwrap(),
+            ])
+            .args(extra)
+            .output()
+            .expect("run clean");
+        assert!(output.status.success());
+        String::from_utf8(output.stdout).unwrap()
+    };
+
+    assert_eq!(run(&[]), ">seq1\nMK-LX*\n\n");
+    assert_eq!(run(&["--overlay-standard"]), ">seq1\nMK-LG\n\n");
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn cli_partition_reports_counts_and_is_reproducible() {
+    let dir = common::unique_temp_dir("rfasta_cli_partition");
+    let input: String = (0..200)
+        .map(|index| format!(">seq{index}\nMKTAYIAKQR\n"))
+        .collect();
+    let input = common::write_text_file(&dir, "input.fasta", &input);
+
+    let partition = |name: &str| {
+        let output_dir = dir.join(name);
+        let output = Command::new(env!("CARGO_BIN_EXE_rfasta"))
+            .args([
+                "partition",
+                input.to_str().unwrap(),
+                "--output-dir",
+                output_dir.to_str().unwrap(),
+                "--ratios",
+                "0.8,0.1,0.1",
+                "--seed",
+                "7",
+            ])
+            .output()
+            .expect("run partition");
+        assert!(output.status.success());
+        let stderr = String::from_utf8_lossy(&output.stderr).into_owned();
+        assert!(stderr.contains("[INFO]: train: "));
+        assert!(stderr.contains("[INFO]: test: "));
+        ["train", "val", "test"].map(|split| {
+            fs::read_to_string(output_dir.join(format!("input_{split}.fasta"))).unwrap()
+        })
+    };
+    let first = partition("first");
+    assert_eq!(first, partition("second"));
+    let records: usize = first.iter().map(|text| text.matches('>').count()).sum();
+    assert_eq!(records, 200);
+
+    let mismatched = Command::new(env!("CARGO_BIN_EXE_rfasta"))
+        .args([
+            "partition",
+            input.to_str().unwrap(),
+            "--output-dir",
+            dir.join("bad").to_str().unwrap(),
+            "--ratios",
+            "0.5,0.3,0.2",
+            "--names",
+            "a,b",
+            "--seed",
+            "1",
+        ])
+        .output()
+        .expect("run partition");
+    assert!(!mismatched.status.success());
+    assert!(String::from_utf8_lossy(&mismatched.stderr).contains("2 names were given for 3 ratios"));
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn cli_partition_by_similarity_reports_leakage() {
+    let dir = common::unique_temp_dir("rfasta_cli_partition_similarity");
+    let input = common::write_text_file(
+        &dir,
+        "input.fasta",
+        ">a\nMKTAYIAKQRQISFVKSHFSRQ\n>b\nMKTAYIAKQRQISFVKSHFSRW\n>c\nWWWWPPPPGGGGHHHH\n>d\nCCCCDDDDEEEEFFFF\n",
+    );
+    let output_dir = dir.join("splits");
+
+    let output = Command::new(env!("CARGO_BIN_EXE_rfasta"))
+        .args([
+            "partition",
+            input.to_str().unwrap(),
+            "--output-dir",
+            output_dir.to_str().unwrap(),
+            "--ratios",
+            "1,1",
+            "--seed",
+            "1",
+            "--min-similarity",
+            "0.5",
+        ])
+        .output()
+        .expect("run partition");
+    assert!(output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("Assigned 3 similarity groups (largest: 2 sequences)"));
+    assert!(stderr.contains("No sequences in different partitions share a k-mer"));
+    let train = fs::read_to_string(output_dir.join("input_train.fasta")).unwrap();
+    assert!(train.contains(">a\n") && train.contains(">b\n"));
+    let manifest = fs::read_to_string(output_dir.join("manifest.json")).unwrap();
+    assert!(manifest.contains("\"kind\": \"grouped\""));
+    assert!(manifest.contains("\"max_cross_similarity\": 0.0"));
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn cli_split_by_key_groups_by_uniprot_field() {
+    let dir = common::unique_temp_dir("rfasta_cli_split_by_key");
+    let input = common::write_text_file(
+        &dir,
+        "input.fasta",
+        concat!(
+            ">sp|P1|A_HUMAN A OS=Homo sapiens OX=9606\nAAAA\n",
+            ">sp|P2|B_MOUSE B OS=Mus musculus OX=10090\nCCCC\n",
+            ">sp|P3|C_HUMAN C OS=Homo sapiens OX=9606\nDDDD\n",
+        ),
+    );
+    let output_dir = dir.join("by_taxon");
+
+    let output = Command::new(env!("CARGO_BIN_EXE_rfasta"))
+        .args([
+            "split",
+            input.to_str().unwrap(),
+            "--output-dir",
+            output_dir.to_str().unwrap(),
+            "--by-key",
+            "field:OX",
+        ])
+        .output()
+        .expect("run split");
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("into 2 groups"));
+    let human = fs::read_to_string(output_dir.join("input_9606.fasta")).unwrap();
+    assert_eq!(human.matches('>').count(), 2);
+    let table = fs::read_to_string(output_dir.join("groups.tsv")).unwrap();
+    assert!(table.contains("10090\tinput_10090.fasta\t1\n"));
+
+    let conflicting = Command::new(env!("CARGO_BIN_EXE_rfasta"))
+        .args([
+            "split",
+            input.to_str().unwrap(),
+            "--output-dir",
+            output_dir.to_str().unwrap(),
+            "--by-key",
+            "field:OX",
+            "--chunks",
+            "2",
+        ])
+        .output()
+        .expect("run split");
+    assert!(!conflicting.status.success());
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn cli_explode_then_gather_restores_the_input() {
+    let dir = common::unique_temp_dir("rfasta_cli_explode");
+    let input = common::write_text_file(
+        &dir,
+        "input.fasta",
+        ">sp|P1|A a\nAAAA\n>sp|P2|B b\nCCCC\n>sp|P3|C c\nDDDD\n",
+    );
+    let exploded = dir.join("exploded");
+
+    let explode = Command::new(env!("CARGO_BIN_EXE_rfasta"))
+        .args([
+            "explode",
+            input.to_str().unwrap(),
+            "--output-dir",
+            exploded.to_str().unwrap(),
+            "--compress",
+            "gzip",
+            "--silent",
+        ])
+        .output()
+        .expect("run explode");
+    assert!(explode.status.success());
+    assert!(exploded.join("P2.fasta.gz").exists());
+
+    let gather = Command::new(env!("CARGO_BIN_EXE_rfasta"))
+        .args(["gather", exploded.to_str().unwrap(), "-o", "-", "--silent"])
+        .output()
+        .expect("run gather");
+    assert!(gather.status.success());
+    assert_eq!(
+        String::from_utf8(gather.stdout).unwrap(),
+        ">sp|P1|A a\nAAAA\n\n>sp|P2|B b\nCCCC\n\n>sp|P3|C c\nDDDD\n\n"
+    );
+
+    let stdin_fan_out = Command::new(env!("CARGO_BIN_EXE_rfasta"))
+        .args([
+            "explode",
+            "-",
+            "--output-dir",
+            dir.join("stdin").to_str().unwrap(),
+            "--max-per-dir",
+            "10",
+        ])
+        .output()
+        .expect("run explode");
+    assert!(!stdin_fan_out.status.success());
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn cli_unsplit_interleaves_shards_in_original_order() {
+    let dir = common::unique_temp_dir("rfasta_cli_unsplit");
+    let input = common::write_text_file(
+        &dir,
+        "input.fasta",
+        ">a\nAAAA\n>b\nCCCC\n>c\nDDDD\n>d\nEEEE\n>e\nFFFF\n",
+    );
+    let shards = dir.join("shards");
+    let by_residues = dir.join("by_residues");
+    for (output_dir, balance) in [(&shards, "records"), (&by_residues, "residues")] {
+        let split = Command::new(env!("CARGO_BIN_EXE_rfasta"))
+            .args([
+                "split",
+                input.to_str().unwrap(),
+                "--output-dir",
+                output_dir.to_str().unwrap(),
+                "--chunks",
+                "2",
+                "--balance",
+                balance,
+                "--silent",
+            ])
+            .output()
+            .expect("run split");
+        assert!(split.status.success());
+    }
+
+    let unsplit = Command::new(env!("CARGO_BIN_EXE_rfasta"))
+        .args(["unsplit", shards.to_str().unwrap(), "-o", "-", "--silent"])
+        .output()
+        .expect("run unsplit");
+    assert!(unsplit.status.success());
+    assert_eq!(
+        String::from_utf8(unsplit.stdout).unwrap(),
+        ">a\nAAAA\n\n>b\nCCCC\n\n>c\nDDDD\n\n>d\nEEEE\n\n>e\nFFFF\n\n"
+    );
+
+    let not_round_robin = Command::new(env!("CARGO_BIN_EXE_rfasta"))
+        .args(["unsplit", by_residues.to_str().unwrap(), "-o", "-"])
+        .output()
+        .expect("run unsplit");
+    assert!(!not_round_robin.status.success());
+    assert!(String::from_utf8(not_round_robin.stderr)
+        .unwrap()
+        .contains("was not split round-robin"));
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn cli_clean_parts_concatenate_to_the_full_output() {
+    let dir = common::unique_temp_dir("rfasta_cli_parts");
+    let input: String = (0..12)
+        .map(|index| format!(">seq{index}\n{}\n", "acdefghik".repeat(index + 1)))
+        .collect();
+    let input = common::write_text_file(&dir, "input.fasta", &input);
+    let clean = |part: Option<&str>| {
+        let mut command = Command::new(env!("CARGO_BIN_EXE_rfasta"));
+        command.args(["clean", input.to_str().unwrap(), "-o", "-", "--silent"]);
+        if let Some(part) = part {
+            command.args(["--part", part]);
+        }
+        command.output().expect("run clean")
+    };
+
+    let full = clean(None);
+    assert!(full.status.success());
+    let mut parts = Vec::new();
+    for part in ["0/3", "1/3", "2/3"] {
+        let output = clean(Some(part));
+        assert!(output.status.success());
+        assert!(!output.stdout.is_empty());
+        parts.extend(output.stdout);
+    }
+    assert_eq!(parts, full.stdout);
+
+    let out_of_range = clean(Some("3/3"));
+    assert!(!out_of_range.status.success());
+    assert!(String::from_utf8(out_of_range.stderr)
+        .unwrap()
+        .contains("needs 0 <= I < N"));
+
+    fs::remove_dir_all(dir).unwrap();
+}
@@ -0,0 +1,159 @@
+mod common;
+
+use std::fs;
+
+use rfasta::index::{fai_path, index_fasta_file, FastaIndex, IndexedFasta};
+use rfasta::parse::FastaRecord;
+use rfasta::write::{write_fasta_file, WriteOptions};
+use rfasta::RfastaError;
+
+#[test]
+fn index_matches_samtools_layout_for_wrapped_records() {
+    let dir = common::unique_temp_dir("rfasta_index_layout");
+    let input = common::write_text_file(
+        &dir,
+        "input.fasta",
+        ">seq1 first protein\nACDEF\nGHIK\n>seq2\nLMNPQ\nRSTVW\nY\n",
+    );
+
+    let index = index_fasta_file(&input, false).unwrap();
+    assert_eq!(index.len(), 2);
+    let fai = fs::read_to_string(fai_path(&input)).unwrap();
+    assert_eq!(fai, "seq1\t9\t20\t5\t6\nseq2\t11\t37\t5\t6\n");
+    assert_eq!(FastaIndex::read(fai_path(&input)).unwrap(), index);
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn fetch_returns_records_and_ranges_across_line_breaks() {
+    let dir = common::unique_temp_dir("rfasta_index_fetch");
+    let input = dir.join("input.fasta");
+    write_fasta_file(
+        &[
+            FastaRecord::new("seq1", "ACDEFGHIKLMNPQRSTVWY"),
+            FastaRecord::new("seq2", "TTTT"),
+        ],
+        &input,
+        WriteOptions {
+            line_length: Some(6),
+            ..WriteOptions::default()
+        },
+        false,
+    )
+    .unwrap();
+    index_fasta_file(&input, false).unwrap();
+
+    let mut fasta = IndexedFasta::open(&input).unwrap();
+    assert_eq!(
+        fasta.fetch("seq1").unwrap(),
+        FastaRecord::new("seq1", "ACDEFGHIKLMNPQRSTVWY")
+    );
+    assert_eq!(fasta.fetch_range("seq1", 4, 14).unwrap(), "FGHIKLMNPQ");
+    assert_eq!(fasta.fetch("seq2").unwrap().sequence, "TTTT");
+
+    let error = fasta.fetch_range("seq2", 2, 5).unwrap_err();
+    assert!(error.to_string().contains("length 4"));
+    let error = fasta.fetch("missing").unwrap_err();
+    assert!(error.to_string().contains("help:"));
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn index_rejects_ragged_records_and_compressed_input() {
+    let dir = common::unique_temp_dir("rfasta_index_errors");
+    let ragged = common::write_text_file(&dir, "ragged.fasta", ">seq1\nACDE\nACDEF\n");
+    let error = FastaIndex::build(&ragged).unwrap_err();
+    assert!(matches!(error, RfastaError::UnindexableRecord { .. }));
+    assert!(error.to_string().contains("seq1"));
+    assert!(error.to_string().contains("help:"));
+
+    let compressed = dir.join("input.fasta.gz");
+    fs::write(&compressed, [0x1f, 0x8b, 0x08, 0x00]).unwrap();
+    let error = FastaIndex::build(&compressed).unwrap_err();
+    assert!(error.to_string().contains("compressed"));
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn mmap_records_match_the_parser() {
+    use rfasta::index::MmapFasta;
+    use rfasta::parse::{parse_fasta_file, ParseOptions};
+
+    let dir = common::unique_temp_dir("rfasta_index_mmap");
+    let input = common::write_text_file(
+        &dir,
+        "input.fasta",
+        "\n>seq1 first\r\nacdef\r\nGHIK\r\n\n  >seq2\nLMN PQ\n\tRS\u{a0}\n>seq1 again\nW",
+    );
+    let parsed = parse_fasta_file(
+        &input,
+        ParseOptions {
+            expect_unique_header: false,
+            ..ParseOptions::default()
+        },
+        false,
+    )
+    .unwrap();
+
+    let fasta = MmapFasta::open(&input).unwrap();
+    assert_eq!(fasta.len().unwrap(), 3);
+    let owned: Vec<FastaRecord> = fasta.records().unwrap().map(|r| r.to_owned()).collect();
+    assert_eq!(owned, parsed);
+
+    let second = fasta.get(1).unwrap().unwrap();
+    assert_eq!(second.header(), "seq2");
+    assert_eq!(second.lines().collect::<Vec<_>>(), [&b"LMN PQ"[..], b"RS"]);
+    assert_eq!(second.len(), 8);
+    assert_eq!(
+        fasta
+            .get(0)
+            .unwrap()
+            .unwrap()
+            .residues()
+            .collect::<Vec<u8>>(),
+        b"ACDEFGHIK"
+    );
+    assert!(fasta.get(3).unwrap().is_none());
+
+    let empty = common::write_text_file(&dir, "empty.fasta", "");
+    assert!(MmapFasta::open(&empty).unwrap().is_empty().unwrap());
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn mmap_scan_reports_malformed_and_compressed_input() {
+    use rfasta::index::MmapFasta;
+
+    let dir = common::unique_temp_dir("rfasta_index_mmap_errors");
+    for (name, text, expected) in [
+        ("headless.fasta", "\nACDE\n>seq1\nA\n", "line 2"),
+        (
+            "empty_record.fasta",
+            ">seq1\nA\n>seq2\n\n>seq3\nC\n",
+            "seq2",
+        ),
+        ("empty_last.fasta", ">seq1\nA\n>seq2", "seq2"),
+    ] {
+        let path = common::write_text_file(&dir, name, text);
+        // Opening only maps the file; problems surface on first use.
+        let fasta = MmapFasta::open(&path).unwrap();
+        let error = fasta.get(0).unwrap_err().to_string();
+        assert!(error.contains(expected), "{name}: {error}");
+    }
+
+    let invalid = dir.join("invalid.fasta");
+    fs::write(&invalid, b">seq1\nAC\n>seq2\nA\xffC\n").unwrap();
+    let error = MmapFasta::open(&invalid).unwrap().len().unwrap_err();
+    assert!(error.to_string().contains("line 4"), "{error}");
+
+    let compressed = dir.join("input.fasta.gz");
+    fs::write(&compressed, [0x1f, 0x8b, 0x08, 0x00]).unwrap();
+    let error = MmapFasta::open(&compressed).err().unwrap();
+    assert!(error.to_string().contains("compressed"));
+
+    fs::remove_dir_all(dir).unwrap();
+}
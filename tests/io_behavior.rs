@@ -4,7 +4,7 @@ use std::fs;
 use std::io::Cursor;
 
 use rfasta::parse::{parse_fasta_file, parse_fasta_reader, FastaRecord, ParseOptions};
-use rfasta::shard::split_fasta_file_round_robin;
+use rfasta::shard::{split_fasta_file_round_robin, SplitOptions};
 use rfasta::write::{write_fasta_file, write_fasta_writer, WriteOptions};
 
 #[test]
@@ -43,6 +43,7 @@ fn write_writer_wraps_output_lines() {
         &WriteOptions {
             line_length: Some(4),
             append: false,
+            ..WriteOptions::default()
         },
     )
     .unwrap();
@@ -83,3 +84,1080 @@ fn write_file_rejects_empty_sequence() {
     assert!(error.to_string().contains("empty"));
     fs::remove_dir_all(dir).unwrap();
 }
+
+#[test]
+fn parse_file_decompresses_multi_member_gzip_zstd_and_xz_inputs() {
+    use std::io::Write;
+
+    let dir = common::unique_temp_dir("rfasta_io_compressed");
+
+    let mut bgzip = Vec::new();
+    for member in [">seq1\nacde\n", ">seq2\nTTTT\n"] {
+        let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(member.as_bytes()).unwrap();
+        bgzip.extend(encoder.finish().unwrap());
+    }
+    let zstd = zstd::encode_all(&b">seq1\nacde\n>seq2\nTTTT\n"[..], 3).unwrap();
+    let mut xz = xz2::write::XzEncoder::new(Vec::new(), 6);
+    xz.write_all(b">seq1\nacde\n>seq2\nTTTT\n").unwrap();
+    let xz = xz.finish().unwrap();
+
+    // Extensions are deliberately misleading: detection relies on magic bytes.
+    for (name, bytes) in [
+        ("bgzip.fasta", bgzip),
+        ("zstd.fasta.gz", zstd),
+        ("xz.fa", xz),
+    ] {
+        let path = dir.join(name);
+        fs::write(&path, bytes).unwrap();
+        let records = parse_fasta_file(&path, ParseOptions::default(), false).unwrap();
+        assert_eq!(
+            records,
+            vec![
+                FastaRecord::new("seq1", "ACDE"),
+                FastaRecord::new("seq2", "TTTT"),
+            ],
+            "failed to decode {name}"
+        );
+    }
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn parse_file_reports_path_for_corrupt_compressed_input() {
+    let dir = common::unique_temp_dir("rfasta_io_corrupt");
+    let path = dir.join("broken.fasta.gz");
+    fs::write(&path, [0x1f, 0x8b, 0x08, 0x00, 0xff, 0xff]).unwrap();
+
+    let error = parse_fasta_file(&path, ParseOptions::default(), false).unwrap_err();
+    assert!(matches!(error, rfasta::RfastaError::Io { .. }));
+    assert!(error.to_string().contains("broken.fasta.gz"));
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn split_file_round_robin_reads_gzip_input() {
+    use std::io::Write;
+
+    let dir = common::unique_temp_dir("rfasta_io_split_gz");
+    let input = dir.join("input.fasta.gz");
+    let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+    encoder
+        .write_all(b">seq1\nAAAA\n>seq2\nCCCC\n>seq3\nDDDD\n")
+        .unwrap();
+    fs::write(&input, encoder.finish().unwrap()).unwrap();
+    let output_dir = dir.join("shards");
+
+    let written = split_fasta_file_round_robin(&input, &output_dir, 2, Some(60), false).unwrap();
+    assert_eq!(written, 2);
+    let shard1 = fs::read_to_string(output_dir.join("input_000001.fasta")).unwrap();
+    assert!(shard1.contains(">seq1"));
+    assert!(shard1.contains(">seq3"));
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn write_file_compresses_output_and_round_trips_through_parser() {
+    use rfasta::write::Compression;
+
+    let dir = common::unique_temp_dir("rfasta_io_write_compressed");
+    let records = vec![
+        FastaRecord::new("seq1", "ACDEFGHIK"),
+        FastaRecord::new("seq2", "TTTT"),
+    ];
+
+    for (name, compression, magic) in [
+        ("out.fasta.gz", Compression::Gzip, &[0x1f, 0x8b][..]),
+        (
+            "out.fasta.bgz",
+            Compression::Bgzf,
+            &[0x1f, 0x8b, 0x08, 0x04][..],
+        ),
+        (
+            "out.fasta.zst",
+            Compression::Zstd,
+            &[0x28, 0xb5, 0x2f, 0xfd][..],
+        ),
+    ] {
+        let output = dir.join(name);
+        write_fasta_file(
+            &records,
+            &output,
+            WriteOptions {
+                compression,
+                ..WriteOptions::default()
+            },
+            false,
+        )
+        .unwrap();
+        assert!(fs::read(&output).unwrap().starts_with(magic));
+        let parsed = parse_fasta_file(&output, ParseOptions::default(), false).unwrap();
+        assert_eq!(parsed, records, "round trip failed for {name}");
+    }
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn split_with_compression_writes_compressed_shard_extensions() {
+    use rfasta::shard::split_fasta_file_round_robin_with_options;
+    use rfasta::write::Compression;
+
+    let dir = common::unique_temp_dir("rfasta_io_split_compressed");
+    let input = common::write_text_file(
+        &dir,
+        "input.fasta",
+        ">seq1\nAAAA\n>seq2\nCCCC\n>seq3\nDDDD\n",
+    );
+    let output_dir = dir.join("shards");
+
+    let written = split_fasta_file_round_robin_with_options(
+        input,
+        &output_dir,
+        2,
+        &SplitOptions::from(WriteOptions {
+            compression: Compression::Zstd,
+            ..WriteOptions::default()
+        }),
+        false,
+    )
+    .unwrap();
+    assert_eq!(written, 2);
+    let shard1 = parse_fasta_file(
+        output_dir.join("input_000001.fasta.zst"),
+        ParseOptions::default(),
+        false,
+    )
+    .unwrap();
+    assert_eq!(shard1.len(), 2);
+    assert_eq!(shard1[1].header, "seq3");
+    assert!(output_dir.join("input_000002.fasta.zst").exists());
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn split_balanced_by_residues_evens_out_shard_lengths() {
+    use rfasta::shard::{split_fasta_file_balanced, ShardBalance};
+
+    let dir = common::unique_temp_dir("rfasta_io_split_balanced");
+    let long = "A".repeat(300);
+    let input = common::write_text_file(
+        &dir,
+        "input.fasta",
+        &format!(">long1\n{long}\n>short1\nCC\n>short2\nCC\n>short3\nCC\n>long2\n{long}\n"),
+    );
+    let output_dir = dir.join("shards");
+
+    let written = split_fasta_file_balanced(
+        &input,
+        &output_dir,
+        2,
+        ShardBalance::Residues,
+        &SplitOptions::default(),
+        false,
+    )
+    .unwrap();
+    assert_eq!(written, 2);
+    let headers = |name: &str| {
+        parse_fasta_file(output_dir.join(name), ParseOptions::default(), false)
+            .unwrap()
+            .into_iter()
+            .map(|record| record.header)
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(headers("input_000001.fasta"), vec!["long1"]);
+    assert_eq!(
+        headers("input_000002.fasta"),
+        vec!["short1", "short2", "short3", "long2"]
+    );
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn split_hashed_by_sequence_is_order_independent() {
+    use rfasta::shard::{shard_hash, split_fasta_file_hashed, ShardKey};
+
+    let dir = common::unique_temp_dir("rfasta_io_split_hashed");
+    let records = [
+        ("seq1", "MKTAYIAKQR"),
+        ("seq2", "ACDEFGHIK"),
+        ("seq3", "MKTAYIAKQR"),
+        ("seq4", "WWWWYYYY"),
+        ("seq5", "PPPPGGGG"),
+    ];
+    let forward: String = records
+        .iter()
+        .map(|(header, sequence)| format!(">{header}\n{sequence}\n"))
+        .collect();
+    let reversed: String = records
+        .iter()
+        .rev()
+        .map(|(header, sequence)| format!(">{header}\n{sequence}\n"))
+        .collect();
+    let forward = common::write_text_file(&dir, "forward.fasta", &forward);
+    let reversed = common::write_text_file(&dir, "reversed.fasta", &reversed);
+
+    let shard_of = |input: &std::path::Path, name: &str| {
+        let output_dir = dir.join(name);
+        split_fasta_file_hashed(
+            input,
+            &output_dir,
+            4,
+            ShardKey::Sequence,
+            &SplitOptions::default(),
+            false,
+        )
+        .unwrap();
+        let mut placement = std::collections::BTreeMap::new();
+        for entry in fs::read_dir(&output_dir).unwrap() {
+            let path = entry.unwrap().path();
+            if path
+                .extension()
+                .is_some_and(|extension| extension == "json")
+            {
+                continue;
+            }
+            let shard = path.file_name().unwrap().to_str().unwrap()[name.len() + 1..].to_string();
+            for record in parse_fasta_file(&path, ParseOptions::default(), false).unwrap() {
+                placement.insert(record.header, shard.clone());
+            }
+        }
+        placement
+    };
+    let forward_placement = shard_of(&forward, "forward");
+    let reversed_placement = shard_of(&reversed, "reversed");
+
+    assert_eq!(forward_placement.len(), 5);
+    assert_eq!(forward_placement, reversed_placement);
+    assert_eq!(forward_placement["seq1"], forward_placement["seq3"]);
+    let expected = shard_hash("MKTAYIAKQR") % 4 + 1;
+    assert_eq!(forward_placement["seq1"], format!("{expected:06}.fasta"));
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn split_rolling_starts_new_shards_at_record_and_byte_limits() {
+    use rfasta::shard::{split_fasta_file_rolling, ShardLimits};
+
+    let dir = common::unique_temp_dir("rfasta_io_split_rolling");
+    let input = common::write_text_file(
+        &dir,
+        "input.fasta",
+        ">seq1\nAAAA\n>seq2\nCCCC\n>seq3\nDDDD\n>seq4\nEEEE\n>seq5\nFFFF\n",
+    );
+
+    let by_records = dir.join("records");
+    let written = split_fasta_file_rolling(
+        &input,
+        &by_records,
+        &ShardLimits {
+            max_records: Some(2),
+            max_bytes: None,
+        },
+        &SplitOptions::default(),
+        false,
+    )
+    .unwrap();
+    assert_eq!(written, 3);
+    assert_eq!(
+        fs::read_to_string(by_records.join("input_000003.fasta")).unwrap(),
+        ">seq5\nFFFF\n\n"
+    );
+
+    // Each record is 12 bytes, so a 40-byte limit fits three per shard.
+    let by_bytes = dir.join("bytes");
+    let written = split_fasta_file_rolling(
+        &input,
+        &by_bytes,
+        &ShardLimits {
+            max_records: None,
+            max_bytes: Some(40),
+        },
+        &SplitOptions::default(),
+        false,
+    )
+    .unwrap();
+    assert_eq!(written, 2);
+    assert_eq!(
+        fs::metadata(by_bytes.join("input_000001.fasta"))
+            .unwrap()
+            .len(),
+        36
+    );
+    assert_eq!(
+        fs::read_to_string(by_bytes.join("input_000002.fasta")).unwrap(),
+        ">seq4\nEEEE\n\n>seq5\nFFFF\n\n"
+    );
+
+    let error = split_fasta_file_rolling(
+        &input,
+        dir.join("none"),
+        &ShardLimits::default(),
+        &SplitOptions::default(),
+        false,
+    )
+    .unwrap_err();
+    assert!(error.to_string().contains("record or byte limit"));
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn split_writes_manifest_that_verifies_until_a_shard_changes() {
+    use rfasta::shard::{
+        read_shard_manifest, split_fasta_file_round_robin, verify_shards, ShardBalance,
+        ShardStrategy, MANIFEST_FILE_NAME,
+    };
+
+    let dir = common::unique_temp_dir("rfasta_io_split_manifest");
+    let input = common::write_text_file(
+        &dir,
+        "input.fasta",
+        ">seq1\nAAAA\n>seq2\nCC\n>seq3\nDDDDDD\n",
+    );
+    let output_dir = dir.join("shards");
+
+    split_fasta_file_round_robin(&input, &output_dir, 2, Some(60), false).unwrap();
+    assert!(output_dir.join(MANIFEST_FILE_NAME).exists());
+
+    let manifest = read_shard_manifest(&output_dir).unwrap();
+    assert_eq!(manifest.input.as_deref(), input.to_str());
+    assert_eq!(
+        manifest.strategy,
+        ShardStrategy::Balanced {
+            chunks: 2,
+            balance: ShardBalance::Records,
+        }
+    );
+    assert_eq!(manifest.line_length, Some(60));
+    assert_eq!(manifest.shards.len(), 2);
+    let first = &manifest.shards[0];
+    assert_eq!(first.path, "input_000001.fasta");
+    assert_eq!((first.records, first.residues, first.bytes), (2, 10, 26));
+    // SHA-256 of ">seq1\nAAAA\n\n>seq3\nDDDDDD\n\n".
+    assert_eq!(
+        first.sha256,
+        "fb286c75e004ee32f24c2eb1b23e1e030466310aad0ac0bdf5d781a6b29ce756"
+    );
+
+    assert_eq!(verify_shards(&output_dir).unwrap(), manifest);
+
+    fs::write(output_dir.join("input_000002.fasta"), ">seq2\nCA\n\n").unwrap();
+    fs::remove_file(output_dir.join("input_000001.fasta")).unwrap();
+    let error = verify_shards(&output_dir).unwrap_err().to_string();
+    assert!(error.contains("2 of 2 shards"));
+    assert!(error.contains("input_000001.fasta: missing"));
+    assert!(error.contains("input_000002.fasta: SHA-256 mismatch"));
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn split_with_few_open_files_matches_unbounded_output() {
+    use rfasta::shard::{
+        read_shard_manifest, split_fasta_file_round_robin_with_options, verify_shards,
+    };
+    use rfasta::write::Compression;
+
+    let dir = common::unique_temp_dir("rfasta_io_split_open_files");
+    let residues = ["A", "C", "D", "E", "F", "G", "H", "K"];
+    let input: String = (0..200)
+        .map(|index| {
+            let sequence = residues[index % residues.len()].repeat(1000 + index * 17);
+            format!(">seq{index}\n{sequence}\n")
+        })
+        .collect();
+    let input = common::write_text_file(&dir, "input.fasta", &input);
+
+    for compression in [
+        Compression::None,
+        Compression::Gzip,
+        Compression::Bgzf,
+        Compression::Zstd,
+    ] {
+        let split = |name: &str, max_open_files: usize| {
+            let output_dir = dir.join(name);
+            split_fasta_file_round_robin_with_options(
+                &input,
+                &output_dir,
+                16,
+                &SplitOptions {
+                    write: WriteOptions {
+                        compression,
+                        ..WriteOptions::default()
+                    },
+                    max_open_files,
+                },
+                false,
+            )
+            .unwrap();
+            let manifest = verify_shards(&output_dir).unwrap();
+            assert_eq!(manifest, read_shard_manifest(&output_dir).unwrap());
+            manifest.shards
+        };
+        let unbounded = split("unbounded", 16);
+        let pooled = split("pooled", 2);
+        assert_eq!(pooled.len(), 16);
+        if compression == Compression::None {
+            assert_eq!(pooled, unbounded);
+        }
+        // Compressed shards closed mid-split hold several members, so compare their records.
+        for (pooled_shard, unbounded_shard) in pooled.iter().zip(&unbounded) {
+            assert_eq!(pooled_shard.path, unbounded_shard.path);
+            assert_eq!(pooled_shard.records, unbounded_shard.records);
+            assert_eq!(pooled_shard.residues, unbounded_shard.residues);
+            let records = |name: &str| {
+                parse_fasta_file(
+                    dir.join(name).join(&pooled_shard.path),
+                    ParseOptions::default(),
+                    false,
+                )
+                .unwrap()
+            };
+            assert_eq!(records("pooled"), records("unbounded"));
+        }
+        fs::remove_dir_all(dir.join("unbounded")).unwrap();
+        fs::remove_dir_all(dir.join("pooled")).unwrap();
+    }
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn split_by_fraction_writes_named_partitions_covering_every_record() {
+    use rfasta::shard::{
+        split_fasta_file_by_fraction, verify_shards, Partition, PartitionAssignment, ShardKey,
+    };
+
+    let dir = common::unique_temp_dir("rfasta_io_split_fraction");
+    let input: String = (0..1000)
+        .map(|index| format!(">seq{index}\nMK{}\n", "A".repeat(index % 7)))
+        .collect();
+    let input = common::write_text_file(&dir, "input.fasta", &input);
+    let partitions = Partition::train_val_test(0.8, 0.1, 0.1);
+
+    let partition = |name: &str, assignment| {
+        let output_dir = dir.join(name);
+        let manifest = split_fasta_file_by_fraction(
+            &input,
+            &output_dir,
+            &partitions,
+            assignment,
+            &SplitOptions::default(),
+            false,
+        )
+        .unwrap();
+        assert_eq!(verify_shards(&output_dir).unwrap(), manifest);
+        let mut headers = Vec::new();
+        for shard in &manifest.shards {
+            let records =
+                parse_fasta_file(output_dir.join(&shard.path), ParseOptions::default(), false)
+                    .unwrap();
+            assert_eq!(records.len(), shard.records);
+            headers.push(
+                records
+                    .into_iter()
+                    .map(|record| record.header)
+                    .collect::<Vec<_>>(),
+            );
+        }
+        headers
+    };
+
+    let seeded = partition("seeded", PartitionAssignment::Random { seed: 7 });
+    assert_eq!(
+        seeded,
+        partition("seeded_again", PartitionAssignment::Random { seed: 7 })
+    );
+    assert_eq!(seeded.iter().map(Vec::len).sum::<usize>(), 1000);
+    assert!((700..900).contains(&seeded[0].len()));
+    assert!(dir.join("seeded").join("input_val.fasta").exists());
+
+    let hashed = partition(
+        "hashed",
+        PartitionAssignment::Hashed {
+            key: ShardKey::Header,
+        },
+    );
+    assert_eq!(hashed.iter().map(Vec::len).sum::<usize>(), 1000);
+    assert!(hashed[0].len() > hashed[1].len() && hashed[0].len() > hashed[2].len());
+
+    let duplicate = split_fasta_file_by_fraction(
+        &input,
+        dir.join("bad"),
+        &[Partition::new("train", 0.5), Partition::new("train", 0.5)],
+        PartitionAssignment::Random { seed: 1 },
+        &SplitOptions::default(),
+        false,
+    );
+    assert!(duplicate
+        .unwrap_err()
+        .to_string()
+        .contains("more than once"));
+
+    let case_duplicate = split_fasta_file_by_fraction(
+        &input,
+        dir.join("bad_case"),
+        &[Partition::new("X", 0.5), Partition::new("x", 0.5)],
+        PartitionAssignment::Random { seed: 1 },
+        &SplitOptions::default(),
+        false,
+    );
+    assert!(case_duplicate
+        .unwrap_err()
+        .to_string()
+        .contains("partition name `x` is used more than once"));
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn split_by_similarity_keeps_related_sequences_in_one_partition() {
+    use rfasta::shard::{
+        read_shard_manifest, split_fasta_file_by_similarity, Partition, ShardStrategy,
+        SimilarityGrouping,
+    };
+
+    let dir = common::unique_temp_dir("rfasta_io_split_similarity");
+    let residues = b"ACDEFGHIKLMNPQRSTVWY";
+    let mut state = 17u64;
+    let mut input = String::new();
+    for family in 0..40 {
+        let base: Vec<u8> = (0..60)
+            .map(|_| {
+                state = state
+                    .wrapping_mul(6364136223846793005)
+                    .wrapping_add(1442695040888963407);
+                residues[(state >> 33) as usize % residues.len()]
+            })
+            .collect();
+        for member in 0..3 {
+            let mut sequence = base.clone();
+            sequence[member * 20] = b'W';
+            input.push_str(&format!(
+                ">fam{family}_{member}\n{}\n",
+                String::from_utf8(sequence).unwrap()
+            ));
+        }
+    }
+    let input = common::write_text_file(&dir, "input.fasta", &input);
+    let output_dir = dir.join("splits");
+
+    let manifest = split_fasta_file_by_similarity(
+        &input,
+        &output_dir,
+        &Partition::train_val_test(0.8, 0.1, 0.1),
+        &SimilarityGrouping::default(),
+        7,
+        &SplitOptions::default(),
+        false,
+    )
+    .unwrap();
+    assert!(matches!(
+        manifest.strategy,
+        ShardStrategy::Grouped { seed: 7, .. }
+    ));
+    assert_eq!(read_shard_manifest(&output_dir).unwrap(), manifest);
+    let records: Vec<usize> = manifest.shards.iter().map(|shard| shard.records).collect();
+    assert_eq!(records, vec![96, 12, 12]);
+
+    let mut family_partition = std::collections::HashMap::new();
+    for (partition, shard) in manifest.shards.iter().enumerate() {
+        for record in
+            parse_fasta_file(output_dir.join(&shard.path), ParseOptions::default(), false).unwrap()
+        {
+            let family = record.header.split('_').next().unwrap().to_string();
+            assert_eq!(
+                *family_partition.entry(family).or_insert(partition),
+                partition
+            );
+        }
+    }
+    assert_eq!(family_partition.len(), 40);
+
+    let leakage = manifest.leakage.unwrap();
+    assert_eq!((leakage.groups, leakage.largest_group), (40, 3));
+    assert!(leakage.max_cross_similarity < 0.5);
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn split_by_similarity_reports_leakage_hidden_by_the_kmer_cap() {
+    use rfasta::shard::{split_fasta_file_by_similarity, Partition, SimilarityGrouping};
+
+    let dir = common::unique_temp_dir("rfasta_io_split_similarity_cap");
+    let residues = b"ACDEFGHIKLMNPQRSTVWY";
+    let base: Vec<u8> = (0..100)
+        .map(|index| residues[(index * 7 + index / 20) % residues.len()])
+        .collect();
+    let mut input = String::new();
+    for variant in 0..40 {
+        let mut sequence = base.clone();
+        sequence[variant * 2] = b'X';
+        input.push_str(&format!(
+            ">v{variant}\n{}\n",
+            String::from_utf8(sequence).unwrap()
+        ));
+    }
+    let input = common::write_text_file(&dir, "input.fasta", &input);
+
+    // With at most three sequences per k-mer, later variants find no neighbors and form their
+    // own groups, so near-identical variants land in both partitions.
+    let grouping = SimilarityGrouping {
+        max_kmer_frequency: 3,
+        ..SimilarityGrouping::default()
+    };
+    let manifest = split_fasta_file_by_similarity(
+        &input,
+        dir.join("splits"),
+        &[Partition::new("a", 0.5), Partition::new("b", 0.5)],
+        &grouping,
+        1,
+        &SplitOptions::default(),
+        false,
+    )
+    .unwrap();
+    let leakage = manifest.leakage.unwrap();
+    assert!(leakage.groups > 1);
+    assert!(leakage.max_cross_similarity > 0.9);
+    assert!(leakage.closest_pair.is_some());
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn split_by_key_writes_one_sanitized_file_per_group() {
+    use rfasta::shard::{split_fasta_file_by_key, GroupKey, GROUP_TABLE_FILE_NAME};
+
+    let dir = common::unique_temp_dir("rfasta_io_split_by_key");
+    let input = common::write_text_file(
+        &dir,
+        "input.fasta",
+        concat!(
+            ">sp|P1|A_HUMAN Protein A OS=Homo sapiens OX=9606 GN=a/b PE=1\nAAAA\n",
+            ">sp|P2|B_MOUSE Protein B OS=Mus musculus OX=10090 GN=a_b PE=1\nCCCC\n",
+            ">sp|P3|C_HUMAN Protein C OS=Homo sapiens OX=9606 PE=1\nDDDD\n",
+            ">custom header without fields\nEEEE\n",
+        ),
+    );
+
+    let by_gene = dir.join("by_gene");
+    let manifest = split_fasta_file_by_key(
+        &input,
+        &by_gene,
+        &GroupKey::UniprotField("GN".to_string()),
+        &SplitOptions::default(),
+        false,
+    )
+    .unwrap();
+    let files: Vec<(&str, &str, usize)> = manifest
+        .shards
+        .iter()
+        .map(|shard| {
+            (
+                shard.group.as_deref().unwrap(),
+                shard.path.as_str(),
+                shard.records,
+            )
+        })
+        .collect();
+    assert_eq!(
+        files,
+        vec![
+            ("a/b", "input_a_b.fasta", 1),
+            ("a_b", "input_a_b_2.fasta", 1),
+            ("", "input_unmatched.fasta", 2),
+        ]
+    );
+    assert_eq!(
+        fs::read_to_string(by_gene.join(GROUP_TABLE_FILE_NAME)).unwrap(),
+        "group\tfile\trecords\na/b\tinput_a_b.fasta\t1\na_b\tinput_a_b_2.fasta\t1\n\tinput_unmatched.fasta\t2\n"
+    );
+
+    let by_organism = dir.join("by_organism");
+    split_fasta_file_by_key(
+        &input,
+        &by_organism,
+        &GroupKey::HeaderRegex(r"OS=(.+?) OX=".to_string()),
+        &SplitOptions::default(),
+        false,
+    )
+    .unwrap();
+    let human = parse_fasta_file(
+        by_organism.join("input_Homo_sapiens.fasta"),
+        ParseOptions::default(),
+        false,
+    )
+    .unwrap();
+    assert_eq!(human.len(), 2);
+    assert!(human[1].header.starts_with("sp|P3|"));
+
+    // Tabs captured into a group name must not add columns to the table.
+    let tabbed = common::write_text_file(&dir, "tabbed.fasta", ">a\tb x\nAAAA\n");
+    let by_prefix = dir.join("by_prefix");
+    split_fasta_file_by_key(
+        &tabbed,
+        &by_prefix,
+        &GroupKey::HeaderRegex(r"^(a\tb)".to_string()),
+        &SplitOptions::default(),
+        false,
+    )
+    .unwrap();
+    assert_eq!(
+        fs::read_to_string(by_prefix.join(GROUP_TABLE_FILE_NAME)).unwrap(),
+        "group\tfile\trecords\na b\ttabbed_a_b.fasta\t1\n"
+    );
+
+    let invalid = split_fasta_file_by_key(
+        &input,
+        dir.join("invalid"),
+        &GroupKey::HeaderRegex("(".to_string()),
+        &SplitOptions::default(),
+        false,
+    );
+    assert!(invalid
+        .unwrap_err()
+        .to_string()
+        .contains("invalid group pattern"));
+
+    let cased = common::write_text_file(
+        &dir,
+        "cased.fasta",
+        ">p1 GN=HBA1\nAAAA\n>p2 GN=hba1\nCCCC\n>p3 GN=HBA1\nDDDD\n",
+    );
+    let by_case = dir.join("by_case");
+    let manifest = split_fasta_file_by_key(
+        &cased,
+        &by_case,
+        &GroupKey::UniprotField("GN".to_string()),
+        &SplitOptions::default(),
+        false,
+    )
+    .unwrap();
+    let files: Vec<(&str, usize)> = manifest
+        .shards
+        .iter()
+        .map(|shard| (shard.path.as_str(), shard.records))
+        .collect();
+    assert_eq!(
+        files,
+        vec![("cased_HBA1.fasta", 2), ("cased_hba1_2.fasta", 1)]
+    );
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn explode_and_gather_round_trip_records() {
+    use rfasta::shard::{explode_fasta_file, gather_fasta_files, list_fasta_files, ExplodeOptions};
+    use rfasta::write::FastaWriter;
+
+    let dir = common::unique_temp_dir("rfasta_io_explode");
+    let mut input: String = (0..25)
+        .map(|index| format!(">sp|P{index:05}|PROT{index}_HUMAN Protein {index}\nMKTAYIAKQR\n"))
+        .collect();
+    input.push_str(">sp|p00000|DUP\nWW\n");
+    let input = common::write_text_file(&dir, "input.fasta", &input);
+    let exploded = dir.join("exploded");
+
+    let written = explode_fasta_file(
+        &input,
+        &exploded,
+        &ExplodeOptions {
+            max_per_dir: Some(5),
+            ..ExplodeOptions::default()
+        },
+        &WriteOptions::default(),
+        false,
+    )
+    .unwrap();
+    assert_eq!(written, 26);
+    let first = parse_fasta_file(
+        exploded.join("0").join("0").join("P00000.fasta"),
+        ParseOptions::default(),
+        false,
+    )
+    .unwrap();
+    assert_eq!(first[0].header, "sp|P00000|PROT0_HUMAN Protein 0");
+    // The 26th record collides with the first accession, ignoring case.
+    assert!(exploded.join("1").join("0").join("p00000_2.fasta").exists());
+    for entry in fs::read_dir(&exploded).unwrap() {
+        for inner in fs::read_dir(entry.unwrap().path()).unwrap() {
+            assert!(fs::read_dir(inner.unwrap().path()).unwrap().count() <= 5);
+        }
+    }
+
+    let files = list_fasta_files(&exploded).unwrap();
+    assert_eq!(files.len(), 26);
+    let mut writer = FastaWriter::new(Vec::new(), &WriteOptions::default()).unwrap();
+    assert_eq!(gather_fasta_files(&files, &mut writer, false).unwrap(), 26);
+    let gathered = parse_fasta_reader(
+        Cursor::new(writer.finish().unwrap()),
+        ParseOptions::default(),
+    )
+    .unwrap();
+    let original = parse_fasta_file(&input, ParseOptions::default(), false).unwrap();
+    assert_eq!(gathered, original);
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn unsplit_restores_round_robin_order_after_shards_are_rewritten() {
+    use rfasta::shard::{unsplit_fasta_files, unsplit_shard_dir};
+    use rfasta::write::FastaWriter;
+
+    let dir = common::unique_temp_dir("rfasta_io_unsplit");
+    let input: String = (0..10)
+        .map(|index| format!(">seq{index}\n{}\n", "ACDEFGHIK".repeat(index + 1)))
+        .collect();
+    let input = common::write_text_file(&dir, "input.fasta", &input);
+    let shards = dir.join("shards");
+    assert_eq!(
+        split_fasta_file_round_robin(&input, &shards, 3, Some(60), false).unwrap(),
+        3
+    );
+    let shard = |number: usize| shards.join(format!("input_{number:06}.fasta"));
+
+    // Re-wrapping a shard changes its bytes but not its records.
+    let rewrapped = parse_fasta_file(shard(2), ParseOptions::default(), false).unwrap();
+    write_fasta_file(
+        &rewrapped,
+        shard(2),
+        WriteOptions {
+            line_length: Some(10),
+            ..WriteOptions::default()
+        },
+        false,
+    )
+    .unwrap();
+    let mut writer = FastaWriter::new(Vec::new(), &WriteOptions::default()).unwrap();
+    assert_eq!(unsplit_shard_dir(&shards, &mut writer, false).unwrap(), 10);
+    let unsplit = parse_fasta_reader(
+        Cursor::new(writer.finish().unwrap()),
+        ParseOptions::default(),
+    )
+    .unwrap();
+    let original = parse_fasta_file(&input, ParseOptions::default(), false).unwrap();
+    assert_eq!(unsplit, original);
+
+    // Shards 1 and 3 hold 4 and 3 records, so swapping them cannot be round-robin.
+    let mut writer = FastaWriter::new(Vec::new(), &WriteOptions::default()).unwrap();
+    let error = unsplit_fasta_files(&[shard(3), shard(2), shard(1)], &mut writer, false)
+        .unwrap_err()
+        .to_string();
+    assert!(error.contains("more records than round-robin assignment allows"));
+
+    // Dropping a shard's last record still fits round-robin, but not the manifest.
+    let first = parse_fasta_file(shard(1), ParseOptions::default(), false).unwrap();
+    write_fasta_file(&first[..3], shard(1), WriteOptions::default(), false).unwrap();
+    let mut writer = FastaWriter::new(Vec::new(), &WriteOptions::default()).unwrap();
+    let error = unsplit_shard_dir(&shards, &mut writer, false)
+        .unwrap_err()
+        .to_string();
+    assert!(error.contains("expected 4, found 3"));
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn file_parts_cover_every_record_exactly_once() {
+    use rfasta::parse::{visit_fasta_file, FilePart};
+
+    let dir = common::unique_temp_dir("rfasta_io_parts");
+    let mut input = String::from("\n");
+    for index in 0..30 {
+        let residues = "MKTAYIAKQRQISFVKSHFSRQ".repeat(index % 4 + 1);
+        input.push_str(&format!(">seq{index} protein {index}\r\n"));
+        for line in residues.as_bytes().chunks(13) {
+            input.push_str(std::str::from_utf8(line).unwrap());
+            input.push('\n');
+        }
+        if index % 5 == 0 {
+            input.push('\n');
+        }
+    }
+    let input = common::write_text_file(&dir, "input.fasta", &input);
+    let original = parse_fasta_file(&input, ParseOptions::default(), false).unwrap();
+
+    // Part counts past the file size leave some parts empty.
+    let size = fs::metadata(&input).unwrap().len() as usize;
+    for count in (1..=64).chain([size - 1, size, size + 7]) {
+        let mut records = Vec::new();
+        for index in 0..count {
+            let options = ParseOptions {
+                part: Some(FilePart::new(index, count).unwrap()),
+                ..ParseOptions::default()
+            };
+            visit_fasta_file(&input, options, false, |record| {
+                records.push(record);
+                Ok(())
+            })
+            .unwrap();
+        }
+        assert_eq!(records, original, "{count} parts");
+    }
+
+    assert!(FilePart::new(4, 4).is_err());
+    let part_of_stream = ParseOptions {
+        part: Some(FilePart::new(0, 2).unwrap()),
+        ..ParseOptions::default()
+    };
+    assert!(parse_fasta_reader(Cursor::new(b">a\nAA\n"), part_of_stream.clone()).is_err());
+    let compressed = dir.join("input.fasta.gz");
+    write_fasta_file(
+        &original,
+        &compressed,
+        WriteOptions {
+            compression: rfasta::write::Compression::Gzip,
+            ..WriteOptions::default()
+        },
+        false,
+    )
+    .unwrap();
+    let error = parse_fasta_file(&compressed, part_of_stream, false)
+        .unwrap_err()
+        .to_string();
+    assert!(error.contains("compressed input"));
+
+    // Line numbers in errors count from the start of the file, not of the part.
+    let mut bad = fs::read(&input).unwrap();
+    let lines = bad.iter().filter(|&&byte| byte == b'\n').count();
+    bad.extend_from_slice(b">bad\xff\nMK\n");
+    fs::write(&input, &bad).unwrap();
+    let last_part = ParseOptions {
+        part: Some(FilePart::new(3, 4).unwrap()),
+        ..ParseOptions::default()
+    };
+    let error = parse_fasta_file(&input, last_part, false)
+        .unwrap_err()
+        .to_string();
+    assert!(error.contains(&format!("line {}", lines + 1)), "{error}");
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn record_refs_match_owned_records() {
+    use rfasta::parse::{visit_fasta_file_refs, visit_fasta_reader_refs, FilePart};
+
+    let dir = common::unique_temp_dir("rfasta_io_refs");
+    let input = "\n>seq1 first\r\nmktay\r\n\r\nIAKQR \n>seq2\t\n\tqisfv\u{a0}\nKSHF\n>seq3\nsrq";
+    let original = parse_fasta_reader(Cursor::new(input), ParseOptions::default()).unwrap();
+    let mut records = Vec::new();
+    let count = visit_fasta_reader_refs(Cursor::new(input), ParseOptions::default(), |record| {
+        records.push(record.to_record()?);
+        Ok(())
+    })
+    .unwrap();
+    assert_eq!(count, 3);
+    assert_eq!(records, original);
+    assert_eq!(original[1], FastaRecord::new("seq2", "QISFVKSHF"));
+
+    let path = common::write_text_file(&dir, "input.fasta", input);
+    let part = ParseOptions {
+        part: Some(FilePart::new(1, 2).unwrap()),
+        ..ParseOptions::default()
+    };
+    let mut headers = Vec::new();
+    visit_fasta_file_refs(&path, part.clone(), false, |record| {
+        headers.push(record.header.to_string());
+        Ok(())
+    })
+    .unwrap();
+    let expected: Vec<String> = parse_fasta_file(&path, part, false)
+        .unwrap()
+        .into_iter()
+        .map(|record| record.header)
+        .collect();
+    assert_eq!(headers, expected);
+
+    // Views skip UTF-8 checks on sequences; owned records still need them.
+    let invalid = b">seq1\nAC\xffGT\n";
+    let error = parse_fasta_reader(Cursor::new(invalid), ParseOptions::default())
+        .unwrap_err()
+        .to_string();
+    assert!(error.contains("not valid UTF-8"), "{error}");
+    visit_fasta_reader_refs(Cursor::new(invalid), ParseOptions::default(), |record| {
+        assert_eq!(record.sequence, b"AC\xffGT");
+        assert!(record.to_record().is_err());
+        Ok(())
+    })
+    .unwrap();
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn fasta_reader_iterates_with_parser_semantics() {
+    use rfasta::parse::{FastaReader, FilePart};
+
+    let input = b">seq1\nac\n\nDE\n>seq2\nTT\n>seq3\nMK\n";
+    let records: Vec<FastaRecord> = FastaReader::new(Cursor::new(input), ParseOptions::default())
+        .unwrap()
+        .collect::<Result<_, _>>()
+        .unwrap();
+    assert_eq!(
+        records,
+        parse_fasta_reader(Cursor::new(input), ParseOptions::default()).unwrap()
+    );
+
+    // A partly consumed reader resumes where it stopped.
+    let mut reader = FastaReader::new(Cursor::new(input), ParseOptions::default()).unwrap();
+    let first = reader.next().unwrap().unwrap();
+    assert_eq!(first, FastaRecord::new("seq1", "ACDE"));
+    assert_eq!(reader.records_read(), 1);
+    let pairs: Vec<(FastaRecord, FastaRecord)> =
+        FastaReader::new(Cursor::new(input), ParseOptions::default())
+            .unwrap()
+            .skip(1)
+            .zip(reader)
+            .map(|(fresh, resumed)| (fresh.unwrap(), resumed.unwrap()))
+            .collect();
+    assert_eq!(pairs.len(), 2);
+    assert!(pairs.iter().all(|(fresh, resumed)| fresh == resumed));
+
+    // Errors keep their line numbers, and the iterator stops after the first one.
+    let duplicate = b">seq1\nAA\n>seq1\nCC\n>seq2\nGG\n";
+    let mut reader = FastaReader::new(Cursor::new(duplicate), ParseOptions::default()).unwrap();
+    assert!(reader.next().unwrap().is_ok());
+    let error = reader.next().unwrap().unwrap_err();
+    assert!(error.to_string().contains("duplicate header"));
+    assert!(reader.next().is_none());
+    let mut reader =
+        FastaReader::new(Cursor::new(b"\n\nAC\n>seq1\nAA\n"), ParseOptions::default()).unwrap();
+    let error = reader.next().unwrap().unwrap_err();
+    assert!(error.to_string().contains("line 3"), "{error}");
+    assert!(reader.next().is_none());
+
+    let dir = common::unique_temp_dir("rfasta_io_reader");
+    let path = common::write_text_file(&dir, "input.fasta", std::str::from_utf8(input).unwrap());
+    let part = ParseOptions {
+        part: Some(FilePart::new(1, 2).unwrap()),
+        ..ParseOptions::default()
+    };
+    assert!(FastaReader::new(Cursor::new(input), part.clone()).is_err());
+    let headers: Vec<String> = FastaReader::open(&path, part.clone())
+        .unwrap()
+        .map(|record| record.unwrap().header)
+        .collect();
+    let expected: Vec<String> = parse_fasta_file(&path, part, false)
+        .unwrap()
+        .into_iter()
+        .map(|record| record.header)
+        .collect();
+    assert_eq!(headers, expected);
+    let error = FastaReader::open(dir.join("missing.fasta"), ParseOptions::default())
+        .err()
+        .unwrap();
+    assert!(error.to_string().contains("missing.fasta"));
+
+    fs::remove_dir_all(dir).unwrap();
+}